@@ -0,0 +1,15 @@
+//! build.rs
+//!
+//! Checks the example's stylesheets at build time, so a typo in one fails
+//! `cargo build` instead of showing up as a blank window.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/15/2018
+
+extern crate shinekit;
+
+fn main() {
+    shinekit::stylesheet::compile::compile_stylesheets(&[
+        ("default", "src/styles/default.json")
+    ]);
+}
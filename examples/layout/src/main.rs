@@ -9,12 +9,13 @@
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
+#[macro_use]
 extern crate shinekit;
 use shinekit::*;
 
 fn main() {
     shinekit::run(vec![
-        StyleSheet::default(include_str!("styles/default.json"))
+        stylesheet!("src/styles/default.json")
     ], App::new("eSports Calendar", View::named("root").subviews(vec![
         View::named("sidebar"),
         View::named("content")
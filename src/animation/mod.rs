@@ -0,0 +1,668 @@
+//! animation/mod.rs
+//!
+//! Moving view properties from one value to another over time, rather than
+//! all at once. Code can start animations itself with `App::animate`, and
+//! stylesheets can ask for property changes to be eased in with a
+//! `transition` entry, same idea as CSS:
+//!
+//! ```json
+//! "sidebar": {
+//!     "width": 200,
+//!     "transition": {"width": "300ms ease-in-out", "backgroundColor": "200ms ease-out"}
+//! }
+//! ```
+//!
+//! Timings are a duration ("200ms", "0.2s"), an optional easing curve
+//! ("linear", "ease", "ease-in", "ease-out", "ease-in-out" or
+//! "cubic-bezier(x1, y1, x2, y2)") and an optional delay, in that order - or
+//! a spring (see `spring`), which works out its own duration. "all" covers
+//! every property that can be animated. Anchors ("top", "left", etc) animate
+//! their offset, as long as they stay pinned to the same thing.
+//!
+//! Nothing here knows about wall clock time: a `Timeline` only moves when it's
+//! stepped. The app steps it every frame while running, and tests can step it
+//! by hand to check exactly where things are at any point.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/18/2018
+
+use serde_json::Value;
+
+use color::Color;
+use layout::{Anchor, Attribute, Constraint};
+use stylesheet::StyleError;
+use view::View;
+
+pub mod spring;
+pub use self::spring::Spring;
+
+/// Every property that can be animated.
+pub const ANIMATABLE_PROPERTIES: [Property; 11] = [
+    Property::BackgroundColor,
+    Property::Width,
+    Property::Height,
+    Property::Offset(Attribute::Top),
+    Property::Offset(Attribute::Leading),
+    Property::Offset(Attribute::Trailing),
+    Property::Offset(Attribute::Bottom),
+    Property::Opacity,
+    Property::CornerRadius,
+    Property::BorderWidth,
+    Property::BorderColor
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Property {
+    BackgroundColor,
+    Width,
+    Height,
+
+    /// The offset of the anchor constraint on an edge, e.g the 8 in
+    /// "sidebar.right + 8".
+    Offset(Attribute),
+
+    Opacity,
+    CornerRadius,
+    BorderWidth,
+    BorderColor
+}
+
+impl Property {
+    /// The name stylesheets use for this property.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Property::BackgroundColor => "backgroundColor",
+            Property::Width => "width",
+            Property::Height => "height",
+            Property::Offset(Attribute::Top) => "top",
+            Property::Offset(Attribute::Leading) => "leading",
+            Property::Offset(Attribute::Trailing) => "trailing",
+            Property::Offset(Attribute::Bottom) => "bottom",
+            Property::Offset(Attribute::Width) => "width",
+            Property::Offset(Attribute::Height) => "height",
+            Property::Opacity => "opacity",
+            Property::CornerRadius => "cornerRadius",
+            Property::BorderWidth => "borderWidth",
+            Property::BorderColor => "borderColor"
+        }
+    }
+
+    /// Like stylesheets, takes "left" and "right" for leading and trailing.
+    pub fn from_name(name: &str) -> Option<Property> {
+        match name {
+            "left" => Some(Property::Offset(Attribute::Leading)),
+            "right" => Some(Property::Offset(Attribute::Trailing)),
+            name => ANIMATABLE_PROPERTIES.iter().find(|property| property.name() == name).cloned()
+        }
+    }
+
+    /// Whether changing this means constraints need activating again.
+    pub fn affects_layout(&self) -> bool {
+        matches!(*self, Property::Width | Property::Height | Property::Offset(_))
+    }
+
+    /// Whether a change from one stylesheet value to another can be animated.
+    /// Anchors can only slide their offset; pinning to something else jumps.
+    pub fn can_transition(&self, from: &Value, to: &Value) -> bool {
+        let anchor = |value: &Value| value.as_str().and_then(|expression| Anchor::parse(expression).ok()).map(|(anchor, _)| anchor);
+
+        match *self {
+            Property::Offset(_) => match (anchor(from), anchor(to)) {
+                (Some(from), Some(to)) => from == to,
+                _ => false
+            },
+
+            _ => true
+        }
+    }
+}
+
+/// A value somewhere along an animation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimatedValue {
+    Number(f64),
+    Color(Color)
+}
+
+impl AnimatedValue {
+    /// Reads the value a stylesheet gives a property.
+    pub fn from_json(property: Property, value: &Value) -> Result<AnimatedValue, StyleError> {
+        match property {
+            Property::BackgroundColor | Property::BorderColor => Color::from_json(value).map(AnimatedValue::Color),
+
+            Property::Offset(_) => match value.as_str() {
+                Some(expression) => Anchor::parse(expression).map(|(_, offset)| AnimatedValue::Number(offset)).map_err(|e| StyleError::new(e.to_string())),
+                None => Err(StyleError::new(format!("expected an anchor like \"sidebar.right + 8\", found {}", value)))
+            },
+
+            _ => value.as_f64().map(AnimatedValue::Number).ok_or_else(|| {
+                StyleError::new(format!("expected a number, found {}", value))
+            })
+        }
+    }
+
+    /// The value `progress` of the way from this one to `to`. Progress can go
+    /// outside 0 - 1 for curves that overshoot.
+    pub fn towards(&self, to: &AnimatedValue, progress: f64) -> AnimatedValue {
+        match (*self, *to) {
+            (AnimatedValue::Number(from), AnimatedValue::Number(to)) => AnimatedValue::Number(from + (to - from) * progress),
+            (AnimatedValue::Color(from), AnimatedValue::Color(to)) => AnimatedValue::Color(to.mix(&from, progress)),
+            (_, to) => to
+        }
+    }
+}
+
+impl From<f64> for AnimatedValue {
+    fn from(value: f64) -> Self {
+        AnimatedValue::Number(value)
+    }
+}
+
+impl From<Color> for AnimatedValue {
+    fn from(color: Color) -> Self {
+        AnimatedValue::Color(color)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+
+    /// The same curves CSS uses; the named easings are all one of these.
+    CubicBezier(f64, f64, f64, f64),
+
+    /// A spring, stretched over however long it takes to settle. See
+    /// `Timing::spring`, which gets the duration right.
+    Spring(Spring)
+}
+
+impl Easing {
+    pub fn ease() -> Self { Easing::CubicBezier(0.25, 0.1, 0.25, 1.) }
+    pub fn ease_in() -> Self { Easing::CubicBezier(0.42, 0., 1., 1.) }
+    pub fn ease_out() -> Self { Easing::CubicBezier(0., 0., 0.58, 1.) }
+    pub fn ease_in_out() -> Self { Easing::CubicBezier(0.42, 0., 0.58, 1.) }
+
+    pub fn parse(easing: &str) -> Result<Easing, StyleError> {
+        match easing {
+            "linear" => Ok(Easing::Linear),
+            "ease" => Ok(Easing::ease()),
+            "ease-in" => Ok(Easing::ease_in()),
+            "ease-out" => Ok(Easing::ease_out()),
+            "ease-in-out" => Ok(Easing::ease_in_out()),
+
+            bezier if bezier.starts_with("cubic-bezier(") && bezier.ends_with(')') => {
+                let points: Vec<f64> = bezier["cubic-bezier(".len()..bezier.len() - 1].split(',')
+                    .filter_map(|point| point.trim().parse().ok()).collect();
+
+                match points.as_slice() {
+                    &[x1, y1, x2, y2] if (0. ..=1.).contains(&x1) && (0. ..=1.).contains(&x2) => Ok(Easing::CubicBezier(x1, y1, x2, y2)),
+                    _ => Err(StyleError::new(format!("expected cubic-bezier(x1, y1, x2, y2) with both x values between 0 and 1, found {}", bezier)))
+                }
+            },
+
+            other => Err(StyleError::new(format!("'{}' isn't an easing, expected linear, ease, ease-in, ease-out, ease-in-out or cubic-bezier(...)", other)))
+        }
+    }
+
+    /// How far along the value is (0 - 1, give or take overshoot) when `time`
+    /// of the way through (0 - 1).
+    pub fn progress(&self, time: f64) -> f64 {
+        match *self {
+            Easing::Linear => time,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let t = solve_bezier(x1, x2, time);
+                bezier(y1, y2, t)
+            },
+
+            Easing::Spring(spring) => spring.position(time * spring.settle_time())
+        }
+    }
+}
+
+/// One dimension of a cubic bezier running from 0 to 1, at `t`.
+fn bezier(p1: f64, p2: f64, t: f64) -> f64 {
+    let u = 1. - t;
+    3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t
+}
+
+/// Finds the `t` at which the curve's x is `x`. Newton's method gets there in a
+/// few steps for most curves, with bisection to fall back on for flat spots.
+fn solve_bezier(x1: f64, x2: f64, x: f64) -> f64 {
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, t) - x;
+        if error.abs() < 1e-7 {
+            return t;
+        }
+
+        let slope = 3. * (1. - t) * (1. - t) * x1 + 6. * (1. - t) * t * (x2 - x1) + 3. * t * t * (1. - x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+
+        t -= error / slope;
+    }
+
+    let (mut low, mut high) = (0., 1.);
+    t = x;
+    for _ in 0..50 {
+        let value = bezier(x1, x2, t);
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+
+        if value < x { low = t; } else { high = t; }
+        t = (low + high) / 2.;
+    }
+
+    t
+}
+
+/// How long an animation takes (in seconds), how it eases, and how long it
+/// waits before starting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub duration: f64,
+    pub easing: Easing,
+    pub delay: f64
+}
+
+impl Timing {
+    pub fn new(duration: f64, easing: Easing) -> Self {
+        Timing { duration, easing, delay: 0. }
+    }
+
+    /// Runs for as long as the spring takes to settle.
+    pub fn spring(spring: Spring) -> Self {
+        Timing { duration: spring.settle_time(), easing: Easing::Spring(spring), delay: 0. }
+    }
+
+    pub fn delay(self, delay: f64) -> Self {
+        Timing { delay, ..self }
+    }
+
+    /// Parses a stylesheet timing, like "200ms ease-out", "0.3s linear 50ms" or
+    /// "spring(170, 26) 50ms". The easing defaults to "ease", like CSS.
+    pub fn parse(timing: &str) -> Result<Timing, StyleError> {
+        // Split on spaces, but not the ones inside cubic-bezier(...) or spring(...).
+        let mut parts: Vec<String> = vec![];
+        let mut depth = 0;
+        for c in timing.trim().chars() {
+            match c {
+                ' ' if depth == 0 => { parts.push(String::new()); continue; },
+                '(' => { depth += 1; },
+                ')' => { depth -= 1; },
+                _ => {}
+            }
+
+            match parts.last_mut() {
+                Some(part) => part.push(c),
+                None => parts.push(c.to_string())
+            }
+        }
+
+        let parts: Vec<&str> = parts.iter().map(|part| part.as_str()).filter(|part| !part.is_empty()).collect();
+        if parts.first().map(|part| part.starts_with("spring(")).unwrap_or(false) {
+            return match parts.as_slice() {
+                [spring] => Ok(Timing::spring(Spring::parse(spring)?)),
+                [spring, delay] => Ok(Timing::spring(Spring::parse(spring)?).delay(seconds(delay)?)),
+                _ => Err(StyleError::new(format!("expected a spring, then an optional delay, found \"{}\"", timing)))
+            };
+        }
+
+        let (duration, easing, delay) = match parts.as_slice() {
+            [duration] => (*duration, None, None),
+            [duration, easing] => (*duration, Some(*easing), None),
+            [duration, easing, delay] => (*duration, Some(*easing), Some(*delay)),
+            _ => { return Err(StyleError::new(format!("expected a duration, then an optional easing and delay, found \"{}\"", timing))); }
+        };
+
+        Ok(Timing {
+            duration: seconds(duration)?,
+            easing: easing.map(Easing::parse).unwrap_or_else(|| Ok(Easing::ease()))?,
+            delay: delay.map(seconds).unwrap_or(Ok(0.))?
+        })
+    }
+}
+
+/// "200ms" or "0.2s", as seconds.
+fn seconds(time: &str) -> Result<f64, StyleError> {
+    let parsed = if let Some(ms) = time.strip_suffix("ms") {
+        ms.parse::<f64>().map(|ms| ms / 1000.).ok()
+    } else {
+        time.strip_suffix('s').and_then(|s| s.parse::<f64>().ok())
+    };
+
+    parsed.filter(|&seconds| seconds >= 0.).ok_or_else(|| {
+        StyleError::new(format!("expected a time like \"200ms\" or \"0.2s\", found \"{}\"", time))
+    })
+}
+
+/// Reads the timing a stylesheet's `transition` entry gives a property, if it
+/// gives one. A timing for the property itself wins over one for "all".
+pub fn transition_for(transition: &Value, property: &str) -> Result<Option<Timing>, StyleError> {
+    let transition = match transition.as_object() {
+        Some(transition) => transition,
+        None if transition.is_null() => { return Ok(None); },
+        None => { return Err(StyleError::new(format!("expected an object of property timings, found {}", transition))); }
+    };
+
+    let (key, timing) = match transition.get(property).map(|timing| (property, timing)).or_else(|| transition.get("all").map(|timing| ("all", timing))) {
+        Some(found) => found,
+        None => { return Ok(None); }
+    };
+
+    match timing.as_str() {
+        Some(timing) => Timing::parse(timing).map(Some).map_err(|e| e.at(key)),
+        None => Err(StyleError::new(format!("expected a timing like \"200ms ease-out\", found {}", timing)).at(key))
+    }
+}
+
+/// Checks a whole `transition` entry: known properties, and timings that parse.
+pub fn check_transition(transition: &Value) -> Result<(), StyleError> {
+    let timings = transition.as_object().ok_or_else(|| {
+        StyleError::new(format!("expected an object of property timings, found {}", transition))
+    })?;
+
+    for property in timings.keys() {
+        if property != "all" && Property::from_name(property).is_none() {
+            let names: Vec<&str> = ANIMATABLE_PROPERTIES.iter().map(|property| property.name()).collect();
+            return Err(StyleError::new(format!("'{}' can't be animated, expected \"all\" or one of {}", property, names.join(", "))).at(property));
+        }
+
+        transition_for(transition, property)?;
+    }
+
+    Ok(())
+}
+
+/// A single property of a single view, moving to a new value.
+pub struct Animation {
+    pub view: String,
+    pub property: Property,
+    pub to: AnimatedValue,
+    pub timing: Timing,
+
+    /// Where the animation starts. If not given, it's whatever the view shows
+    /// when the animation first gets stepped.
+    pub from: Option<AnimatedValue>,
+
+    elapsed: f64,
+
+    /// Velocity (in value units per second) inherited from an animation this
+    /// one replaced, which springs carry on with.
+    velocity: Option<f64>,
+
+    on_complete: Option<Box<dyn FnMut()>>
+}
+
+impl Animation {
+    pub fn new<V: Into<AnimatedValue>>(view: &str, property: Property, to: V, timing: Timing) -> Self {
+        Animation {
+            view: view.into(),
+            property,
+            to: to.into(),
+            timing,
+            from: None,
+            elapsed: 0.,
+            velocity: None,
+            on_complete: None
+        }
+    }
+
+    pub fn from<V: Into<AnimatedValue>>(self, from: V) -> Self {
+        Animation { from: Some(from.into()), ..self }
+    }
+
+    /// Called once the animation reaches its end value - for springs, once
+    /// they've settled. Animations that get replaced by another on the same
+    /// property never complete. This runs while
+    /// the timeline is being stepped, so it shouldn't try to borrow the app.
+    pub fn on_complete<F: FnMut() + 'static>(self, callback: F) -> Self {
+        Animation { on_complete: Some(Box::new(callback)), ..self }
+    }
+
+    /// How far through the animation's time it is (0 - 1), delay aside.
+    fn time(&self) -> f64 {
+        self.time_at(self.elapsed)
+    }
+
+    fn time_at(&self, elapsed: f64) -> f64 {
+        let elapsed = elapsed - self.timing.delay;
+        if self.timing.duration <= 0. {
+            return if elapsed >= 0. { 1. } else { 0. };
+        }
+
+        (elapsed / self.timing.duration).clamp(0., 1.)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.timing.delay + self.timing.duration
+    }
+
+    /// How fast a number is moving right now, in value units per second.
+    fn current_velocity(&self) -> Option<f64> {
+        let (from, to) = match (self.from, self.to) {
+            (Some(AnimatedValue::Number(from)), AnimatedValue::Number(to)) => (from, to),
+            _ => { return None; }
+        };
+
+        if self.elapsed <= self.timing.delay || self.is_finished() {
+            return None;
+        }
+
+        let h = 1e-3;
+        let progress = |elapsed: f64| self.timing.easing.progress(self.time_at(elapsed));
+        Some((to - from) * (progress(self.elapsed) - progress(self.elapsed - h)) / h)
+    }
+
+    /// Hands an inherited velocity to the spring, now that the distance it's
+    /// relative to is known.
+    fn carry_velocity(&mut self) {
+        if let (Some(velocity), Easing::Spring(spring), Some(AnimatedValue::Number(from)), AnimatedValue::Number(to)) = (self.velocity.take(), self.timing.easing, self.from, self.to) {
+            if to != from {
+                self.timing = Timing::spring(spring.with_velocity(velocity / (to - from))).delay(self.timing.delay);
+            }
+        }
+    }
+}
+
+/// Every running animation. Step it to move them along; nothing happens
+/// otherwise.
+#[derive(Default)]
+pub struct Timeline {
+    animations: Vec<Animation>
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Timeline { animations: vec![] }
+    }
+
+    /// Starts an animation, replacing any already running on the same property
+    /// of the same view. The new one picks up from wherever the old one got to,
+    /// and springs keep whatever speed it had, so interrupting one doesn't jerk.
+    pub fn add(&mut self, mut animation: Animation) {
+        if let Easing::Spring(_) = animation.timing.easing {
+            animation.velocity = self.animations.iter()
+                .find(|running| running.view == animation.view && running.property == animation.property)
+                .and_then(|running| running.current_velocity());
+        }
+
+        self.animations.retain(|running| !(running.view == animation.view && running.property == animation.property));
+        self.animations.push(animation);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    /// Whether the given property of the given view is being animated.
+    pub fn is_animating(&self, view: &str, property: Property) -> bool {
+        self.animations.iter().any(|animation| animation.view == view && animation.property == property)
+    }
+
+    /// Moves every animation `dt` seconds along, setting the values on the views
+    /// in `root`'s tree. Returns whether anything that affects layout changed, in
+    /// which case constraints need activating. Animations whose view can't be
+    /// found just finish.
+    pub fn step(&mut self, root: &mut View, dt: f64) -> bool {
+        let mut layout_changed = false;
+        let mut finished = vec![];
+
+        for (index, animation) in self.animations.iter_mut().enumerate() {
+            animation.elapsed += dt;
+
+            let view = match root.find_mut(&animation.view) {
+                Some(view) => view,
+                None => {
+                    finished.push(index);
+                    continue;
+                }
+            };
+
+            let from = match animation.from.or_else(|| view.animated_value(animation.property)) {
+                Some(from) => from,
+                None => animation.to
+            };
+
+            animation.from = Some(from);
+            animation.carry_velocity();
+            if animation.elapsed < animation.timing.delay {
+                continue;
+            }
+
+            let progress = if animation.is_finished() { 1. } else { animation.timing.easing.progress(animation.time()) };
+            view.set_animated_value(animation.property, from.towards(&animation.to, progress));
+            layout_changed = layout_changed || animation.property.affects_layout();
+
+            if animation.is_finished() {
+                finished.push(index);
+            }
+        }
+
+        for index in finished.into_iter().rev() {
+            let mut animation = self.animations.remove(index);
+            if let Some(mut callback) = animation.on_complete.take() {
+                callback();
+            }
+        }
+
+        layout_changed
+    }
+}
+
+impl View {
+    /// What the view currently shows for a property, if it's set at all.
+    pub fn animated_value(&self, property: Property) -> Option<AnimatedValue> {
+        let constant = |attribute: Attribute| self.constant_for(attribute).map(AnimatedValue::Number);
+
+        match property {
+            Property::BackgroundColor => Some(AnimatedValue::Color(self.background_color)),
+            Property::Width => constant(Attribute::Width),
+            Property::Height => constant(Attribute::Height),
+            Property::Offset(attribute) => self.constraints.iter()
+                .find(|constraint| constraint.attribute == attribute && constraint.target.is_some())
+                .map(|constraint| AnimatedValue::Number(constraint.constant)),
+            Property::Opacity => Some(AnimatedValue::Number(self.appearance.opacity)),
+            Property::CornerRadius => Some(AnimatedValue::Number(self.appearance.corner_radius)),
+            Property::BorderWidth => Some(AnimatedValue::Number(self.appearance.border_width)),
+            Property::BorderColor => self.appearance.border_color.map(AnimatedValue::Color)
+        }
+    }
+
+    /// Sets a property straight away. Sizes and offsets only show up once
+    /// constraints are activated.
+    pub fn set_animated_value(&mut self, property: Property, value: AnimatedValue) {
+        match (property, value) {
+            (Property::BackgroundColor, AnimatedValue::Color(color)) => self.set_background_color(&color),
+            (Property::Width, AnimatedValue::Number(width)) => self.set_constraint(Constraint::constant(Attribute::Width, width)),
+            (Property::Height, AnimatedValue::Number(height)) => self.set_constraint(Constraint::constant(Attribute::Height, height)),
+            (Property::Offset(attribute), AnimatedValue::Number(offset)) => {
+                let anchored = self.constraints.iter().find(|constraint| constraint.attribute == attribute && constraint.target.is_some()).cloned();
+                if let Some(mut constraint) = anchored {
+                    constraint.constant = offset;
+                    self.set_constraint(constraint);
+                }
+            },
+            (Property::Opacity, AnimatedValue::Number(opacity)) => self.set_opacity(opacity.clamp(0., 1.)),
+            (Property::CornerRadius, AnimatedValue::Number(radius)) => self.set_corner_radius(radius.max(0.)),
+            (Property::BorderWidth, AnimatedValue::Number(width)) => {
+                let color = self.appearance.border_color.unwrap_or_else(|| Color::rgba(0, 0, 0, 1.));
+                self.set_border(width.max(0.), &color);
+            },
+            (Property::BorderColor, AnimatedValue::Color(color)) => {
+                let width = self.appearance.border_width;
+                self.set_border(width, &color);
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Steps an opacity animation from 0 to 1 over a second, a quarter second at
+    /// a time, returning the opacity after each step.
+    fn opacities(easing: Easing) -> (Vec<f64>, Timeline) {
+        let mut view = View::named("sidebar");
+        let mut timeline = Timeline::new();
+        timeline.add(Animation::new("sidebar", Property::Opacity, 1., Timing::new(1., easing)).from(0.));
+
+        let opacities = (0..4).map(|_| {
+            timeline.step(&mut view, 0.25);
+            view.appearance.opacity
+        }).collect();
+
+        (opacities, timeline)
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-3, "expected {:?} to be close to {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn interpolates_each_easing() {
+        assert_close(&opacities(Easing::Linear).0, &[0.25, 0.5, 0.75, 1.]);
+
+        // Halfway values for the CSS curves.
+        assert_close(&opacities(Easing::ease()).0[1..2], &[0.8024]);
+        assert_close(&opacities(Easing::ease_in()).0[1..2], &[0.3154]);
+        assert_close(&opacities(Easing::ease_out()).0[1..2], &[0.6846]);
+        assert_close(&opacities(Easing::ease_in_out()).0[1..2], &[0.5]);
+
+        let (springy, _) = opacities(Easing::Spring(Spring::default()));
+        assert!(springy[0] > 0. && springy[0] < springy[1]);
+        assert_eq!(springy[3], 1.);
+    }
+
+    #[test]
+    fn cubic_beziers_start_and_end_in_place() {
+        for &easing in &[Easing::ease(), Easing::ease_in_out(), Easing::CubicBezier(0.7, -0.5, 0.2, 1.6)] {
+            assert_close(&[easing.progress(0.), easing.progress(1.)], &[0., 1.]);
+            assert_eq!(opacities(easing).0[3], 1.);
+        }
+    }
+
+    #[test]
+    fn goes_idle_once_done() {
+        let mut view = View::named("sidebar");
+        let mut timeline = Timeline::new();
+        timeline.add(Animation::new("sidebar", Property::Opacity, 0.5, Timing::new(0.5, Easing::Linear).delay(0.25)));
+
+        for _ in 0..2 {
+            timeline.step(&mut view, 0.25);
+            assert!(!timeline.is_idle());
+        }
+
+        timeline.step(&mut view, 0.25);
+        assert!(timeline.is_idle());
+        assert_eq!(view.appearance.opacity, 0.5);
+        assert!(opacities(Easing::Linear).1.is_idle());
+    }
+}
@@ -0,0 +1,222 @@
+//! animation/spring.rs
+//!
+//! Springs, for motion that should feel physical rather than timed. A spring
+//! is described the way most physics-y animation APIs do it: stiffness,
+//! damping, mass, and the velocity it starts out with. Stylesheets write one
+//! in place of a duration and easing, optionally followed by a delay:
+//!
+//! ```json
+//! "transition": {"width": "spring(170, 26)", "opacity": "spring(300, 20, 1, 0) 50ms"}
+//! ```
+//!
+//! Position is worked out from the closed-form solution rather than by
+//! integrating frame by frame, so where a spring is at any moment doesn't
+//! depend on how often it's been stepped. A spring "settles" once it's close
+//! enough to its target, and slow enough, that nobody could tell it's still
+//! moving; that's how long the animation runs for.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/19/2018
+
+use stylesheet::StyleError;
+
+/// How close (as a fraction of the distance travelled) counts as there.
+const REST_DISTANCE: f64 = 0.001;
+
+/// How slow (in distances per second) counts as stopped.
+const REST_VELOCITY: f64 = 0.01;
+
+/// Springs that take longer than this (in seconds) to settle are cut off.
+const MAX_SETTLE_TIME: f64 = 10.;
+
+/// Set one up with `new` and the `with_` methods. The fields stay private so
+/// the settle time (worked out as it's built) can't go stale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spring {
+    stiffness: f64,
+    damping: f64,
+    mass: f64,
+
+    /// Velocity at the start, in distances per second: 1 means it starts out
+    /// moving at the whole distance per second towards the target.
+    velocity: f64,
+
+    /// Worked out up front, since easing needs it every frame.
+    settle_time: f64
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Spring { stiffness: 170., damping: 26., mass: 1., velocity: 0., settle_time: 0. }.settled()
+    }
+}
+
+impl Spring {
+    /// A spring with a mass of 1, starting at rest. Stiffness has to be above 0
+    /// and damping 0 or more, or it'd never get anywhere.
+    pub fn new(stiffness: f64, damping: f64) -> Result<Self, StyleError> {
+        Spring { stiffness, damping, ..Spring::default() }.checked()
+    }
+
+    pub fn with_mass(self, mass: f64) -> Result<Self, StyleError> {
+        Spring { mass, ..self }.checked()
+    }
+
+    pub fn with_velocity(self, velocity: f64) -> Self {
+        Spring { velocity, ..self }.settled()
+    }
+
+    pub fn stiffness(&self) -> f64 { self.stiffness }
+    pub fn damping(&self) -> f64 { self.damping }
+    pub fn mass(&self) -> f64 { self.mass }
+    pub fn velocity(&self) -> f64 { self.velocity }
+
+    /// Parses "spring(stiffness, damping)", with mass and velocity optionally
+    /// following.
+    pub fn parse(spring: &str) -> Result<Spring, StyleError> {
+        let error = || StyleError::new(format!("expected spring(stiffness, damping, mass, velocity) with the last two optional, found {}", spring));
+        if !spring.starts_with("spring(") || !spring.ends_with(')') {
+            return Err(error());
+        }
+
+        let values: Option<Vec<f64>> = spring["spring(".len()..spring.len() - 1].split(',').map(|value| value.trim().parse().ok()).collect();
+        let (stiffness, damping, mass, velocity) = match values.as_deref() {
+            Some(&[stiffness, damping]) => (stiffness, damping, 1., 0.),
+            Some(&[stiffness, damping, mass]) => (stiffness, damping, mass, 0.),
+            Some(&[stiffness, damping, mass, velocity]) => (stiffness, damping, mass, velocity),
+            _ => { return Err(error()); }
+        };
+
+        Spring { stiffness, damping, mass, velocity, settle_time: 0. }.checked()
+    }
+
+    /// Makes sure the spring can actually move, then works out its settle time.
+    fn checked(self) -> Result<Self, StyleError> {
+        // Written so NaN fails too.
+        if !(self.stiffness > 0. && self.mass > 0. && self.damping >= 0.) {
+            return Err(StyleError::new(format!(
+                "a spring needs a stiffness and mass above 0, and damping of 0 or more (found stiffness {}, damping {}, mass {})",
+                self.stiffness, self.damping, self.mass
+            )));
+        }
+
+        Ok(self.settled())
+    }
+
+    /// How far along (0 at the start, 1 at the target) the spring is `time`
+    /// seconds in. Underdamped springs overshoot past 1 on the way.
+    pub fn position(&self, time: f64) -> f64 {
+        1. - self.displacement(time)
+    }
+
+    /// How long it takes to settle, in seconds.
+    pub fn settle_time(&self) -> f64 {
+        self.settle_time
+    }
+
+    /// Works out `settle_time` for the spring as it now stands.
+    fn settled(self) -> Self {
+        let step = 1. / 120.;
+        let mut time = 0.;
+        while time < MAX_SETTLE_TIME {
+            let speed = (self.displacement(time + 1e-4) - self.displacement(time)) / 1e-4;
+            if self.displacement(time).abs() < REST_DISTANCE && speed.abs() < REST_VELOCITY {
+                return Spring { settle_time: time, ..self };
+            }
+
+            time += step;
+        }
+
+        Spring { settle_time: MAX_SETTLE_TIME, ..self }
+    }
+
+    /// Distance left to the target, as a fraction of the whole: a damped
+    /// harmonic oscillator starting at 1 and heading for 0.
+    fn displacement(&self, time: f64) -> f64 {
+        let natural = (self.stiffness / self.mass).sqrt();
+        let ratio = self.damping / (2. * (self.stiffness * self.mass).sqrt());
+
+        // Moving towards the target means the displacement is shrinking.
+        let initial_velocity = -self.velocity;
+
+        if ratio < 1. {
+            let damped = natural * (1. - ratio * ratio).sqrt();
+            let b = (ratio * natural + initial_velocity) / damped;
+            (-ratio * natural * time).exp() * ((damped * time).cos() + b * (damped * time).sin())
+        } else if ratio == 1. {
+            let b = initial_velocity + natural;
+            (-natural * time).exp() * (1. + b * time)
+        } else {
+            let root = (ratio * ratio - 1.).sqrt();
+            let (r1, r2) = (-natural * (ratio - root), -natural * (ratio + root));
+            let c2 = (initial_velocity - r1) / (r2 - r1);
+            (1. - c2) * (r1 * time).exp() + c2 * (r2 * time).exp()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The furthest past the target a spring gets, as a fraction of the distance.
+    fn overshoot(spring: &Spring) -> f64 {
+        (0..1200).map(|frame| spring.position(frame as f64 / 120.) - 1.).fold(0., f64::max)
+    }
+
+    #[test]
+    fn settles_where_it_stops_moving() {
+        let spring = Spring::default();
+        let settle = spring.settle_time();
+        assert!(settle > 0.3 && settle < 1., "settled after {}", settle);
+        assert!((spring.position(settle) - 1.).abs() < REST_DISTANCE);
+        assert!((spring.position(settle - 0.1) - 1.).abs() >= REST_DISTANCE);
+
+        // Stiffer springs get there sooner; springs that never settle get cut off.
+        assert!(Spring::new(600., 49.).unwrap().settle_time() < settle);
+        assert_eq!(Spring::new(170., 0.).unwrap().settle_time(), MAX_SETTLE_TIME);
+    }
+
+    #[test]
+    fn keeps_its_settle_time_up_to_date() {
+        let heavy = Spring::new(170., 26.).unwrap().with_mass(4.).unwrap();
+        assert_eq!(heavy.mass(), 4.);
+        assert_eq!(heavy.settle_time(), Spring::parse("spring(170, 26, 4)").unwrap().settle_time());
+        assert!(heavy.settle_time() != Spring::default().settle_time());
+
+        let pushed = Spring::default().with_velocity(5.);
+        assert_eq!(pushed.velocity(), 5.);
+        assert_eq!(pushed.settle_time(), Spring::parse("spring(170, 26, 1, 5)").unwrap().settle_time());
+    }
+
+    #[test]
+    fn only_underdamped_springs_overshoot() {
+        assert!(overshoot(&Spring::new(170., 10.).unwrap()) > 0.2);
+        assert_eq!(overshoot(&Spring::new(100., 40.).unwrap()), 0.);
+
+        // Starting out fast carries even a well damped spring past the target.
+        assert!(overshoot(&Spring::new(100., 20.).unwrap().with_velocity(20.)) > 0.);
+    }
+
+    #[test]
+    fn rejects_springs_that_cant_move() {
+        assert!(Spring::new(0., 26.).is_err());
+        assert!(Spring::new(170., -1.).is_err());
+        assert!(Spring::new(f64::NAN, 26.).is_err());
+        assert!(Spring::default().with_mass(0.).is_err());
+    }
+
+    #[test]
+    fn parses_springs() {
+        assert_eq!(Spring::parse("spring(170, 26)"), Spring::new(170., 26.));
+        assert_eq!(Spring::parse("spring( 300,20 , 2 , 1 )").map(|spring| (spring.stiffness(), spring.damping(), spring.mass(), spring.velocity())), Ok((300., 20., 2., 1.)));
+
+        for bad in &["spring(170)", "spring(170, 26, 1, 0, 5)", "spring(a, b)", "spring(170, 26", "bounce(170, 26)"] {
+            let error = Spring::parse(bad).unwrap_err();
+            assert!(error.message.starts_with("expected spring(stiffness, damping, mass, velocity)"), "{}: {}", bad, error);
+        }
+
+        assert!(Spring::parse("spring(170, 26, 0)").unwrap_err().message.starts_with("a spring needs a stiffness and mass above 0"));
+        assert!(Spring::parse("spring(-5, 26)").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! appearance.rs
+//!
+//! The layer-ish parts of how a view looks, beyond its background color:
+//! borders, rounded corners, opacity and drop shadows. Stylesheets set these
+//! with `borderWidth`, `borderColor`, `cornerRadius`, `opacity` (0 - 1) and
+//! `shadow`, which is either "none" or an object like
+//! `{"color": "rgba(0, 0, 0, 0.3)", "offsetX": 0, "offsetY": 2, "blur": 4}`.
+//!
+//! Like typography, the whole thing is kept on the View and handed to the
+//! backend in one go, since that's how it ends up on the layer anyway.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/13/2018
+
+use serde_json::Value;
+
+use backend::{Backend, Platform};
+use color::Color;
+use stylesheet::{StyleError, optional_number};
+use view::View;
+
+/// Every appearance property a stylesheet can set.
+pub const APPEARANCE_PROPERTIES: [&str; 5] = ["borderWidth", "borderColor", "cornerRadius", "opacity", "shadow"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub color: Color,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    pub blur: f64
+}
+
+impl Shadow {
+    /// Offsets are in points, with positive y going down (same as CSS).
+    pub fn new(color: Color, offset_x: f64, offset_y: f64, blur: f64) -> Self {
+        Shadow { color, offset_x, offset_y, blur }
+    }
+
+    /// Reads a shadow from a stylesheet. "none" (or null) means no shadow.
+    pub fn from_json(value: &Value) -> Result<Option<Shadow>, StyleError> {
+        match *value {
+            Value::Null => Ok(None),
+            Value::String(ref none) if none == "none" => Ok(None),
+
+            Value::Object(_) => {
+                let color = match value.get("color") {
+                    Some(color) => Color::from_json(color).map_err(|e| e.at("color"))?,
+                    None => Color::rgba(0, 0, 0, 0.33)
+                };
+
+                let blur = optional_number(value, "blur")?.unwrap_or(0.);
+                if blur < 0. {
+                    return Err(StyleError::new(format!("expected a blur of 0 or more, found {}", blur)).at("blur"));
+                }
+
+                Ok(Some(Shadow {
+                    color,
+                    offset_x: optional_number(value, "offsetX")?.unwrap_or(0.),
+                    offset_y: optional_number(value, "offsetY")?.unwrap_or(0.),
+                    blur
+                }))
+            },
+
+            _ => Err(StyleError::new(format!("expected \"none\" or an object with color, offsetX, offsetY and blur, found {}", value)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Appearance {
+    pub border_width: f64,
+    pub border_color: Option<Color>,
+    pub corner_radius: f64,
+    pub opacity: f64,
+    pub shadow: Option<Shadow>
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Appearance {
+            border_width: 0.,
+            border_color: None,
+            corner_radius: 0.,
+            opacity: 1.,
+            shadow: None
+        }
+    }
+}
+
+impl Appearance {
+    /// Updates this from whichever appearance properties a style object sets.
+    pub fn apply_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if let Some(width) = optional_number(style, "borderWidth")? {
+            self.border_width = non_negative(width).map_err(|e| e.at("borderWidth"))?;
+        }
+
+        if let Some(color) = style.get("borderColor").filter(|color| !color.is_null()) {
+            self.border_color = Some(Color::from_json(color).map_err(|e| e.at("borderColor"))?);
+        }
+
+        if let Some(radius) = optional_number(style, "cornerRadius")? {
+            self.corner_radius = non_negative(radius).map_err(|e| e.at("cornerRadius"))?;
+        }
+
+        if let Some(opacity) = optional_number(style, "opacity")? {
+            if !(0. ..=1.).contains(&opacity) {
+                return Err(StyleError::new(format!("expected an opacity between 0 and 1, found {}", opacity)).at("opacity"));
+            }
+
+            self.opacity = opacity;
+        }
+
+        if let Some(shadow) = style.get("shadow") {
+            self.shadow = Shadow::from_json(shadow).map_err(|e| e.at("shadow"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Puts a single property back to its default.
+    pub fn reset(&mut self, property: &str) {
+        let default = Appearance::default();
+        match property {
+            "borderWidth" => { self.border_width = default.border_width; },
+            "borderColor" => { self.border_color = default.border_color; },
+            "cornerRadius" => { self.corner_radius = default.corner_radius; },
+            "opacity" => { self.opacity = default.opacity; },
+            "shadow" => { self.shadow = default.shadow; },
+            _ => {}
+        }
+    }
+}
+
+fn non_negative(value: f64) -> Result<f64, StyleError> {
+    if value < 0. {
+        return Err(StyleError::new(format!("expected 0 or more, found {}", value)));
+    }
+
+    Ok(value)
+}
+
+impl View {
+    pub fn set_appearance(&mut self, appearance: Appearance) {
+        Platform::set_appearance(&self.backing_node, &appearance);
+        self.appearance = appearance;
+    }
+
+    pub fn set_border(&mut self, width: f64, color: &Color) {
+        let appearance = Appearance { border_width: width, border_color: Some(*color), ..self.appearance.clone() };
+        self.set_appearance(appearance);
+    }
+
+    pub fn set_corner_radius(&mut self, radius: f64) {
+        let appearance = Appearance { corner_radius: radius, ..self.appearance.clone() };
+        self.set_appearance(appearance);
+    }
+
+    pub fn set_opacity(&mut self, opacity: f64) {
+        let appearance = Appearance { opacity, ..self.appearance.clone() };
+        self.set_appearance(appearance);
+    }
+
+    pub fn set_shadow(&mut self, shadow: Option<Shadow>) {
+        let appearance = Appearance { shadow, ..self.appearance.clone() };
+        self.set_appearance(appearance);
+    }
+
+    /// The appearance half of `apply_styles`.
+    pub fn apply_appearance_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if APPEARANCE_PROPERTIES.iter().any(|property| style.get(property).is_some()) {
+            let mut appearance = self.appearance.clone();
+            appearance.apply_style(style)?;
+            self.set_appearance(appearance);
+        }
+
+        Ok(())
+    }
+}
@@ -1,30 +1,45 @@
 //! application.rs
 //!
-//! Wraps application lifetime pieces across platforms. A lot of
-//! unsafe code in here since it has to interact with a lot of
-//! outside actors. With that said, if this crashes... there's bigger
-//! problems with the system, lol.
+//! Wraps application lifetime pieces across platforms. The platform
+//! specific (read: unsafe) parts live in the backend; this just
+//! holds onto the window and drives styling, including swapping
+//! themes out while the app is running.
 //!
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use cocoa::base::{id, nil};
-use cocoa::foundation::{NSRect, NSPoint, NSSize, NSAutoreleasePool};
-use cocoa::appkit::{
-    NSApp, NSApplication, NSApplicationActivationPolicyRegular,
-    NSRunningApplication, NSApplicationActivateIgnoringOtherApps
-};
-
+use std::path::{Path, PathBuf};
 use serde_json::{Map, Value};
 
+use animation::{Animation, Timeline};
+use backend::{AppHandle, Backend, Platform};
 use color::Color;
+use layout::{Rect, place_children};
+use stylesheet::{StyleError, StyleSource, load_sources, optional_number};
+use stylesheet::inspect::{Provenance, report, trace};
+use stylesheet::media::resolve_media;
+use stylesheet::schema::validate_styles;
 use window::Window;
 use view::View;
 
 pub struct App {
-    pub app: id,
+    pub app: AppHandle,
     pub window: Window,
+    pub styles: Map<String, Value>,
+    pub active_theme: String,
+
+    /// The stylesheets `styles` was built from, as they were loaded. Only used to
+    /// answer `inspect`.
+    pub sources: Vec<StyleSource>,
+
+    /// Where "@import"s in stylesheets that weren't read from a file (e.g, via
+    /// `include_str!`) are relative to.
+    pub asset_root: Option<PathBuf>,
+
+    /// Animations in flight, from `animate` and stylesheet transitions.
+    pub timeline: Timeline
 }
+
 /*
 pub trait Delegate {
     // AppDelegate lifecycle callbacks
@@ -45,37 +60,491 @@ pub trait Delegate {
 impl App {
     pub fn new(title: &str, view: View) -> Self {
         App {
-            app: unsafe {
-                let _pool = NSAutoreleasePool::new(nil);
-                let app = NSApp();
-                app.setActivationPolicy_(NSApplicationActivationPolicyRegular);            
-                app
-            },
-
-            window: Window::new(view, title, 0, 0, 0, 0)
+            app: Platform::create_app(),
+            window: Window::new(view, title, 0, 0, 0, 0),
+            styles: Map::new(),
+            active_theme: "default".into(),
+            sources: vec![],
+            asset_root: None,
+            timeline: Timeline::new()
+        }
+    }
+
+    /// Sets the directory embedded stylesheets import files relative to.
+    pub fn asset_root<P: AsRef<Path>>(self, path: P) -> Self {
+        App { asset_root: Some(path.as_ref().to_path_buf()), ..self }
+    }
+
+    /// Sets the theme to apply when the app launches. Defaults to "default".
+    pub fn theme(self, name: &str) -> Self {
+        App { active_theme: name.into(), ..self }
+    }
+
+    pub fn available_themes(&self) -> Vec<&str> {
+        self.styles.keys().map(|name| name.as_str()).collect()
+    }
+
+    fn theme_styles(&self, name: &str) -> Result<Map<String, Value>, StyleError> {
+        match self.styles.get(name).and_then(|theme| theme.as_object()) {
+            Some(theme) => Ok(theme.clone()),
+            None => Err(StyleError::new(format!("no theme named '{}' has been loaded", name)).in_theme(name))
+        }
+    }
+
+    /// Applies the active theme from the loaded styles, sizing the window along
+    /// the way. This is what happens at launch.
+    pub fn apply_theme(&mut self) -> Result<(), StyleError> {
+        let name = self.active_theme.clone();
+        let styles = self.theme_styles(&name)?;
+        self.apply_styles(&styles).map_err(|e| e.in_theme(&name))
+    }
+
+    /// Switches to another loaded theme while running. The existing window and
+    /// view tree move over to it in place, so properties the new theme doesn't set
+    /// go back to their defaults. The window keeps whatever size it has.
+    pub fn set_theme(&mut self, name: &str) -> Result<(), StyleError> {
+        let current = self.theme_styles(&self.active_theme).unwrap_or_default();
+        let old = self.at_current_size(&current);
+        let new = self.at_current_size(&self.theme_styles(name)?);
+        self.transition(&old, &new).map_err(|e| e.in_theme(name))?;
+        self.active_theme = name.into();
+        Ok(())
+    }
+
+    /// Swaps in freshly loaded stylesheets, re-applying only what changed in the
+    /// active theme. If anything fails to load or apply, the previous styles are
+    /// kept around, so the next reload diffs against the last set that fully worked.
+    pub fn reload_styles(&mut self, sources: Vec<StyleSource>) -> Result<(), StyleError> {
+        let styles = load_sources(&sources)?;
+        let name = self.active_theme.clone();
+        let old = self.theme_styles(&name).unwrap_or_default();
+        let new = match styles.get(&name).and_then(|theme| theme.as_object()) {
+            Some(theme) => theme.clone(),
+            None => { return Err(StyleError::new(format!("no theme named '{}' has been loaded", name)).in_theme(&name)); }
+        };
+
+        self.apply_changed_styles(&old, &new).map_err(|e| e.in_theme(&name))?;
+        self.styles = styles;
+        self.sources = sources;
+        Ok(())
+    }
+
+    /// Called when the user resizes the window. If that crosses a breakpoint (see
+    /// `stylesheet::media`), the views it affects get restyled.
+    pub fn window_did_resize(&mut self, frame: Rect) -> Result<(), StyleError> {
+        let name = self.active_theme.clone();
+        let styles = self.theme_styles(&name).unwrap_or_default();
+        let old = self.at_current_size(&styles);
+        self.window.frame = frame;
+        let new = self.at_current_size(&styles);
+
+        if old != new {
+            self.transition(&old, &new).map_err(|e| e.in_theme(&name))?;
+        } else {
+            // Flex containers may still have changed size.
+            self.layout()?;
+        }
+
+        Ok(())
+    }
+
+    /// Every property in the named view's computed style (for the active theme, at
+    /// the window's current size), along with the theme, file and rule it came
+    /// from. Returns None if there's no view by that name.
+    pub fn inspect(&self, view: &str) -> Option<Vec<Provenance>> {
+        let path = self.window.content_view.element_path(view)?;
+        let theme = self.theme_styles(&self.active_theme).unwrap_or_default();
+        let effective = self.at_current_size(&theme);
+        let size = (self.window.frame.width, self.window.frame.height);
+        Some(trace(&self.sources, &self.active_theme, &theme, &effective, size, &path))
+    }
+
+    /// `inspect`, formatted for reading. Handy to eprintln! while debugging.
+    pub fn style_report(&self, view: &str) -> Option<String> {
+        self.inspect(view).map(|trace| report(view, &trace))
+    }
+
+    /// Checks the loaded styles against the schema and this app's view tree:
+    /// unknown properties, values of the wrong type, Label-only properties on
+    /// rules that match no Labels, and view names that don't exist. None of these
+    /// stop styles from applying, so they're worth a look when something doesn't.
+    pub fn validate_styles(&self) -> Vec<StyleError> {
+        validate_styles(&self.styles, Some(&self.window.content_view))
+    }
+
+    /// Re-applies the active theme and lays everything out again. Call this after
+    /// changing the view tree or classes, so new views pick up their styles, views
+    /// drop what no longer matches them, and containers make room.
+    pub fn relayout(&mut self) -> Result<(), StyleError> {
+        let name = self.active_theme.clone();
+        let styles = self.at_current_size(&self.theme_styles(&name).unwrap_or_default());
+        for animation in self.window.content_view.restyle(&styles).map_err(|e| e.in_theme(&name))? {
+            self.timeline.add(animation);
+        }
+
+        self.layout()
+    }
+
+    /// Adds a view to the end of the named stack (or any other view), then styles
+    /// it and lays everything out again so it shows up in place.
+    pub fn add_arranged_subview(&mut self, stack: &str, view: View) -> Result<(), StyleError> {
+        self.find_stack(stack)?.add_arranged_subview(view);
+        self.relayout()
+    }
+
+    /// Like `add_arranged_subview`, but puts it at `index` (or at the end, if
+    /// that's past it).
+    pub fn insert_arranged_subview(&mut self, stack: &str, index: usize, view: View) -> Result<(), StyleError> {
+        self.find_stack(stack)?.insert_arranged_subview(index, view);
+        self.relayout()
+    }
+
+    /// Takes the named view out of the named stack and closes the gap, handing the
+    /// view back. Returns None if the stack has no direct subview by that name.
+    pub fn remove_arranged_subview(&mut self, stack: &str, name: &str) -> Result<Option<View>, StyleError> {
+        let removed = self.find_stack(stack)?.remove_arranged_subview(name);
+        if removed.is_some() {
+            self.relayout()?;
+        }
+
+        Ok(removed)
+    }
+
+    fn find_stack(&mut self, stack: &str) -> Result<&mut View, StyleError> {
+        self.window.content_view.find_mut(stack).ok_or_else(|| StyleError::new(format!("no view named '{}'", stack)))
+    }
+
+    /// Starts animating a view property (see `animation`).
+    pub fn animate(&mut self, animation: Animation) {
+        self.timeline.add(animation);
+    }
+
+    /// Moves running animations `dt` seconds along. While the app is running this
+    /// happens every frame; tests can call it directly to step through animations.
+    pub fn tick(&mut self, dt: f64) -> Result<(), StyleError> {
+        if self.timeline.is_idle() {
+            return Ok(());
+        }
+
+        if self.timeline.step(&mut self.window.content_view, dt) {
+            self.layout()?;
         }
+
+        Ok(())
     }
 
     pub fn run(&self) {
-        unsafe {
-            let current_app = NSRunningApplication::currentApplication(nil);
-            current_app.activateWithOptions_(NSApplicationActivateIgnoringOtherApps);
-            self.window.make_key();
-            self.app.run();
+        self.window.make_key();
+        Platform::run_app(&self.app);
+    }
+
+    pub fn apply_styles(&mut self, styles: &Map<String, Value>) -> Result<(), StyleError> {
+        let window = styles.get("window").unwrap_or(&Value::Null);
+        let width = optional_number(window, "defaultWidth").map_err(|e| e.at("window"))?;
+        let height = optional_number(window, "defaultHeight").map_err(|e| e.at("window"))?;
+        if let (Some(width), Some(height)) = (width, height) {
+            self.window.set_frame(Rect::new(0., 0., width, height));
+        }
+
+        let styles = self.at_current_size(styles);
+        self.restyle(&styles)
+    }
+
+    /// The styles in effect for the window's current size, with any matching
+    /// media blocks merged in.
+    fn at_current_size(&self, styles: &Map<String, Value>) -> Map<String, Value> {
+        resolve_media(styles, self.window.frame.width, self.window.frame.height)
+    }
+
+    fn apply_changed_styles(&mut self, old: &Map<String, Value>, new: &Map<String, Value>) -> Result<(), StyleError> {
+        let (old_window, new_window) = (old.get("window").unwrap_or(&Value::Null), new.get("window").unwrap_or(&Value::Null));
+
+        let width = optional_number(new_window, "defaultWidth").map_err(|e| e.at("window"))?;
+        let height = optional_number(new_window, "defaultHeight").map_err(|e| e.at("window"))?;
+        let resized = old_window.get("defaultWidth") != new_window.get("defaultWidth") ||
+            old_window.get("defaultHeight") != new_window.get("defaultHeight");
+
+        let old = self.at_current_size(old);
+        if let (true, Some(width), Some(height)) = (resized, width, height) {
+            self.window.set_frame(Rect::new(0., 0., width, height));
         }
+
+        let new = self.at_current_size(new);
+        self.transition(&old, &new)
+    }
+
+    /// Moves the window and view tree from one set of (size-resolved) styles to
+    /// another, only touching what differs.
+    fn transition(&mut self, old: &Map<String, Value>, new: &Map<String, Value>) -> Result<(), StyleError> {
+        let (old_window, new_window) = (old.get("window").unwrap_or(&Value::Null), new.get("window").unwrap_or(&Value::Null));
+        if let Some(color) = new_window.get("backgroundColor").filter(|&color| old_window.get("backgroundColor") != Some(color)) {
+            let bg_color = Color::from_json(color).map_err(|e| e.at("backgroundColor").at("window"))?;
+            self.window.set_background_color(bg_color);
+        }
+
+        for animation in self.window.content_view.apply_changed_styles(old, new)? {
+            self.timeline.add(animation);
+        }
+
+        self.layout()
+    }
+
+    fn restyle(&mut self, styles: &Map<String, Value>) -> Result<(), StyleError> {
+        if let Some(color) = styles.get("window").and_then(|window| window.get("backgroundColor")) {
+            let bg_color = Color::from_json(color).map_err(|e| e.at("backgroundColor").at("window"))?;
+            self.window.set_background_color(bg_color);
+        }
+
+        self.window.content_view.apply_styles(styles)?;
+        self.layout()
     }
 
-    pub fn apply_styles(&mut self, styles: &mut Map<String, Value>) {
-        let bg_color = Color::from_json(&styles["window"]["backgroundColor"]);
-        self.window.set_background_color(bg_color);
-        
-        let width = &styles["window"]["defaultWidth"].as_f64().unwrap();
-        let height = &styles["window"]["defaultHeight"].as_f64().unwrap();
-        let rect = NSRect::new(NSPoint::new(0., 0.), NSSize::new(*width, *height));
-        self.window.set_frame(rect);
+    /// Places the children of flex containers for the window's current size, then
+    /// activates constraints across the whole tree.
+    fn layout(&mut self) -> Result<(), StyleError> {
+        let frame = self.window.frame;
+        place_children(&mut self.window.content_view, frame.width, frame.height).map_err(|e| {
+            StyleError::new(format!("could not lay out views, {}", e))
+        })?;
 
-        self.window.content_view.apply_styles(styles);
+        self.window.content_view.activate_constraints();
+        Ok(())
     }
 }
 
 //impl Delegate for App {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use animation::{AnimatedValue, Property};
+    use layout::{Attribute, Constraint, Display, compute_frames};
+    use layout::flex::{Align, Direction};
+    use stack::{HStack, VStack};
+    use stylesheet::{load_sources, StyleSheet, StyleSource};
+
+    fn app() -> App {
+        let mut app = App::new("test", View::named("root").subviews(vec![View::named("sidebar")]));
+        app.sources = vec![
+            StyleSource::inline(StyleSheet::default(r#"{
+                "window": {"backgroundColor": {"r": 255, "g": 255, "b": 255}, "defaultWidth": 800, "defaultHeight": 600},
+                "root": {"backgroundColor": {"r": 255, "g": 255, "b": 255}},
+                "sidebar": {"backgroundColor": {"r": 255, "g": 0, "b": 0}, "width": 100},
+                "@media (max-width: 500)": {"sidebar": {"width": 0}}
+            }"#).unwrap()),
+            StyleSource::inline(StyleSheet::theme("dark", r#"{
+                "window": {"backgroundColor": {"r": 0, "g": 0, "b": 0}},
+                "sidebar": {"backgroundColor": {"r": 5, "g": 5, "b": 5}, "top": "root.top"}
+            }"#).unwrap()),
+            StyleSource::inline(StyleSheet::theme("broken", r#"{
+                "sidebar": {"width": "wide", "backgroundColor": {"r": 5, "g": 5, "b": 5}}
+            }"#).unwrap())
+        ];
+        app.styles = load_sources(&app.sources).unwrap();
+        app
+    }
+
+    fn sidebar_color(app: &App) -> Option<Color> {
+        app.window.content_view.subviews[0].backing_node.borrow().background_color
+    }
+
+    #[test]
+    fn applies_the_chosen_theme_at_launch() {
+        let mut app = app().theme("dark");
+        app.apply_theme().unwrap();
+
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(5, 5, 5)));
+        assert_eq!(app.window.window.borrow().background_color, Some(Color::rgb(0, 0, 0)));
+        assert_eq!(app.window.window.borrow().frame, Rect::new(0., 0., 800., 600.));
+    }
+
+    #[test]
+    fn switches_themes_in_place() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(255, 0, 0)));
+
+        app.window.set_frame(Rect::new(0., 0., 400., 300.));
+        app.set_theme("dark").unwrap();
+        assert_eq!(app.active_theme, "dark");
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(5, 5, 5)));
+        assert_eq!(app.window.window.borrow().frame, Rect::new(0., 0., 400., 300.));
+
+        app.set_theme("default").unwrap();
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn switching_themes_drops_the_old_themes_properties() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+        app.set_theme("dark").unwrap();
+        let top = Constraint::relative(Attribute::Top, "root", Attribute::Top, 0.);
+        assert!(app.window.content_view.subviews[0].backing_node.borrow().constraints.contains(&top));
+
+        app.set_theme("default").unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constraints, vec![Constraint::constant(Attribute::Width, 100.)]);
+        assert_eq!(app.window.content_view.subviews[0].backing_node.borrow().constraints, vec![Constraint::constant(Attribute::Width, 100.)]);
+    }
+
+    #[test]
+    fn reloading_applies_only_what_changed() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+        app.window.content_view.subviews[0].set_background_color(&Color::rgb(1, 2, 3));
+
+        let mut sources = app.sources.clone();
+        sources[0].styles["sidebar"]["width"] = json!(150);
+        sources[0].styles["root"].as_object_mut().unwrap().remove("backgroundColor");
+        app.reload_styles(sources).unwrap();
+
+        // The sidebar's color didn't change in the stylesheet, so it's left alone.
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(1, 2, 3)));
+        assert_eq!(app.window.content_view.subviews[0].constraints, vec![Constraint::constant(Attribute::Width, 150.)]);
+        assert_eq!(app.window.content_view.backing_node.borrow().background_color, Some(Color::rgba(0, 0, 0, 0.)));
+    }
+
+    #[test]
+    fn keeps_the_old_styles_when_a_reload_fails() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+
+        let mut sources = app.sources.clone();
+        sources[0].styles["sidebar"]["width"] = json!("wide");
+        assert_eq!(app.reload_styles(sources).unwrap_err().json_path(), "default.sidebar.width");
+        assert_eq!(app.styles["default"]["sidebar"]["width"], json!(100));
+    }
+
+    #[test]
+    fn rejects_unknown_themes() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+
+        let e = app.set_theme("solarized").unwrap_err();
+        assert_eq!(e.to_string(), "solarized: no theme named 'solarized' has been loaded");
+        assert_eq!(app.active_theme, "default");
+    }
+
+    #[test]
+    fn reports_where_bad_values_are() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+
+        let e = app.set_theme("broken").unwrap_err();
+        assert_eq!(e.json_path(), "broken.sidebar.width");
+        assert_eq!(app.active_theme, "default");
+    }
+
+    #[test]
+    fn switching_themes_runs_transitions() {
+        let mut app = app();
+        app.reload_styles(vec![
+            StyleSource::inline(StyleSheet::default(r#"{"sidebar": {"width": 100}}"#).unwrap()),
+            StyleSource::inline(StyleSheet::theme("wide", r#"{
+                "sidebar": {"width": 300, "transition": {"width": "200ms linear"}}
+            }"#).unwrap())
+        ]).unwrap();
+
+        app.set_theme("wide").unwrap();
+        assert!(!app.timeline.is_idle());
+
+        let width = |app: &App| app.window.content_view.subviews[0].animated_value(Property::Width);
+        app.tick(0.1).unwrap();
+        assert_eq!(width(&app), Some(AnimatedValue::Number(200.)));
+        app.tick(0.1).unwrap();
+        assert!(app.timeline.is_idle());
+        assert_eq!(width(&app), Some(AnimatedValue::Number(300.)));
+    }
+
+    #[test]
+    fn lays_out_arranged_subviews_as_they_come_and_go() {
+        let mut app = App::new("test", View::named("root").subviews(vec![VStack::named("list").alignment(Align::Stretch)]));
+        app.reload_styles(vec![StyleSource::inline(StyleSheet::default(r#"{
+            "window": {"defaultWidth": 400, "defaultHeight": 300},
+            "list": {"top": "root.top", "left": "root.left", "width": 200, "bottom": "root.bottom"},
+            ".row": {"height": 30}
+        }"#).unwrap())]).unwrap();
+
+        app.add_arranged_subview("list", View::named("first").classes(&["row"])).unwrap();
+        app.add_arranged_subview("list", View::named("third").classes(&["row"])).unwrap();
+        app.insert_arranged_subview("list", 1, View::named("second").classes(&["row"])).unwrap();
+        let frames = compute_frames(&app.window.content_view, 400., 300.).unwrap();
+        assert_eq!(frames["first"], Rect::new(0., 0., 200., 30.));
+        assert_eq!(frames["second"], Rect::new(0., 30., 200., 30.));
+        assert_eq!(frames["third"], Rect::new(0., 60., 200., 30.));
+
+        let removed = app.remove_arranged_subview("list", "first").unwrap();
+        assert_eq!(removed.map(|view| view.name), Some("first".to_string()));
+        let frames = compute_frames(&app.window.content_view, 400., 300.).unwrap();
+        assert_eq!(frames["second"], Rect::new(0., 0., 200., 30.));
+        assert_eq!(frames["third"], Rect::new(0., 30., 200., 30.));
+        assert!(app.add_arranged_subview("missing", View::named("row")).is_err());
+    }
+
+    #[test]
+    fn relayout_drops_properties_that_no_longer_match() {
+        let mut app = App::new("test", View::named("root").subviews(vec![View::named("sidebar")]));
+        app.reload_styles(vec![StyleSource::inline(StyleSheet::default(r#"{
+            "sidebar": {"width": 100},
+            "sidebar.wide": {"width": 300, "backgroundColor": {"r": 255, "g": 0, "b": 0}}
+        }"#).unwrap())]).unwrap();
+
+        app.window.content_view.subviews[0].add_class("wide");
+        app.relayout().unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constant_for(Attribute::Width), Some(300.));
+        assert_eq!(sidebar_color(&app), Some(Color::rgb(255, 0, 0)));
+
+        app.window.content_view.subviews[0].remove_class("wide");
+        app.relayout().unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constant_for(Attribute::Width), Some(100.));
+        assert_eq!(sidebar_color(&app), Some(Color::rgba(0, 0, 0, 0.)));
+    }
+
+    #[test]
+    fn switching_themes_keeps_what_stacks_set_in_code() {
+        let toolbar = HStack::named("toolbar").spacing(8.).alignment(Align::Start);
+        let mut app = App::new("test", View::named("root").subviews(vec![toolbar]));
+        app.reload_styles(vec![
+            StyleSource::inline(StyleSheet::default(r#"{
+                "toolbar": {"flexDirection": "column", "alignItems": "stretch", "gap": 2, "display": "grid"}
+            }"#).unwrap()),
+            StyleSource::inline(StyleSheet::theme("plain", r#"{"extends": null}"#).unwrap())
+        ]).unwrap();
+
+        let toolbar = &app.window.content_view.subviews[0];
+        assert_eq!((toolbar.display, toolbar.flex.direction, toolbar.flex.align_items, toolbar.flex.gap), (Display::Grid, Direction::Column, Align::Stretch, 2.));
+
+        app.set_theme("plain").unwrap();
+        let toolbar = &app.window.content_view.subviews[0];
+        assert_eq!((toolbar.display, toolbar.flex.direction, toolbar.flex.align_items, toolbar.flex.gap), (Display::Flex, Direction::Row, Align::Start, 8.));
+    }
+
+    #[test]
+    fn restyles_when_resizing_across_breakpoints() {
+        let mut app = app();
+        app.apply_theme().unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constraints, vec![Constraint::constant(Attribute::Width, 100.)]);
+
+        app.window_did_resize(Rect::new(0., 0., 450., 600.)).unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constraints, vec![Constraint::constant(Attribute::Width, 0.)]);
+
+        app.window_did_resize(Rect::new(0., 0., 700., 600.)).unwrap();
+        assert_eq!(app.window.content_view.subviews[0].constraints, vec![Constraint::constant(Attribute::Width, 100.)]);
+    }
+
+    #[test]
+    fn traces_properties_to_where_they_were_written() {
+        let mut app = app().theme("dark");
+        app.apply_theme().unwrap();
+        app.window_did_resize(Rect::new(0., 0., 450., 600.)).unwrap();
+
+        let trace = app.inspect("sidebar").unwrap();
+        let origin = |property: &str| trace.iter().find(|entry| entry.property == property).map(|entry| (entry.theme.as_str(), entry.rule.as_str()));
+        assert_eq!(origin("backgroundColor"), Some(("dark", "sidebar")));
+        assert_eq!(origin("top"), Some(("dark", "sidebar")));
+        assert_eq!(origin("width"), Some(("default", "@media (max-width: 500) > sidebar")));
+
+        assert!(app.style_report("sidebar").unwrap().contains("from \"sidebar\" in dark"));
+        assert_eq!(app.inspect("toolbar"), None);
+    }
+}
@@ -0,0 +1,868 @@
+//! cocoa.rs
+//!
+//! The Cocoa (AppKit) backend. This is where all the NSView/NSWindow wrangling that
+//! used to live directly in view.rs, window.rs and friends ended up. Lots of unsafe
+//! in here, as it's all message passing to Objective-C.
+//!
+//! "Fixes" NSView to be a bit more... how does one say, modern. Flips drawing
+//! and layout coordinates to be fitting for {{CURRENT YEAR}}, layer-backs it all
+//! by default, and does some ivar trickery to make NSColor less of a headache.
+//!
+//! ListViews are NSTableViews inside an NSScrollView. The table's delegate holds the
+//! list's row source and asks it for rows as they scroll into view, passing along
+//! any row the table has queued up for reuse.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/02/2018
+
+use std::sync::{Once, ONCE_INIT};
+use std::os::raw::c_void;
+
+use objc_id::Id;
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel, BOOL};
+use cocoa::base::{class, id, nil, YES, NO};
+use cocoa::foundation::{NSArray, NSInteger, NSRect, NSPoint, NSSize, NSString, NSAutoreleasePool};
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular,
+    NSRunningApplication, NSApplicationActivateIgnoringOtherApps,
+    NSWindow, NSWindowStyleMask, NSBackingStoreType
+};
+
+use backend::{Backend, RowSourceRef};
+use appearance::{Appearance, Shadow};
+use color::Color;
+use label::{FontWeight, TextAlignment, Truncation, Typography};
+use layout::{Attribute, Constraint, Rect};
+use view::ViewKind;
+
+pub struct Cocoa;
+
+impl Backend for Cocoa {
+    type Node = Id<Object>;
+    type Window = id;
+    type App = id;
+
+    fn create_node(kind: &ViewKind, name: &str) -> Id<Object> {
+        match *kind {
+            ViewKind::View => unsafe {
+                let alloc: id = msg_send![register_view_class(), alloc];
+                let view: id = msg_send![alloc, initWithFrame:empty_frame()];
+                msg_send![view, setWantsLayer:YES];
+                msg_send![view, setLayerContentsRedrawPolicy:1];
+                msg_send![view, setTranslatesAutoresizingMaskIntoConstraints:NO];
+                (*view).set_ivar("shinekitOpacity", 1.0f64);
+                Id::from_ptr(view)
+            },
+
+            ViewKind::Label => unsafe {
+                let alloc: id = msg_send![register_label_class(), alloc];
+                let view: id = msg_send![alloc, initWithFrame:empty_frame()];
+                msg_send![view, setTranslatesAutoresizingMaskIntoConstraints:NO];
+                msg_send![view, setEditable:NO];
+                msg_send![view, setBezeled:NO];
+                msg_send![view, setBordered:NO];
+                msg_send![view, setDrawsBackground:YES];
+                msg_send![view, setAllowsEditingTextAttributes:NO];
+                msg_send![view, setContentCompressionResistancePriority:250 forOrientation:0];
+
+                (*view).set_ivar("shinekitOpacity", 1.0f64);
+
+                let cell: id = msg_send![view, cell];
+                msg_send![cell, setUsesSingleLineMode:NO];
+                msg_send![cell, setWraps:YES];
+                msg_send![cell, setLineBreakMode:0];
+                Id::from_ptr(view)
+            },
+
+            ViewKind::ScrollView => unsafe {
+                let scrollview = create_scrollview();
+
+                // Subviews go in a (flipped) document view, which scrolls vertically
+                // and tracks the scroll view's width.
+                let document = Cocoa::create_node(&ViewKind::View, name);
+                msg_send![scrollview, setDocumentView:&*document];
+                let clip: id = msg_send![scrollview, contentView];
+                for &attribute in &[Attribute::Top, Attribute::Leading, Attribute::Trailing] {
+                    let constraint: id = msg_send![anchor_for(&*document, attribute), constraintEqualToAnchor:anchor_for(&*clip, attribute)];
+                    msg_send![constraint, setActive:YES];
+                }
+
+                Id::from_ptr(scrollview)
+            },
+
+            ViewKind::ListView => unsafe {
+                let scrollview = create_scrollview();
+                let alloc: id = msg_send![register_list_class(), alloc];
+                let tableview: id = msg_send![alloc, initWithFrame:empty_frame()];
+                msg_send![tableview, setWantsLayer:YES];
+                msg_send![tableview, setUsesAutomaticRowHeights:YES];
+                msg_send![tableview, setRowHeight:100.];
+                msg_send![tableview, setFloatsGroupRows:YES];
+                msg_send![tableview, setIntercellSpacing:NSSize::new(0., 0.)];
+                msg_send![tableview, setColumnAutoresizingStyle:1];
+                msg_send![tableview, setUsesAlternatingRowBackgroundColors:NO];
+                msg_send![tableview, setAllowsEmptySelection:YES];
+                msg_send![tableview, setAllowsMultipleSelection:NO];
+                msg_send![tableview, setHeaderView:nil];
+
+                // NSTableView requires at least one column to be manually added if doing so by code.
+                // A relic of a bygone era, indeed.
+                let column: id = msg_send![class("NSTableColumn"), alloc];
+                let column: id = msg_send![column, initWithIdentifier:NSString::alloc(nil).init_str(ROW_IDENTIFIER)];
+                msg_send![column, setResizingMask:(1<<0)];
+                msg_send![tableview, addTableColumn:column];
+
+                msg_send![scrollview, setDocumentView:tableview];
+                Id::from_ptr(scrollview)
+            }
+        }
+    }
+
+    fn add_subview(parent: &Id<Object>, child: &Id<Object>) {
+        unsafe {
+            msg_send![subview_container(&**parent), addSubview:&**child];
+        }
+    }
+
+    fn same_node(a: &Id<Object>, b: &Id<Object>) -> bool {
+        &**a as *const Object == &**b as *const Object
+    }
+
+    fn insert_subview(parent: &Id<Object>, child: &Id<Object>, index: usize) {
+        unsafe {
+            let container = subview_container(&**parent);
+            let subviews: id = msg_send![container, subviews];
+            if (index as u64) < subviews.count() {
+                // -1 is NSWindowBelow.
+                let sibling = subviews.objectAtIndex(index as u64);
+                msg_send![container, addSubview:&**child positioned:-1isize relativeTo:sibling];
+            } else {
+                msg_send![container, addSubview:&**child];
+            }
+        }
+    }
+
+    fn remove_subview(_parent: &Id<Object>, child: &Id<Object>) {
+        unsafe {
+            msg_send![&**child, removeFromSuperview];
+        }
+    }
+
+    fn set_background_color(node: &Id<Object>, color: &Color) {
+        unsafe {
+            let color = into_platform_specific_color(color);
+            let is_label: BOOL = msg_send![&**node, isKindOfClass:class("NSTextField")];
+
+            // NSTextField draws its own background, so there's no layer trickery needed.
+            if is_label == YES {
+                msg_send![&**node, setBackgroundColor:color];
+            } else {
+                let this = &**node as *const Object as id;
+                (*this).set_ivar("shinekitBackgroundColor", color);
+                msg_send![&**node, setNeedsDisplay:YES];
+            }
+        }
+    }
+
+    fn set_appearance(node: &Id<Object>, appearance: &Appearance) {
+        unsafe {
+            let this = &**node as *const Object as id;
+            let border_color = appearance.border_color.map(|color| into_platform_specific_color(&color)).unwrap_or(nil);
+            let shadow_color = appearance.shadow.map(|shadow| into_platform_specific_color(&shadow.color)).unwrap_or(nil);
+            let shadow = appearance.shadow.unwrap_or_else(|| Shadow::new(Color::rgba(0, 0, 0, 0.), 0., 0., 0.));
+
+            (*this).set_ivar("shinekitBorderWidth", appearance.border_width);
+            (*this).set_ivar("shinekitBorderColor", border_color);
+            (*this).set_ivar("shinekitCornerRadius", appearance.corner_radius);
+            (*this).set_ivar("shinekitOpacity", appearance.opacity);
+            (*this).set_ivar("shinekitShadowColor", shadow_color);
+            (*this).set_ivar("shinekitShadowOffsetX", shadow.offset_x);
+            (*this).set_ivar("shinekitShadowOffsetY", shadow.offset_y);
+            (*this).set_ivar("shinekitShadowBlur", shadow.blur);
+
+            // Labels don't go through updateLayer, so they get their layer set up right here.
+            let is_label: BOOL = msg_send![&**node, isKindOfClass:class("NSTextField")];
+            if is_label == YES {
+                msg_send![&**node, setWantsLayer:YES];
+                apply_layer_ivars(&**node);
+            } else {
+                msg_send![&**node, setNeedsDisplay:YES];
+            }
+        }
+    }
+
+    fn set_text(node: &Id<Object>, text: &str) {
+        unsafe {
+            let value = NSString::alloc(nil).init_str(text);
+            let this = &**node as *const Object as id;
+            let attributes: id = *(*this).get_ivar("shinekitTextAttributes");
+
+            // Once typography is set, the text has to go in as an attributed string,
+            // or letter spacing and line height get dropped on the floor.
+            if attributes != nil {
+                let alloc: id = msg_send![class("NSAttributedString"), alloc];
+                let string: id = msg_send![alloc, initWithString:value attributes:attributes];
+                msg_send![&**node, setAttributedStringValue:string];
+            } else {
+                msg_send![&**node, setStringValue:value];
+            }
+        }
+    }
+
+    fn set_text_color(node: &Id<Object>, color: Option<&Color>) {
+        unsafe {
+            let color = match color {
+                Some(color) => into_platform_specific_color(color),
+                None => msg_send![class("NSColor"), labelColor]
+            };
+            msg_send![&**node, setTextColor:color];
+
+            let this = &**node as *const Object as id;
+            let attributes: id = *(*this).get_ivar("shinekitTextAttributes");
+            if attributes != nil {
+                let key = NSString::alloc(nil).init_str("NSColor");
+                msg_send![attributes, setObject:color forKey:key];
+                restyle_text(this, attributes);
+            }
+        }
+    }
+
+    fn set_typography(node: &Id<Object>, typography: &Typography) {
+        unsafe {
+            let this = &**node as *const Object as id;
+            let font = font_for(typography);
+
+            let paragraph: id = msg_send![class("NSMutableParagraphStyle"), new];
+            msg_send![paragraph, setAlignment:text_alignment(typography.alignment)];
+            msg_send![paragraph, setLineBreakMode:line_break_mode(typography.truncation)];
+            if let Some(line_height) = typography.line_height {
+                msg_send![paragraph, setMinimumLineHeight:line_height];
+                msg_send![paragraph, setMaximumLineHeight:line_height];
+            }
+
+            msg_send![this, setFont:font];
+            msg_send![this, setAlignment:text_alignment(typography.alignment)];
+            msg_send![this, setMaximumNumberOfLines:typography.line_limit as i64];
+
+            let cell: id = msg_send![this, cell];
+            msg_send![cell, setLineBreakMode:line_break_mode(typography.truncation)];
+            msg_send![cell, setWraps:if typography.truncation == Truncation::None { YES } else { NO }];
+            msg_send![cell, setTruncatesLastVisibleLine:if typography.truncation == Truncation::None { NO } else { YES }];
+
+            let attributes: id = msg_send![class("NSMutableDictionary"), new];
+            let text_color: id = msg_send![this, textColor];
+            let entries: [(&str, id); 4] = [
+                ("NSFont", font),
+                ("NSParagraphStyle", paragraph),
+                ("NSKern", msg_send![class("NSNumber"), numberWithDouble:typography.letter_spacing]),
+                ("NSColor", text_color)
+            ];
+
+            for &(key, value) in &entries {
+                if value != nil {
+                    let key = NSString::alloc(nil).init_str(key);
+                    msg_send![attributes, setObject:value forKey:key];
+                }
+            }
+
+            let existing: id = *(*this).get_ivar("shinekitTextAttributes");
+            if existing != nil {
+                msg_send![existing, release];
+            }
+
+            (*this).set_ivar("shinekitTextAttributes", attributes);
+            restyle_text(this, attributes);
+        }
+    }
+
+    fn set_row_source(node: &Id<Object>, source: RowSourceRef<Id<Object>>) {
+        unsafe {
+            // NSTableView doesn't retain its delegate, so the scroll view holds onto it.
+            let delegate: id = msg_send![register_list_delegate_class(), new];
+            let source = Box::into_raw(Box::new(source));
+            (*delegate).set_ivar("shinekitRowSource", source as *mut c_void);
+
+            let this = &**node as *const Object as id;
+            let existing: id = *(*this).get_ivar("shinekitListDelegate");
+            (*this).set_ivar("shinekitListDelegate", delegate);
+
+            let tableview: id = msg_send![&**node, documentView];
+            msg_send![tableview, setDelegate:delegate];
+            msg_send![tableview, setDataSource:delegate];
+
+            if existing != nil {
+                msg_send![existing, release];
+            }
+        }
+    }
+
+    fn reload_rows(node: &Id<Object>) {
+        unsafe {
+            let tableview: id = msg_send![&**node, documentView];
+            msg_send![tableview, reloadData];
+        }
+    }
+
+    fn activate_constraints(node: &Id<Object>, constraints: &[(&Constraint, Option<&Id<Object>>)]) {
+        let mut bundle: Vec<id> = vec![];
+
+        unsafe {
+            let this = &**node as *const Object as id;
+
+            for &(constraint, target) in constraints {
+                let anchor = anchor_for(&**node, constraint.attribute);
+                let platform_constraint: id = match (&constraint.target, target) {
+                    (&Some(ref relation), Some(target)) => {
+                        let target_anchor = anchor_for(&*constraint_target(&**node, &**target), relation.attribute);
+                        msg_send![anchor, constraintEqualToAnchor:target_anchor constant:constraint.constant]
+                    },
+
+                    (&None, _) => msg_send![anchor, constraintEqualToConstant:constraint.constant],
+                    _ => { continue; }
+                };
+
+                // Swap out whatever was there before, otherwise re-applying styles just
+                // piles up conflicting constraints.
+                let ivar = constraint_ivar(constraint.attribute);
+                let existing: id = *(*this).get_ivar(ivar);
+                if existing != nil {
+                    msg_send![existing, setActive:NO];
+                }
+
+                (*this).set_ivar(ivar, platform_constraint);
+                bundle.push(platform_constraint);
+            }
+
+            if !bundle.is_empty() {
+                let bundle = NSArray::arrayWithObjects(nil, &bundle);
+                msg_send![class("NSLayoutConstraint"), activateConstraints:bundle];
+            }
+        }
+    }
+
+    fn deactivate_constraint(node: &Id<Object>, attribute: Attribute) {
+        unsafe {
+            let this = &**node as *const Object as id;
+            let ivar = constraint_ivar(attribute);
+            let existing: id = *(*this).get_ivar(ivar);
+            if existing != nil {
+                msg_send![existing, setActive:NO];
+                (*this).set_ivar(ivar, nil);
+            }
+        }
+    }
+
+    fn create_window(title: &str, frame: &Rect, content: &Id<Object>) -> id {
+        unsafe {
+            let style = NSWindowStyleMask::NSResizableWindowMask |
+                NSWindowStyleMask::NSUnifiedTitleAndToolbarWindowMask | NSWindowStyleMask::NSMiniaturizableWindowMask |
+                NSWindowStyleMask::NSClosableWindowMask | NSWindowStyleMask::NSTitledWindowMask;
+
+            let window = NSWindow::alloc(nil).initWithContentRect_styleMask_backing_defer_(
+                into_platform_specific_rect(frame),
+                style,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                NO
+            ).autorelease();
+
+            let title = NSString::alloc(nil).init_str(title);
+            window.setTitle_(title);
+            msg_send![&**content, setTranslatesAutoresizingMaskIntoConstraints:YES];
+            msg_send![window, setContentView:&**content];
+            msg_send![window, setTitlebarAppearsTransparent:YES];
+            msg_send![window, setTitleVisibility:1];
+            window
+        }
+    }
+
+    fn set_window_frame(window: &id, frame: &Rect) {
+        unsafe {
+            msg_send![*window, setFrame:into_platform_specific_rect(frame) display:YES];
+        }
+    }
+
+    fn set_window_background_color(window: &id, color: &Color) {
+        unsafe {
+            msg_send![*window, setBackgroundColor:into_platform_specific_color(color)];
+        }
+    }
+
+    fn make_key_window(window: &id) {
+        unsafe {
+            window.makeKeyAndOrderFront_(nil);
+        }
+    }
+
+    fn on_window_resize(window: &id, callback: Box<dyn FnMut(Rect)>) {
+        unsafe {
+            let alloc: id = msg_send![register_resize_observer_class(), alloc];
+            let observer: id = msg_send![alloc, init];
+
+            // Lives as long as the window does, which is as long as the app does.
+            let callback = Box::into_raw(Box::new(callback)) as *mut c_void;
+            (*observer).set_ivar("shinekitCallback", callback);
+
+            let center: id = msg_send![class("NSNotificationCenter"), defaultCenter];
+            let name = NSString::alloc(nil).init_str("NSWindowDidResizeNotification");
+            msg_send![center, addObserver:observer selector:sel!(windowDidResize:) name:name object:*window];
+        }
+    }
+
+    fn create_app() -> id {
+        unsafe {
+            let _pool = NSAutoreleasePool::new(nil);
+            let app = NSApp();
+            app.setActivationPolicy_(NSApplicationActivationPolicyRegular);
+            app
+        }
+    }
+
+    fn run_app(app: &id) {
+        unsafe {
+            let current_app = NSRunningApplication::currentApplication(nil);
+            current_app.activateWithOptions_(NSApplicationActivateIgnoringOtherApps);
+            app.run();
+        }
+    }
+
+    fn schedule_repeating(interval: f64, callback: Box<dyn FnMut()>) {
+        unsafe {
+            let alloc: id = msg_send![register_timer_target_class(), alloc];
+            let target: id = msg_send![alloc, init];
+
+            // The timer retains its target for as long as it repeats, which is the
+            // life of the app, so the callback is deliberately never freed.
+            let callback = Box::into_raw(Box::new(callback)) as *mut c_void;
+            (*target).set_ivar("shinekitCallback", callback);
+
+            msg_send![class("NSTimer"), scheduledTimerWithTimeInterval:interval
+                target:target selector:sel!(tick:) userInfo:nil repeats:YES];
+        }
+    }
+}
+
+const ROW_IDENTIFIER: &str = "shinekitRow";
+
+unsafe fn create_scrollview() -> id {
+    let alloc: id = msg_send![register_scrollview_class(), alloc];
+    let scrollview: id = msg_send![alloc, initWithFrame:empty_frame()];
+    msg_send![scrollview, setTranslatesAutoresizingMaskIntoConstraints:NO];
+    msg_send![scrollview, setDrawsBackground:NO];
+    msg_send![scrollview, setWantsLayer:YES];
+    msg_send![scrollview, setBorderType:0];
+    msg_send![scrollview, setHorizontalScrollElasticity:1];
+    msg_send![scrollview, setHasVerticalScroller:YES];
+    scrollview
+}
+
+pub fn empty_frame() -> NSRect {
+    NSRect::new(NSPoint::new(0., 0.), NSSize::new(0., 0.))
+}
+
+pub fn into_platform_specific_rect(rect: &Rect) -> NSRect {
+    NSRect::new(NSPoint::new(rect.x, rect.y), NSSize::new(rect.width, rect.height))
+}
+
+pub fn into_platform_specific_color(color: &Color) -> id {
+    unsafe { msg_send![class("NSColor"), colorWithRed:color.r green:color.g blue:color.b alpha:color.a] }
+}
+
+/// Re-sets a label's current text with new attributes.
+unsafe fn restyle_text(label: id, attributes: id) {
+    let text: id = msg_send![label, stringValue];
+    let alloc: id = msg_send![class("NSAttributedString"), alloc];
+    let string: id = msg_send![alloc, initWithString:text attributes:attributes];
+    msg_send![label, setAttributedStringValue:string];
+}
+
+unsafe fn font_for(typography: &Typography) -> id {
+    let size = match typography.font_size {
+        Some(size) => size,
+        None => msg_send![class("NSFont"), systemFontSize]
+    };
+
+    let font: id = match typography.font_family {
+        // NSFontManager wants weights on its own 0 - 15 scale, where 5 is regular and 9 is bold.
+        Some(ref family) => {
+            let manager: id = msg_send![class("NSFontManager"), sharedFontManager];
+            let family = NSString::alloc(nil).init_str(family);
+            let traits: u64 = if typography.italic { 1 } else { 0 };
+            msg_send![manager, fontWithFamily:family traits:traits weight:font_manager_weight(typography.font_weight) size:size]
+        },
+
+        None => msg_send![class("NSFont"), systemFontOfSize:size weight:system_font_weight(typography.font_weight)]
+    };
+
+    // Unknown families come back nil; fall back to the system font instead of drawing nothing.
+    let font = if font == nil {
+        msg_send![class("NSFont"), systemFontOfSize:size weight:system_font_weight(typography.font_weight)]
+    } else {
+        font
+    };
+
+    if typography.italic {
+        let manager: id = msg_send![class("NSFontManager"), sharedFontManager];
+        msg_send![manager, convertFont:font toHaveTrait:1u64]
+    } else {
+        font
+    }
+}
+
+fn system_font_weight(weight: FontWeight) -> f64 {
+    match weight {
+        FontWeight::UltraLight => -0.8,
+        FontWeight::Thin => -0.6,
+        FontWeight::Light => -0.4,
+        FontWeight::Regular => 0.,
+        FontWeight::Medium => 0.23,
+        FontWeight::Semibold => 0.3,
+        FontWeight::Bold => 0.4,
+        FontWeight::Heavy => 0.56,
+        FontWeight::Black => 0.62
+    }
+}
+
+fn font_manager_weight(weight: FontWeight) -> i64 {
+    match weight {
+        FontWeight::UltraLight => 1,
+        FontWeight::Thin => 2,
+        FontWeight::Light => 3,
+        FontWeight::Regular => 5,
+        FontWeight::Medium => 6,
+        FontWeight::Semibold => 8,
+        FontWeight::Bold => 9,
+        FontWeight::Heavy => 11,
+        FontWeight::Black => 12
+    }
+}
+
+fn text_alignment(alignment: TextAlignment) -> u64 {
+    match alignment {
+        TextAlignment::Left => 0,
+        TextAlignment::Right => 1,
+        TextAlignment::Center => 2,
+        TextAlignment::Justified => 3,
+        TextAlignment::Natural => 4
+    }
+}
+
+fn line_break_mode(truncation: Truncation) -> u64 {
+    match truncation {
+        Truncation::None => 0,
+        Truncation::Clip => 2,
+        Truncation::Head => 3,
+        Truncation::Tail => 4,
+        Truncation::Middle => 5
+    }
+}
+
+fn constraint_ivar(attribute: Attribute) -> &'static str {
+    match attribute {
+        Attribute::Width => "shinekitConstraintWidth",
+        Attribute::Height => "shinekitConstraintHeight",
+        Attribute::Top => "shinekitConstraintTop",
+        Attribute::Leading => "shinekitConstraintLeading",
+        Attribute::Trailing => "shinekitConstraintTrailing",
+        Attribute::Bottom => "shinekitConstraintBottom"
+    }
+}
+
+/// Where subviews of a node actually go: a ScrollView's document view, or the
+/// node itself for everything else (ListViews manage their own rows).
+unsafe fn subview_container(node: &Object) -> id {
+    let is_scrollview: BOOL = msg_send![node, isKindOfClass:class("NSScrollView")];
+    if is_scrollview == YES {
+        let document: id = msg_send![node, documentView];
+        let is_table: BOOL = msg_send![document, isKindOfClass:class("NSTableView")];
+        if is_table == NO {
+            return document;
+        }
+    }
+
+    node as *const Object as id
+}
+
+/// Subviews of a ScrollView pin themselves to its document view, so they move
+/// when it scrolls. Anything else pinned to a scroll view gets the scroll view.
+unsafe fn constraint_target(node: &Object, target: &Object) -> id {
+    let container = subview_container(target);
+    let superview: id = msg_send![node, superview];
+    if superview == container {
+        container
+    } else {
+        target as *const Object as id
+    }
+}
+
+unsafe fn anchor_for(node: &Object, attribute: Attribute) -> id {
+    match attribute {
+        Attribute::Width => msg_send![node, widthAnchor],
+        Attribute::Height => msg_send![node, heightAnchor],
+        Attribute::Top => msg_send![node, topAnchor],
+        Attribute::Leading => msg_send![node, leadingAnchor],
+        Attribute::Trailing => msg_send![node, trailingAnchor],
+        Attribute::Bottom => msg_send![node, bottomAnchor]
+    }
+}
+
+/// Everything that ends up on the layer: the background color, plus the rest of
+/// a view's `Appearance`.
+pub fn add_layer_ivars(decl: &mut ClassDecl) {
+    decl.add_ivar::<id>("shinekitBackgroundColor");
+    decl.add_ivar::<f64>("shinekitBorderWidth");
+    decl.add_ivar::<id>("shinekitBorderColor");
+    decl.add_ivar::<f64>("shinekitCornerRadius");
+    decl.add_ivar::<f64>("shinekitOpacity");
+    decl.add_ivar::<id>("shinekitShadowColor");
+    decl.add_ivar::<f64>("shinekitShadowOffsetX");
+    decl.add_ivar::<f64>("shinekitShadowOffsetY");
+    decl.add_ivar::<f64>("shinekitShadowBlur");
+}
+
+pub fn add_autolayout_ivars(decl: &mut ClassDecl) {
+    decl.add_ivar::<id>("shinekitConstraintWidth");
+    decl.add_ivar::<id>("shinekitConstraintHeight");
+    decl.add_ivar::<id>("shinekitConstraintTop");
+    decl.add_ivar::<id>("shinekitConstraintLeading");
+    decl.add_ivar::<id>("shinekitConstraintTrailing");
+    decl.add_ivar::<id>("shinekitConstraintBottom");
+}
+
+extern fn enforce_normalcy(_: &Object, _: Sel) -> BOOL {
+    return YES;
+}
+
+extern fn update_layer(this: &Object, _: Sel) {
+    unsafe {
+        let background_color: id = *this.get_ivar("shinekitBackgroundColor");
+        if background_color != nil {
+            let layer: id = msg_send![this, layer];
+            let cg: id = msg_send![background_color, CGColor];
+            msg_send![layer, setBackgroundColor:cg];
+        }
+
+        apply_layer_ivars(this);
+    }
+}
+
+unsafe fn apply_layer_ivars(this: &Object) {
+    let layer: id = msg_send![this, layer];
+    if layer == nil {
+        return;
+    }
+
+    let border_width: f64 = *this.get_ivar("shinekitBorderWidth");
+    let border_color: id = *this.get_ivar("shinekitBorderColor");
+    let corner_radius: f64 = *this.get_ivar("shinekitCornerRadius");
+    let opacity: f64 = *this.get_ivar("shinekitOpacity");
+    let shadow_color: id = *this.get_ivar("shinekitShadowColor");
+
+    msg_send![layer, setBorderWidth:border_width];
+    msg_send![layer, setCornerRadius:corner_radius];
+    msg_send![layer, setOpacity:opacity as f32];
+
+    if border_color != nil {
+        let cg: id = msg_send![border_color, CGColor];
+        msg_send![layer, setBorderColor:cg];
+    }
+
+    // Clipping to the rounded corners would clip the shadow right along with them.
+    msg_send![layer, setMasksToBounds:if corner_radius > 0. && shadow_color == nil { YES } else { NO }];
+
+    if shadow_color != nil {
+        let offset_x: f64 = *this.get_ivar("shinekitShadowOffsetX");
+        let offset_y: f64 = *this.get_ivar("shinekitShadowOffsetY");
+        let blur: f64 = *this.get_ivar("shinekitShadowBlur");
+        let cg: id = msg_send![shadow_color, CGColor];
+
+        // The shadow's alpha comes from its color. CSS blur is roughly twice what
+        // Core Animation calls a radius.
+        msg_send![layer, setShadowColor:cg];
+        msg_send![layer, setShadowOpacity:1.0f32];
+        msg_send![layer, setShadowOffset:NSSize::new(offset_x, offset_y)];
+        msg_send![layer, setShadowRadius:blur / 2.];
+    } else {
+        msg_send![layer, setShadowOpacity:0.0f32];
+    }
+}
+
+pub fn register_view_class() -> *const Class {
+    static mut view_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSView").unwrap();
+        let mut decl = ClassDecl::new("shinekitView", superclass).unwrap();
+        decl.add_method(sel!(isFlipped), enforce_normalcy as extern fn(&Object, _) -> BOOL);
+        decl.add_method(sel!(requiresConstraintBasedLayout), enforce_normalcy as extern fn(&Object, _) -> BOOL);
+        decl.add_method(sel!(wantsUpdateLayer), enforce_normalcy as extern fn(&Object, _) -> BOOL);
+        decl.add_method(sel!(updateLayer), update_layer as extern fn(&Object, _));
+        add_layer_ivars(&mut decl);
+        add_autolayout_ivars(&mut decl);
+        view_class = decl.register();
+    });
+
+    unsafe {
+        view_class
+    }
+}
+
+fn register_label_class() -> *const Class {
+    static mut label_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSTextField").unwrap();
+        let mut decl = ClassDecl::new("shinekitLabel", superclass).unwrap();
+        decl.add_ivar::<id>("shinekitTextAttributes");
+        add_layer_ivars(&mut decl);
+        add_autolayout_ivars(&mut decl);
+        label_class = decl.register();
+    });
+
+    unsafe {
+        label_class
+    }
+}
+
+fn register_scrollview_class() -> *const Class {
+    static mut scrollview_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSScrollView").unwrap();
+        let mut decl = ClassDecl::new("shinekitScrollView", superclass).unwrap();
+        decl.add_method(sel!(wantsUpdateLayer), enforce_normalcy as extern fn(&Object, _) -> BOOL);
+        decl.add_method(sel!(updateLayer), update_layer as extern fn(&Object, _));
+        decl.add_ivar::<id>("shinekitListDelegate");
+        add_autolayout_ivars(&mut decl);
+        scrollview_class = decl.register();
+    });
+
+    unsafe {
+        scrollview_class
+    }
+}
+
+fn register_list_class() -> *const Class {
+    static mut list_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSTableView").unwrap();
+        let decl = ClassDecl::new("shinekitListView", superclass).unwrap();
+        list_class = decl.register();
+    });
+
+    unsafe {
+        list_class
+    }
+}
+
+unsafe fn row_source(this: &Object) -> &RowSourceRef<Id<Object>> {
+    let source: *mut c_void = *this.get_ivar("shinekitRowSource");
+    &*(source as *const RowSourceRef<Id<Object>>)
+}
+
+extern fn number_of_rows(this: &Object, _: Sel, _: id) -> NSInteger {
+    unsafe {
+        row_source(this).borrow().number_of_rows() as NSInteger
+    }
+}
+
+extern fn view_for_row(this: &Object, _: Sel, table_view: id, _: id, row: NSInteger) -> id {
+    unsafe {
+        let identifier = NSString::alloc(nil).init_str(ROW_IDENTIFIER);
+        let reusable: id = msg_send![table_view, makeViewWithIdentifier:identifier owner:nil];
+        let reusing = if reusable != nil { Some(Id::from_ptr(reusable)) } else { None };
+
+        let mut source = row_source(this).borrow_mut();
+        let cell = &**source.row(row as usize, reusing.as_ref()) as *const Object as id;
+        msg_send![cell, setIdentifier:identifier];
+        cell
+    }
+}
+
+extern fn dealloc_list_delegate(this: &Object, _: Sel) {
+    unsafe {
+        let source: *mut c_void = *this.get_ivar("shinekitRowSource");
+        if !source.is_null() {
+            drop(Box::from_raw(source as *mut RowSourceRef<Id<Object>>));
+        }
+
+        let superclass = Class::get("NSObject").unwrap();
+        msg_send![super(this, superclass), dealloc];
+    }
+}
+
+fn register_list_delegate_class() -> *const Class {
+    static mut delegate_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSObject").unwrap();
+        let mut decl = ClassDecl::new("shinekitListDelegate", superclass).unwrap();
+        decl.add_method(sel!(numberOfRowsInTableView:), number_of_rows as extern fn(&Object, _, id) -> NSInteger);
+        decl.add_method(sel!(tableView:viewForTableColumn:row:), view_for_row as extern fn(&Object, _, id, id, NSInteger) -> id);
+        decl.add_method(sel!(dealloc), dealloc_list_delegate as extern fn(&Object, _));
+        decl.add_ivar::<*mut c_void>("shinekitRowSource");
+        delegate_class = decl.register();
+    });
+
+    unsafe {
+        delegate_class
+    }
+}
+
+extern fn timer_tick(this: &Object, _: Sel, _timer: id) {
+    unsafe {
+        let callback: *mut c_void = *this.get_ivar("shinekitCallback");
+        let callback = &mut *(callback as *mut Box<dyn FnMut()>);
+        callback();
+    }
+}
+
+fn register_timer_target_class() -> *const Class {
+    static mut timer_target_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSObject").unwrap();
+        let mut decl = ClassDecl::new("shinekitTimerTarget", superclass).unwrap();
+        decl.add_ivar::<*mut c_void>("shinekitCallback");
+        decl.add_method(sel!(tick:), timer_tick as extern fn(&Object, _, id));
+        timer_target_class = decl.register();
+    });
+
+    unsafe {
+        timer_target_class
+    }
+}
+
+extern fn window_did_resize(this: &Object, _: Sel, notification: id) {
+    unsafe {
+        let window: id = msg_send![notification, object];
+        let frame: NSRect = msg_send![window, frame];
+        let callback: *mut c_void = *this.get_ivar("shinekitCallback");
+        let callback = &mut *(callback as *mut Box<dyn FnMut(Rect)>);
+        callback(Rect::new(frame.origin.x, frame.origin.y, frame.size.width, frame.size.height));
+    }
+}
+
+fn register_resize_observer_class() -> *const Class {
+    static mut resize_observer_class: *const Class = 0 as *const Class;
+    static INIT: Once = ONCE_INIT;
+
+    INIT.call_once(|| unsafe {
+        let superclass = Class::get("NSObject").unwrap();
+        let mut decl = ClassDecl::new("shinekitResizeObserver", superclass).unwrap();
+        decl.add_ivar::<*mut c_void>("shinekitCallback");
+        decl.add_method(sel!(windowDidResize:), window_did_resize as extern fn(&Object, _, id));
+        resize_observer_class = decl.register();
+    });
+
+    unsafe {
+        resize_observer_class
+    }
+}
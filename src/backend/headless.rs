@@ -0,0 +1,471 @@
+//! headless.rs
+//!
+//! A backend that doesn't draw anything. Every node is just a struct that records
+//! what would have happened to it - subviews added, colors set, constraints
+//! activated - so view trees can be built and poked at on machines with no
+//! windowing system at all.
+//!
+//! Lists have no viewport here, so reloading one asks its source for every row,
+//! handing back the rows it built last time for reuse.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/02/2018
+
+use std::mem;
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use backend::{Backend, RowSourceRef};
+use appearance::Appearance;
+use color::Color;
+use label::Typography;
+use layout::{Attribute, Constraint, Rect};
+use view::ViewKind;
+
+#[derive(Debug)]
+pub struct NodeData {
+    pub kind: ViewKind,
+    pub name: String,
+    pub subviews: Vec<HeadlessNode>,
+    pub background_color: Option<Color>,
+    pub appearance: Option<Appearance>,
+    pub text: Option<String>,
+    pub text_color: Option<Color>,
+    pub typography: Option<Typography>,
+    pub constraints: Vec<Constraint>,
+    pub row_source: Option<RowSourceRef<HeadlessNode>>,
+    pub rows: Vec<HeadlessNode>
+}
+
+#[derive(Debug)]
+pub struct WindowData {
+    pub title: String,
+    pub frame: Rect,
+    pub background_color: Option<Color>,
+    pub content: HeadlessNode,
+    pub is_key: bool
+}
+
+#[derive(Debug, Default)]
+pub struct AppData {
+    pub has_run: bool
+}
+
+pub type HeadlessNode = Rc<RefCell<NodeData>>;
+pub type HeadlessWindow = Rc<RefCell<WindowData>>;
+pub type HeadlessApp = Rc<RefCell<AppData>>;
+
+type ResizeHandler = (HeadlessWindow, Box<dyn FnMut(Rect)>);
+
+thread_local! {
+    static TIMERS: RefCell<Vec<Box<dyn FnMut()>>> = RefCell::new(vec![]);
+    static RESIZE_HANDLERS: RefCell<Vec<ResizeHandler>> = RefCell::new(vec![]);
+}
+
+/// Resizes a window the way a user dragging its corner would, which (unlike
+/// `set_window_frame`) lets anything listening for resizes know about it.
+pub fn resize_window(window: &HeadlessWindow, frame: Rect) {
+    window.borrow_mut().frame = frame;
+
+    let mut handlers = RESIZE_HANDLERS.with(|handlers| handlers.replace(vec![]));
+    for &mut (ref handler_window, ref mut handler) in &mut handlers {
+        if Rc::ptr_eq(handler_window, window) {
+            handler(frame);
+        }
+    }
+
+    RESIZE_HANDLERS.with(|registered| {
+        let mut registered = registered.borrow_mut();
+        handlers.append(&mut registered);
+        *registered = handlers;
+    });
+}
+
+/// Runs every scheduled repeating callback once. With no event loop around,
+/// this stands in for time passing.
+pub fn fire_timers() {
+    let mut timers = TIMERS.with(|timers| timers.replace(vec![]));
+    for timer in &mut timers {
+        timer();
+    }
+
+    // Anything scheduled while firing goes after the ones that were already there.
+    TIMERS.with(|scheduled| {
+        let mut scheduled = scheduled.borrow_mut();
+        timers.append(&mut scheduled);
+        *scheduled = timers;
+    });
+}
+
+pub struct Headless;
+
+impl Backend for Headless {
+    type Node = HeadlessNode;
+    type Window = HeadlessWindow;
+    type App = HeadlessApp;
+
+    fn create_node(kind: &ViewKind, name: &str) -> HeadlessNode {
+        Rc::new(RefCell::new(NodeData {
+            kind: kind.clone(),
+            name: name.into(),
+            subviews: vec![],
+            background_color: None,
+            appearance: None,
+            text: None,
+            text_color: None,
+            typography: None,
+            constraints: vec![],
+            row_source: None,
+            rows: vec![]
+        }))
+    }
+
+    fn add_subview(parent: &HeadlessNode, child: &HeadlessNode) {
+        parent.borrow_mut().subviews.push(child.clone());
+    }
+
+    fn same_node(a: &HeadlessNode, b: &HeadlessNode) -> bool {
+        Rc::ptr_eq(a, b)
+    }
+
+    fn insert_subview(parent: &HeadlessNode, child: &HeadlessNode, index: usize) {
+        let mut parent = parent.borrow_mut();
+        let index = index.min(parent.subviews.len());
+        parent.subviews.insert(index, child.clone());
+    }
+
+    fn remove_subview(parent: &HeadlessNode, child: &HeadlessNode) {
+        parent.borrow_mut().subviews.retain(|subview| !Rc::ptr_eq(subview, child));
+    }
+
+    fn set_background_color(node: &HeadlessNode, color: &Color) {
+        node.borrow_mut().background_color = Some(*color);
+    }
+
+    fn set_appearance(node: &HeadlessNode, appearance: &Appearance) {
+        node.borrow_mut().appearance = Some(appearance.clone());
+    }
+
+    fn set_text(node: &HeadlessNode, text: &str) {
+        node.borrow_mut().text = Some(text.into());
+    }
+
+    fn set_text_color(node: &HeadlessNode, color: Option<&Color>) {
+        node.borrow_mut().text_color = color.cloned();
+    }
+
+    fn set_typography(node: &HeadlessNode, typography: &Typography) {
+        node.borrow_mut().typography = Some(typography.clone());
+    }
+
+    fn set_row_source(node: &HeadlessNode, source: RowSourceRef<HeadlessNode>) {
+        node.borrow_mut().row_source = Some(source);
+    }
+
+    fn reload_rows(node: &HeadlessNode) {
+        let source = match node.borrow().row_source {
+            Some(ref source) => source.clone(),
+            None => { return; }
+        };
+
+        let previous = mem::take(&mut node.borrow_mut().rows);
+        let mut source = source.borrow_mut();
+        let rows = (0..source.number_of_rows()).map(|index| {
+            source.row(index, previous.get(index)).clone()
+        }).collect();
+
+        node.borrow_mut().rows = rows;
+    }
+
+    fn activate_constraints(node: &HeadlessNode, constraints: &[(&Constraint, Option<&HeadlessNode>)]) {
+        let mut node = node.borrow_mut();
+        for &(constraint, target) in constraints {
+            if constraint.target.is_some() && target.is_none() {
+                continue;
+            }
+
+            node.constraints.retain(|existing| existing.attribute != constraint.attribute);
+            node.constraints.push(constraint.clone());
+        }
+    }
+
+    fn deactivate_constraint(node: &HeadlessNode, attribute: Attribute) {
+        node.borrow_mut().constraints.retain(|existing| existing.attribute != attribute);
+    }
+
+    fn create_window(title: &str, frame: &Rect, content: &HeadlessNode) -> HeadlessWindow {
+        Rc::new(RefCell::new(WindowData {
+            title: title.into(),
+            frame: *frame,
+            background_color: None,
+            content: content.clone(),
+            is_key: false
+        }))
+    }
+
+    fn set_window_frame(window: &HeadlessWindow, frame: &Rect) {
+        window.borrow_mut().frame = *frame;
+    }
+
+    fn set_window_background_color(window: &HeadlessWindow, color: &Color) {
+        window.borrow_mut().background_color = Some(*color);
+    }
+
+    fn make_key_window(window: &HeadlessWindow) {
+        window.borrow_mut().is_key = true;
+    }
+
+    fn on_window_resize(window: &HeadlessWindow, callback: Box<dyn FnMut(Rect)>) {
+        RESIZE_HANDLERS.with(|handlers| handlers.borrow_mut().push((window.clone(), callback)));
+    }
+
+    fn create_app() -> HeadlessApp {
+        Rc::new(RefCell::new(AppData::default()))
+    }
+
+    /// There's no event loop to sit in, so this returns right away.
+    fn run_app(app: &HeadlessApp) {
+        app.borrow_mut().has_run = true;
+    }
+
+    /// Nothing fires on its own; see `fire_timers`.
+    fn schedule_repeating(_interval: f64, callback: Box<dyn FnMut()>) {
+        TIMERS.with(|timers| timers.borrow_mut().push(callback));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+
+    use appearance::Shadow;
+    use color::Color;
+    use label::{FontWeight, Label};
+    use layout::{AnchorError, Attribute, Constraint};
+    use listview::{ListView, ListViewData, ListViewRow};
+    use view::{View, ViewKind};
+
+    fn tree() -> View {
+        View::named("root").subviews(vec![
+            View::named("sidebar"),
+            Label::named("title")
+        ])
+    }
+
+    #[test]
+    fn records_subviews() {
+        let root = tree();
+        let node = root.backing_node.borrow();
+        let children: Vec<(String, ViewKind)> = node.subviews.iter().map(|child| {
+            let child = child.borrow();
+            (child.name.clone(), child.kind.clone())
+        }).collect();
+
+        assert_eq!(node.name, "root");
+        assert_eq!(children, vec![("sidebar".to_string(), ViewKind::View), ("title".to_string(), ViewKind::Label)]);
+    }
+
+    #[test]
+    fn records_background_colors() {
+        let mut root = tree();
+        let mut styles = json!({
+            "root": {"backgroundColor": {"r": 35, "g": 108, "b": 218}},
+            "sidebar": {"backgroundColor": {"r": 5, "g": 5, "b": 5}},
+            "title": {"backgroundColor": {"r": 255, "g": 255, "b": 255}}
+        });
+        root.apply_styles(styles.as_object_mut().unwrap()).unwrap();
+
+        assert_eq!(root.backing_node.borrow().background_color, Some(Color::rgb(35, 108, 218)));
+        assert_eq!(root.subviews[0].backing_node.borrow().background_color, Some(Color::rgb(5, 5, 5)));
+        assert_eq!(root.subviews[1].backing_node.borrow().background_color, Some(Color::rgb(255, 255, 255)));
+    }
+
+    #[test]
+    fn records_activated_constraints() {
+        let mut root = tree();
+        let parent = View::named("root");
+        root.subviews[0].set_width(200.);
+        root.subviews[0].top_relative_to(&parent, 0);
+        root.subviews[0].bottom_relative_to(&parent, 8);
+
+        // Nothing's installed until constraints are activated.
+        assert!(root.subviews[0].backing_node.borrow().constraints.is_empty());
+
+        root.activate_constraints();
+        let constraints = root.subviews[0].backing_node.borrow().constraints.clone();
+        assert_eq!(constraints, vec![
+            Constraint::constant(Attribute::Width, 200.),
+            Constraint::relative(Attribute::Top, "root", Attribute::Top, 0.),
+            Constraint::relative(Attribute::Bottom, "root", Attribute::Bottom, -8.)
+        ]);
+        assert!(root.subviews[1].backing_node.borrow().constraints.is_empty());
+    }
+
+    #[test]
+    fn records_anchors_from_styles() {
+        let mut root = tree();
+        let mut styles = json!({
+            "root": {"backgroundColor": {"r": 0, "g": 0, "b": 0}},
+            "sidebar": {"backgroundColor": {"r": 0, "g": 0, "b": 0}, "top": "root.top", "left": "root.left + 8"},
+            "title": {"backgroundColor": {"r": 0, "g": 0, "b": 0}, "left": "sidebar.right", "bottom": "root.bottom - 8"}
+        });
+        root.apply_styles(styles.as_object_mut().unwrap()).unwrap();
+        root.activate_constraints();
+
+        assert_eq!(root.subviews[0].backing_node.borrow().constraints, vec![
+            Constraint::relative(Attribute::Top, "root", Attribute::Top, 0.),
+            Constraint::relative(Attribute::Leading, "root", Attribute::Leading, 8.)
+        ]);
+        assert_eq!(root.subviews[1].backing_node.borrow().constraints, vec![
+            Constraint::relative(Attribute::Leading, "sidebar", Attribute::Trailing, 0.),
+            Constraint::relative(Attribute::Bottom, "root", Attribute::Bottom, -8.)
+        ]);
+    }
+
+    #[test]
+    fn rejects_anchors_to_unknown_views() {
+        let mut root = tree();
+        let mut styles = json!({
+            "root": {"backgroundColor": {"r": 0, "g": 0, "b": 0}},
+            "sidebar": {"backgroundColor": {"r": 0, "g": 0, "b": 0}, "top": "header.bottom"},
+            "title": {"backgroundColor": {"r": 0, "g": 0, "b": 0}}
+        });
+
+        let e = root.apply_styles(styles.as_object_mut().unwrap()).unwrap_err();
+        assert_eq!(e.json_path(), "sidebar.top");
+        assert_eq!(e.message, AnchorError::UnknownView("header".into()).to_string());
+    }
+
+    #[test]
+    fn replaces_constraints_per_attribute() {
+        let mut root = tree();
+        root.subviews[0].set_width(200.);
+        root.activate_constraints();
+        root.subviews[0].set_width(120.);
+        root.activate_constraints();
+
+        assert_eq!(root.subviews[0].backing_node.borrow().constraints, vec![Constraint::constant(Attribute::Width, 120.)]);
+    }
+
+    #[test]
+    fn skips_constraints_to_views_that_arent_there() {
+        let mut root = tree();
+        let elsewhere = View::named("elsewhere");
+        root.subviews[0].top_relative_to(&elsewhere, 0);
+        root.activate_constraints();
+
+        assert!(root.subviews[0].backing_node.borrow().constraints.is_empty());
+    }
+
+    struct Items {
+        items: Rc<RefCell<Vec<&'static str>>>
+    }
+
+    impl ListViewData for Items {
+        fn number_of_items(&self) -> usize {
+            self.items.borrow().len()
+        }
+
+        fn layout_item(&mut self, row: &mut ListViewRow) {
+            row.view.add_subview(&Label::named("text"));
+        }
+
+        fn configure_item(&mut self, row: &mut ListViewRow) {
+            row.view.set_text(self.items.borrow()[row.index]);
+        }
+    }
+
+    fn row_texts(list: &View) -> Vec<Option<String>> {
+        list.backing_node.borrow().rows.iter().map(|row| row.borrow().text.clone()).collect()
+    }
+
+    #[test]
+    fn records_list_rows() {
+        let items = Rc::new(RefCell::new(vec!["one", "two"]));
+        let list = ListView::named("inbox", Items { items: items.clone() });
+
+        let node = list.backing_node.borrow();
+        assert_eq!(node.kind, ViewKind::ListView);
+        assert_eq!(node.rows.iter().map(|row| row.borrow().name.clone()).collect::<Vec<_>>(), vec!["inbox-row-0", "inbox-row-1"]);
+        assert_eq!(node.rows[0].borrow().subviews.len(), 1);
+        drop(node);
+
+        assert_eq!(row_texts(&list), vec![Some("one".to_string()), Some("two".to_string())]);
+    }
+
+    #[test]
+    fn reuses_rows_when_reloading() {
+        let items = Rc::new(RefCell::new(vec!["one", "two"]));
+        let list = ListView::named("inbox", Items { items: items.clone() });
+        let first = list.backing_node.borrow().rows[0].clone();
+
+        items.borrow_mut().remove(0);
+        list.reload_data();
+        assert!(Rc::ptr_eq(&list.backing_node.borrow().rows[0], &first));
+        assert_eq!(row_texts(&list), vec![Some("two".to_string())]);
+
+        // Coming back to two items needs a row that was never handed back, so it's new.
+        items.borrow_mut().push("three");
+        list.reload_data();
+        let node = list.backing_node.borrow();
+        assert_eq!(node.rows.iter().map(|row| row.borrow().name.clone()).collect::<Vec<_>>(), vec!["inbox-row-0", "inbox-row-2"]);
+        assert_eq!(node.rows[0].borrow().subviews.len(), 1);
+    }
+
+    #[test]
+    fn records_typography_and_text_color_separately() {
+        let mut root = tree();
+        let old = json!({"title": {"textColor": "#236cda", "fontSize": 18, "fontWeight": "bold"}});
+        let new = json!({"title": {"fontSize": 18, "fontWeight": "bold"}});
+        let recolored = json!({"title": {"textColor": "red", "fontSize": 18, "fontWeight": "bold"}});
+        root.apply_styles(old.as_object().unwrap()).unwrap();
+
+        let typography = root.subviews[1].backing_node.borrow().typography.clone().unwrap();
+        assert_eq!((typography.font_size, typography.font_weight), (Some(18.), FontWeight::Bold));
+        assert_eq!(root.subviews[1].text_color, Some(Color::rgb(35, 108, 218)));
+        assert_eq!(root.subviews[1].backing_node.borrow().text_color, Some(Color::rgb(35, 108, 218)));
+
+        // Only the text color goes to the backend when that's all that changed.
+        root.subviews[1].backing_node.borrow_mut().typography = None;
+        root.apply_changed_styles(old.as_object().unwrap(), recolored.as_object().unwrap()).unwrap();
+        assert_eq!(root.subviews[1].backing_node.borrow().text_color, Some(Color::rgb(255, 0, 0)));
+        assert_eq!(root.subviews[1].backing_node.borrow().typography, None);
+
+        root.apply_changed_styles(recolored.as_object().unwrap(), new.as_object().unwrap()).unwrap();
+        assert_eq!(root.subviews[1].text_color, None);
+        assert_eq!(root.subviews[1].backing_node.borrow().text_color, None);
+        assert_eq!(root.subviews[1].backing_node.borrow().typography, None);
+    }
+
+    #[test]
+    fn records_appearance_and_resets_removed_properties() {
+        let mut root = tree();
+        let old = json!({"sidebar": {
+            "borderWidth": 1, "borderColor": "red", "cornerRadius": 6, "opacity": 0.5,
+            "shadow": {"color": "black", "offsetY": 2, "blur": 4}
+        }});
+        let new = json!({"sidebar": {"borderWidth": 1, "borderColor": "red"}});
+        root.apply_styles(old.as_object().unwrap()).unwrap();
+
+        let appearance = root.subviews[0].backing_node.borrow().appearance.clone().unwrap();
+        assert_eq!((appearance.border_width, appearance.border_color), (1., Some(Color::rgb(255, 0, 0))));
+        assert_eq!((appearance.corner_radius, appearance.opacity), (6., 0.5));
+        assert_eq!(appearance.shadow, Some(Shadow::new(Color::rgb(0, 0, 0), 0., 2., 4.)));
+
+        root.apply_changed_styles(old.as_object().unwrap(), new.as_object().unwrap()).unwrap();
+        let appearance = root.subviews[0].backing_node.borrow().appearance.clone().unwrap();
+        assert_eq!((appearance.border_width, appearance.corner_radius, appearance.opacity), (1., 0., 1.));
+        assert_eq!(appearance.shadow, None);
+    }
+
+    #[test]
+    fn rejects_bad_appearance_values() {
+        let mut root = tree();
+        let error = root.apply_styles(json!({"sidebar": {"opacity": 2}}).as_object().unwrap()).unwrap_err();
+        assert_eq!(error.json_path(), "sidebar.opacity");
+
+        let error = root.apply_styles(json!({"sidebar": {"shadow": {"blur": -1}}}).as_object().unwrap()).unwrap_err();
+        assert_eq!(error.json_path(), "sidebar.shadow.blur");
+    }
+}
@@ -0,0 +1,116 @@
+//! backend/mod.rs
+//!
+//! Everything that actually has to talk to a platform lives behind the `Backend`
+//! trait, so the rest of the framework (views, stylesheets, layout) can stay plain
+//! Rust. Cocoa is gated behind the `cocoa` feature; without it, the headless backend
+//! is used, which just records the node tree in memory. That's mostly useful for
+//! running things somewhere AppKit doesn't exist, like a Linux CI box.
+//!
+//! Backends are zero-sized and chosen at compile time, so there's no dispatch cost
+//! and no backend object to thread around - `Platform` is simply whichever one is
+//! active.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/02/2018
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use appearance::Appearance;
+use color::Color;
+use label::Typography;
+use layout::{Attribute, Constraint, Rect};
+use view::ViewKind;
+
+#[cfg(feature = "cocoa")]
+pub mod cocoa;
+pub mod headless;
+
+#[cfg(feature = "cocoa")]
+pub use self::cocoa::Cocoa as Platform;
+
+#[cfg(not(feature = "cocoa"))]
+pub use self::headless::Headless as Platform;
+
+pub type Node = <Platform as Backend>::Node;
+pub type WindowHandle = <Platform as Backend>::Window;
+pub type AppHandle = <Platform as Backend>::App;
+
+/// What a list node asks for its rows. Backends that virtualize (Cocoa) only ask for
+/// the rows that are on screen, and hand back a row node that scrolled out of view so
+/// it can be reused instead of building a new one.
+pub trait RowSource<N> {
+    fn number_of_rows(&self) -> usize;
+
+    /// Returns the node for the row at `index`, configured for that row. `reusing` is
+    /// a node this source handed out before, if the backend has one to spare.
+    fn row(&mut self, index: usize, reusing: Option<&N>) -> &N;
+}
+
+impl<N> Debug for dyn RowSource<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RowSource {{ rows: {} }}", self.number_of_rows())
+    }
+}
+
+pub type RowSourceRef<N> = Rc<RefCell<dyn RowSource<N>>>;
+
+pub trait Backend {
+    type Node: Debug;
+    type Window: Debug;
+    type App: Debug;
+
+    /// Creates the platform node backing a view of the given kind.
+    fn create_node(kind: &ViewKind, name: &str) -> Self::Node;
+    fn add_subview(parent: &Self::Node, child: &Self::Node);
+
+    /// Whether two handles point at the same platform node.
+    fn same_node(a: &Self::Node, b: &Self::Node) -> bool;
+
+    /// Adds `child` below whichever subview of `parent` is at `index`, or on top
+    /// of them all if `index` is past the end.
+    fn insert_subview(parent: &Self::Node, child: &Self::Node, index: usize);
+    fn remove_subview(parent: &Self::Node, child: &Self::Node);
+    fn set_background_color(node: &Self::Node, color: &Color);
+
+    /// Sets borders, corner radius, opacity and shadow, all at once.
+    fn set_appearance(node: &Self::Node, appearance: &Appearance);
+    fn set_text(node: &Self::Node, text: &str);
+
+    /// Sets a label's text color; None goes back to the platform's default.
+    fn set_text_color(node: &Self::Node, color: Option<&Color>);
+
+    /// Sets the font, spacing, alignment and line handling for a label's text. Text
+    /// set afterwards keeps the same typography.
+    fn set_typography(node: &Self::Node, typography: &Typography);
+
+    /// Hands a list node the source it pulls rows from. Nothing is fetched until the
+    /// rows are reloaded.
+    fn set_row_source(node: &Self::Node, source: RowSourceRef<Self::Node>);
+    fn reload_rows(node: &Self::Node);
+
+    /// Installs constraints on a node, replacing any previously active constraint
+    /// for the same attribute. Relative constraints come paired with the node they
+    /// point at; if that couldn't be found, the constraint is skipped.
+    fn activate_constraints(node: &Self::Node, constraints: &[(&Constraint, Option<&Self::Node>)]);
+
+    /// Deactivates and forgets the constraint for an attribute, if one is active.
+    fn deactivate_constraint(node: &Self::Node, attribute: Attribute);
+
+    fn create_window(title: &str, frame: &Rect, content: &Self::Node) -> Self::Window;
+    fn set_window_frame(window: &Self::Window, frame: &Rect);
+    fn set_window_background_color(window: &Self::Window, color: &Color);
+    fn make_key_window(window: &Self::Window);
+
+    /// Calls `callback` with the new frame whenever the window gets resized.
+    fn on_window_resize(window: &Self::Window, callback: Box<dyn FnMut(Rect)>);
+
+    fn create_app() -> Self::App;
+    fn run_app(app: &Self::App);
+
+    /// Calls `callback` every `interval` seconds on the main thread, for as long as
+    /// the app runs.
+    fn schedule_repeating(interval: f64, callback: Box<dyn FnMut()>);
+}
@@ -0,0 +1,499 @@
+//! color/mod.rs
+//!
+//! Interface that wraps [NS/UI]Color depending on the platform. Decidedly
+//! basic as I don't care to get into the whole colorspace issue, and would
+//! rather just be able to put color on a screen.
+//!
+//! Stylesheets can write colors pretty much however they would in CSS: hex
+//! (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`), named colors (`"tomato"`),
+//! `rgb()`/`rgba()`, `hsl()`/`hsla()`, or an object with `r`, `g`, `b`
+//! (0 - 255) and an optional `a` (0 - 1).
+//!
+//! Colors can also be derived from other colors, both in code and in
+//! stylesheets, so designers can hand over a few base colors and get
+//! hover/pressed/disabled shades out of them: `lighten(c, 10%)`,
+//! `darken(c, 10%)`, `saturate(c, 10%)`, `desaturate(c, 10%)`,
+//! `mix(a, b, 25%)` and `fade(c, 50%)`. Arguments can be any color
+//! expression, including other functions.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 05/23/2018
+
+use serde_json::Value;
+use stylesheet::StyleError;
+
+mod named;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64
+}
+
+#[allow(dead_code)]
+impl Color {
+    pub fn rgb(r: i32, g: i32, b: i32) -> Self {
+        Color {
+            r: r as f64 / 255.0,
+            g: g as f64 / 255.0,
+            b: b as f64 / 255.0,
+            a: 1.
+        }
+    }
+
+    pub fn rgba(r: i32, g: i32, b: i32, a: f64) -> Self {
+        Color { a: clamp_unit(a), ..Color::rgb(r, g, b) }
+    }
+
+    /// Builds a color from hue (degrees), saturation and lightness (both 0 - 1).
+    pub fn hsla(h: f64, s: f64, l: f64, a: f64) -> Self {
+        let h = ((h % 360.) + 360.) % 360. / 360.;
+        let s = clamp_unit(s);
+        let l = clamp_unit(l);
+
+        if s == 0. {
+            return Color { r: l, g: l, b: l, a: clamp_unit(a) };
+        }
+
+        let q = if l < 0.5 { l * (1. + s) } else { l + s - l * s };
+        let p = 2. * l - q;
+
+        Color {
+            r: hue_to_channel(p, q, h + 1. / 3.),
+            g: hue_to_channel(p, q, h),
+            b: hue_to_channel(p, q, h - 1. / 3.),
+            a: clamp_unit(a)
+        }
+    }
+
+    /// Returns (hue in degrees, saturation, lightness, alpha).
+    pub fn to_hsla(&self) -> (f64, f64, f64, f64) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = (max + min) / 2.;
+
+        if max == min {
+            return (0., 0., l, self.a);
+        }
+
+        let d = max - min;
+        let s = if l > 0.5 { d / (2. - max - min) } else { d / (max + min) };
+        let h = if max == self.r {
+            (self.g - self.b) / d + if self.g < self.b { 6. } else { 0. }
+        } else if max == self.g {
+            (self.b - self.r) / d + 2.
+        } else {
+            (self.r - self.g) / d + 4.
+        };
+
+        (h * 60., s, l, self.a)
+    }
+
+    /// Raises lightness by `amount` (0 - 1), same as Sass' `lighten`.
+    pub fn lighten(&self, amount: f64) -> Self {
+        let (h, s, l, a) = self.to_hsla();
+        Color::hsla(h, s, l + amount, a)
+    }
+
+    pub fn darken(&self, amount: f64) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// Raises saturation by `amount` (0 - 1).
+    pub fn saturate(&self, amount: f64) -> Self {
+        let (h, s, l, a) = self.to_hsla();
+        Color::hsla(h, s + amount, l, a)
+    }
+
+    pub fn desaturate(&self, amount: f64) -> Self {
+        self.saturate(-amount)
+    }
+
+    /// Mixes two colors, channel by channel. `weight` is how much of `self`
+    /// ends up in the result, so 0.5 is an even mix.
+    pub fn mix(&self, other: &Color, weight: f64) -> Self {
+        let weight = clamp_unit(weight);
+        let blend = |a: f64, b: f64| a * weight + b * (1. - weight);
+
+        Color {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+            a: blend(self.a, other.a)
+        }
+    }
+
+    pub fn with_alpha(&self, a: f64) -> Self {
+        Color { a: clamp_unit(a), ..*self }
+    }
+
+    /// Composites this color over `background` (Porter-Duff "source over"),
+    /// which is what ends up on screen when a translucent view sits on top
+    /// of another.
+    pub fn over(&self, background: &Color) -> Self {
+        let a = self.a + background.a * (1. - self.a);
+        if a == 0. {
+            return Color { r: 0., g: 0., b: 0., a: 0. };
+        }
+
+        let composite = |fg: f64, bg: f64| (fg * self.a + bg * background.a * (1. - self.a)) / a;
+
+        Color {
+            r: composite(self.r, background.r),
+            g: composite(self.g, background.g),
+            b: composite(self.b, background.b),
+            a
+        }
+    }
+
+    /// Relative luminance, as defined by WCAG 2. Alpha is ignored; composite
+    /// over a background first if that matters.
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = |channel: f64| {
+            if channel <= 0.03928 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// The WCAG contrast ratio between two colors, from 1 (none) to 21 (black
+    /// on white). 4.5 is the usual minimum for body text.
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    pub fn from_json(obj: &Value) -> Result<Self, StyleError> {
+        match *obj {
+            Value::String(ref value) => Color::parse(value),
+            Value::Object(_) => {
+                let channel = |key: &str| obj[key].as_f64().ok_or_else(|| {
+                    StyleError::new(format!("expected a number, found {}", obj[key])).at(key)
+                });
+
+                let alpha = match obj.get("a") {
+                    Some(_) => channel("a")?,
+                    None => 1.
+                };
+
+                Ok(Color {
+                    r: clamp_unit(channel("r")? / 255.0),
+                    g: clamp_unit(channel("g")? / 255.0),
+                    b: clamp_unit(channel("b")? / 255.0),
+                    a: clamp_unit(alpha)
+                })
+            },
+
+            _ => Err(StyleError::new(format!("expected a color string or an object like {{\"r\": 35, \"g\": 108, \"b\": 218}}, found {}", obj)))
+        }
+    }
+
+    /// Parses a CSS-ish color string: hex, a named color, one of the
+    /// rgb/rgba/hsl/hsla functions, or one of the manipulation functions.
+    pub fn parse(value: &str) -> Result<Self, StyleError> {
+        let value = value.trim();
+
+        if let Some(hex) = value.strip_prefix('#') {
+            return Color::from_hex(hex).ok_or_else(|| {
+                StyleError::new(format!("'{}' is not a valid hex color, expected #rgb, #rgba, #rrggbb or #rrggbbaa", value))
+            });
+        }
+
+        if let Some(open) = value.find('(') {
+            if !value.ends_with(')') {
+                return Err(StyleError::new(format!("'{}' is missing a closing parenthesis", value)));
+            }
+
+            let function = value[..open].trim().to_lowercase();
+            let arguments = split_arguments(&value[open + 1..value.len() - 1]);
+            return Color::from_function(&function, &arguments).map_err(|message| {
+                StyleError::new(format!("could not parse '{}', {}", value, message))
+            });
+        }
+
+        let name = value.to_lowercase();
+        if name == "transparent" {
+            return Ok(Color { r: 0., g: 0., b: 0., a: 0. });
+        }
+
+        match named::lookup(&name) {
+            Some(hex) => Ok(Color::from_rgb_u32(hex, 1.)),
+            None => Err(StyleError::new(format!("'{}' is not a color", value)))
+        }
+    }
+
+    fn from_rgb_u32(hex: u32, a: f64) -> Self {
+        Color::rgba(((hex >> 16) & 0xff) as i32, ((hex >> 8) & 0xff) as i32, (hex & 0xff) as i32, a)
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        // Short forms double up each digit, i.e, #f0a is #ff00aa.
+        let expanded: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| vec![c, c]).collect(),
+            6 | 8 => hex.into(),
+            _ => { return None; }
+        };
+
+        let value = u32::from_str_radix(&expanded, 16).ok()?;
+        if expanded.len() == 8 {
+            Some(Color::from_rgb_u32(value >> 8, (value & 0xff) as f64 / 255.))
+        } else {
+            Some(Color::from_rgb_u32(value, 1.))
+        }
+    }
+
+    fn from_function(function: &str, arguments: &[&str]) -> Result<Self, String> {
+        match function {
+            "lighten" | "darken" | "saturate" | "desaturate" | "fade" => {
+                if arguments.len() != 2 {
+                    return Err(format!("expected a color and an amount, found {} arguments", arguments.len()));
+                }
+
+                let color = parse_color_argument(arguments[0])?;
+                let amount = parse_fraction(arguments[1])?;
+                return Ok(match function {
+                    "lighten" => color.lighten(amount),
+                    "darken" => color.darken(amount),
+                    "saturate" => color.saturate(amount),
+                    "desaturate" => color.desaturate(amount),
+                    _ => color.with_alpha(amount)
+                });
+            },
+
+            "mix" => {
+                let weight = match arguments.len() {
+                    2 => 0.5,
+                    3 => parse_fraction(arguments[2])?,
+                    count => { return Err(format!("expected two colors and an optional weight, found {} arguments", count)); }
+                };
+
+                let color = parse_color_argument(arguments[0])?;
+                return Ok(color.mix(&parse_color_argument(arguments[1])?, weight));
+            },
+
+            _ => {}
+        }
+
+        let alpha = match arguments.len() {
+            3 => 1.,
+            4 => parse_fraction(arguments[3])?,
+            count => { return Err(format!("expected 3 or 4 arguments, found {}", count)); }
+        };
+
+        match function {
+            "rgb" | "rgba" => Ok(Color {
+                r: parse_rgb_channel(arguments[0])?,
+                g: parse_rgb_channel(arguments[1])?,
+                b: parse_rgb_channel(arguments[2])?,
+                a: alpha
+            }),
+
+            "hsl" | "hsla" => {
+                let hue = arguments[0].trim_end_matches("deg").trim();
+                let hue: f64 = hue.parse().map_err(|_| format!("'{}' is not a hue", arguments[0]))?;
+                Ok(Color::hsla(hue, parse_percentage(arguments[1])?, parse_percentage(arguments[2])?, alpha))
+            },
+
+            _ => Err(format!("'{}' is not a color function, expected one of rgb, rgba, hsl, hsla, lighten, darken, saturate, desaturate, mix or fade", function))
+        }
+    }
+}
+
+fn clamp_unit(value: f64) -> f64 {
+    value.clamp(0., 1.)
+}
+
+fn hue_to_channel(p: f64, q: f64, t: f64) -> f64 {
+    let t = if t < 0. { t + 1. } else if t > 1. { t - 1. } else { t };
+
+    if t < 1. / 6. {
+        p + (q - p) * 6. * t
+    } else if t < 1. / 2. {
+        q
+    } else if t < 2. / 3. {
+        p + (q - p) * (2. / 3. - t) * 6.
+    } else {
+        p
+    }
+}
+
+/// Splits function arguments on commas, or on whitespace (and `/` before the
+/// alpha) for the newer comma-less CSS syntax. Commas inside nested function
+/// calls are left alone, so `mix(rgb(0, 0, 0), red)` splits in two.
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut split = vec![];
+
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(arguments[start..index].trim());
+                start = index + 1;
+            },
+            _ => {}
+        }
+    }
+
+    if split.is_empty() {
+        return arguments.split(|c: char| c.is_whitespace() || c == '/').filter(|argument| !argument.is_empty()).collect();
+    }
+
+    split.push(arguments[start..].trim());
+    split
+}
+
+fn parse_color_argument(value: &str) -> Result<Color, String> {
+    Color::parse(value).map_err(|e| e.message)
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value.trim().parse().map_err(|_| format!("'{}' is not a number", value.trim()))
+}
+
+/// A percentage ("50%") as 0 - 1.
+fn parse_percentage(value: &str) -> Result<f64, String> {
+    match value.trim().strip_suffix('%') {
+        Some(number) => Ok(clamp_unit(parse_number(number)? / 100.)),
+        None => Err(format!("expected a percentage, found '{}'", value.trim()))
+    }
+}
+
+/// An rgb channel, either 0 - 255 or a percentage, as 0 - 1. Anything out of
+/// range is almost certainly a typo, so it's an error rather than clamped.
+fn parse_rgb_channel(value: &str) -> Result<f64, String> {
+    if value.trim().ends_with('%') {
+        return parse_percentage(value);
+    }
+
+    let channel = parse_number(value)?;
+    if !(0. ..=255.).contains(&channel) {
+        return Err(format!("'{}' is out of range, expected 0 - 255", value.trim()));
+    }
+
+    Ok(channel / 255.)
+}
+
+/// An alpha value or amount, either 0 - 1 or a percentage.
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    if value.trim().ends_with('%') {
+        parse_percentage(value)
+    } else {
+        Ok(clamp_unit(parse_number(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_color_strings() {
+        let cases = [
+            ("#236cda", Color::rgb(35, 108, 218)),
+            ("#FFF", Color::rgb(255, 255, 255)),
+            ("#f0a8", Color::rgba(255, 0, 170, 136. / 255.)),
+            ("#236cda80", Color::rgba(35, 108, 218, 128. / 255.)),
+            ("rebeccapurple", Color::rgb(102, 51, 153)),
+            ("White", Color::rgb(255, 255, 255)),
+            ("transparent", Color::rgba(0, 0, 0, 0.)),
+            ("rgb(35, 108, 218)", Color::rgb(35, 108, 218)),
+            ("rgb(100%, 0%, 0%)", Color::rgb(255, 0, 0)),
+            ("rgba(35, 108, 218, 0.5)", Color::rgba(35, 108, 218, 0.5)),
+            ("rgb(35 108 218 / 50%)", Color::rgba(35, 108, 218, 0.5)),
+            ("hsl(0, 100%, 50%)", Color::rgb(255, 0, 0)),
+            ("hsl(120deg, 100%, 25%)", Color { r: 0., g: 0.5, b: 0., a: 1. }),
+            ("hsla(240, 100%, 50%, 0.25)", Color::rgba(0, 0, 255, 0.25))
+        ];
+
+        for &(value, expected) in &cases {
+            assert_eq!(Color::parse(value), Ok(expected), "parsing {}", value);
+        }
+    }
+
+    #[test]
+    fn parses_json_colors() {
+        assert_eq!(Color::from_json(&json!("#236cda")), Ok(Color::rgb(35, 108, 218)));
+        assert_eq!(Color::from_json(&json!({"r": 35, "g": 108, "b": 218})), Ok(Color::rgb(35, 108, 218)));
+        assert_eq!(Color::from_json(&json!({"r": 35, "g": 108, "b": 218, "a": 0.5})), Ok(Color::rgba(35, 108, 218, 0.5)));
+        assert_eq!(Color::from_json(&json!({"r": 35, "g": "lots", "b": 218})).unwrap_err().json_path(), "g");
+        assert!(Color::from_json(&json!(12)).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_colors() {
+        let cases = [
+            ("#12345", "'#12345' is not a valid hex color, expected #rgb, #rgba, #rrggbb or #rrggbbaa"),
+            ("#ggg", "'#ggg' is not a valid hex color, expected #rgb, #rgba, #rrggbb or #rrggbbaa"),
+            ("rgb(300,0,0)", "could not parse 'rgb(300,0,0)', '300' is out of range, expected 0 - 255"),
+            ("rgb(1, 2)", "could not parse 'rgb(1, 2)', expected 3 or 4 arguments, found 2"),
+            ("rgb(1, 2, 3", "'rgb(1, 2, 3' is missing a closing parenthesis"),
+            ("hsl(red, 50%, 50%)", "could not parse 'hsl(red, 50%, 50%)', 'red' is not a hue"),
+            ("hsl(0, 50, 50%)", "could not parse 'hsl(0, 50, 50%)', expected a percentage, found '50'"),
+            ("cmyk(0, 0, 0)", "could not parse 'cmyk(0, 0, 0)', 'cmyk' is not a color function, expected one of rgb, rgba, hsl, hsla, lighten, darken, saturate, desaturate, mix or fade"),
+            ("blurple", "'blurple' is not a color"),
+            ("lighten(red)", "could not parse 'lighten(red)', expected a color and an amount, found 1 arguments")
+        ];
+
+        for &(value, message) in &cases {
+            assert_eq!(Color::parse(value).map_err(|e| e.message), Err(message.to_string()), "parsing {}", value);
+        }
+    }
+
+    fn assert_close(actual: Color, expected: Color) {
+        let channels = [(actual.r, expected.r), (actual.g, expected.g), (actual.b, expected.b), (actual.a, expected.a)];
+        assert!(channels.iter().all(|&(a, b)| (a - b).abs() < 0.005), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn manipulates_colors() {
+        let red = Color::rgb(255, 0, 0);
+        let cases = [
+            (red.lighten(0.25), Color::rgb(255, 128, 128)),
+            (red.darken(0.25), Color::rgb(0, 0, 0).mix(&red, 0.5)),
+            (red.desaturate(1.), Color::rgb(128, 128, 128)),
+            (Color::rgb(191, 64, 64).saturate(0.5), Color::rgb(255, 0, 0)),
+            (red.mix(&Color::rgb(0, 0, 255), 0.25), Color::rgb(64, 0, 191)),
+            (red.with_alpha(0.5), Color::rgba(255, 0, 0, 0.5)),
+            (Color::parse("mix(rgb(0, 0, 0), white)").unwrap(), Color::rgb(128, 128, 128)),
+            (Color::parse("fade(darken(#f00, 50%), 25%)").unwrap(), Color::rgba(0, 0, 0, 0.25))
+        ];
+
+        for &(actual, expected) in &cases {
+            assert_close(actual, expected);
+        }
+
+        assert_eq!(Color::rgb(255, 0, 0).to_hsla(), (0., 1., 0.5, 1.));
+    }
+
+    #[test]
+    fn composites_colors() {
+        let white = Color::rgb(255, 255, 255);
+        assert_close(Color::rgba(0, 0, 0, 0.5).over(&white), Color::rgb(128, 128, 128));
+        assert_close(Color::rgb(0, 0, 255).over(&white), Color::rgb(0, 0, 255));
+        assert_close(Color::rgba(255, 0, 0, 0.5).over(&Color::rgba(0, 0, 255, 0.5)), Color::rgba(170, 0, 85, 0.75));
+        assert_eq!(Color::rgba(255, 0, 0, 0.).over(&Color::rgba(0, 0, 255, 0.)), Color::rgba(0, 0, 0, 0.));
+    }
+
+    #[test]
+    fn measures_contrast() {
+        let (black, white) = (Color::rgb(0, 0, 0), Color::rgb(255, 255, 255));
+        assert_eq!(black.contrast_ratio(&white), 21.);
+        assert_eq!(white.contrast_ratio(&black), 21.);
+        assert_eq!(white.contrast_ratio(&white), 1.);
+        assert!((Color::rgb(118, 118, 118).contrast_ratio(&white) - 4.54).abs() < 0.01);
+    }
+}
@@ -1,91 +1,253 @@
-//! text.rs
+//! label.rs
 //!
-//! A class that wraps NSTextField and/or UILabel to make them act pretty
+//! Labels wrap NSTextField and/or UILabel to make them act pretty
 //! much the same across platforms. Believe it or not... this is a thing.
+//! They're just Views of kind `ViewKind::Label`; the backend takes care
+//! of creating the right native widget.
+//!
+//! Labels pick up typography from stylesheets: `fontFamily`, `fontSize`,
+//! `fontWeight` (a name like "semibold", or 100 - 900), `fontStyle`
+//! ("italic" or "normal"), `lineHeight` and `letterSpacing` (points),
+//! `textAlign` ("left", "center", "right", "justify", "natural"),
+//! `lineLimit` (0 for no limit), `truncation` ("none", "clip", "head",
+//! "middle", "tail") and `textColor`. Views that aren't Labels ignore them.
 //!
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use std::sync::{Once, ONCE_INIT};
+use serde_json::Value;
 
-use objc_id::Id;
-use objc::declare::ClassDecl;
-use objc::runtime::{Class, Object};
+use backend::{Backend, Platform};
+use color::Color;
+use stylesheet::{StyleError, optional_number, optional_str};
+use view::{View, ViewKind};
 
-use cocoa::base::{id, nil, YES, NO};
-use cocoa::foundation::NSString;
+/// Every typography property a stylesheet can set on a Label.
+pub const TYPOGRAPHY_PROPERTIES: [&str; 9] = [
+    "fontFamily", "fontSize", "fontWeight", "fontStyle", "lineHeight",
+    "letterSpacing", "textAlign", "lineLimit", "truncation"
+];
 
-use shinekit::color::Color;
-use shinekit::util::empty_frame;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontWeight {
+    UltraLight,
+    Thin,
+    Light,
+    Regular,
+    Medium,
+    Semibold,
+    Bold,
+    Heavy,
+    Black
+}
 
-pub struct Label;
+impl FontWeight {
+    /// Takes either a name ("semibold") or a CSS-style number (100 - 900).
+    pub fn from_json(value: &Value) -> Result<FontWeight, StyleError> {
+        if let Some(weight) = value.as_f64() {
+            let weights = [
+                FontWeight::UltraLight, FontWeight::Thin, FontWeight::Light, FontWeight::Regular, FontWeight::Medium,
+                FontWeight::Semibold, FontWeight::Bold, FontWeight::Heavy, FontWeight::Black
+            ];
 
-impl Label {
-    pub fn named(name: &str) -> Self {
-        View::named_of_kind_with_backing_node(name, ViewKind::Label, unsafe {
-            let alloc: id = msg_send![register_text_class(), alloc];
-            let view: id = msg_send![alloc, initWithFrame:empty_frame()];
-            msg_send![view, setTranslatesAutoresizingMaskIntoConstraints:NO];
-            msg_send![view, setEditable:NO];
-            msg_send![view, setBezeled:NO];
-            msg_send![view, setBordered:NO];
-            msg_send![view, setDrawsBackground:YES];
-            msg_send![view, setAllowsEditingTextAttributes:NO];
-            msg_send![view, setContentCompressionResistancePriority:250 forOrientation:0];
-            
-            let cell: id = msg_send![view, cell];
-            msg_send![cell, setUsesSingleLineMode:NO];
-            msg_send![cell, setWraps:YES];
-            msg_send![cell, setLineBreakMode:0];
-            Id::from_ptr(view)
-        })
+            let index = (weight / 100.).round() as i64 - 1;
+            return match weights.get(index as usize) {
+                Some(weight) if index >= 0 => Ok(*weight),
+                _ => Err(StyleError::new(format!("expected a font weight between 100 and 900, found {}", weight)))
+            };
+        }
+
+        match value.as_str() {
+            Some("ultraLight") => Ok(FontWeight::UltraLight),
+            Some("thin") => Ok(FontWeight::Thin),
+            Some("light") => Ok(FontWeight::Light),
+            Some("regular") | Some("normal") => Ok(FontWeight::Regular),
+            Some("medium") => Ok(FontWeight::Medium),
+            Some("semibold") => Ok(FontWeight::Semibold),
+            Some("bold") => Ok(FontWeight::Bold),
+            Some("heavy") => Ok(FontWeight::Heavy),
+            Some("black") => Ok(FontWeight::Black),
+            _ => Err(StyleError::new(format!("unknown font weight {}", value)))
+        }
     }
 }
 
-#[allow(dead_code)]
-impl Text {
-    pub fn new() -> Self {
-        Text {
-            backing_node: unsafe {
-            }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextAlignment {
+    Left,
+    Center,
+    Right,
+    Justified,
+    Natural
+}
+
+/// What happens to text that doesn't fit. `None` wraps it onto more lines (up to
+/// the label's line limit), the rest cut it off in various places.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Truncation {
+    None,
+    Clip,
+    Head,
+    Middle,
+    Tail
+}
+
+/// How a Label's text is set. `None` for the font family or size means the
+/// system font, at its default size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Typography {
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    pub font_weight: FontWeight,
+    pub italic: bool,
+    pub line_height: Option<f64>,
+    pub letter_spacing: f64,
+    pub alignment: TextAlignment,
+    pub line_limit: u32,
+    pub truncation: Truncation
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Typography {
+            font_family: None,
+            font_size: None,
+            font_weight: FontWeight::Regular,
+            italic: false,
+            line_height: None,
+            letter_spacing: 0.,
+            alignment: TextAlignment::Natural,
+            line_limit: 0,
+            truncation: Truncation::None
         }
     }
-    
-    pub fn set_background_color(&mut self, color: &Color) {
-        unsafe {
-            self.backing_node.set_ivar("shinekitBackgroundColor", color.into_platform_specific_color());
-            msg_send![&*self.backing_node, setNeedsDisplay:YES];
+}
+
+impl Typography {
+    /// Updates this from whichever typography properties a style object sets.
+    pub fn apply_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if let Some(family) = optional_str(style, "fontFamily")? {
+            self.font_family = Some(family.into());
         }
-    } 
 
-    pub fn set_text(&self, text: &str) {
-        unsafe {
-            let value = NSString::alloc(nil).init_str(text);
-            msg_send![&*self.backing_node, setStringValue:value];
+        if let Some(size) = optional_number(style, "fontSize")? {
+            self.font_size = Some(size);
+        }
+
+        if let Some(weight) = style.get("fontWeight").filter(|weight| !weight.is_null()) {
+            self.font_weight = FontWeight::from_json(weight).map_err(|e| e.at("fontWeight"))?;
+        }
+
+        self.italic = match optional_str(style, "fontStyle")? {
+            Some("italic") => true,
+            Some("normal") => false,
+            Some(other) => { return Err(StyleError::new(format!("expected \"italic\" or \"normal\", found \"{}\"", other)).at("fontStyle")); },
+            None => self.italic
+        };
+
+        if let Some(line_height) = optional_number(style, "lineHeight")? {
+            self.line_height = Some(line_height);
+        }
+
+        if let Some(spacing) = optional_number(style, "letterSpacing")? {
+            self.letter_spacing = spacing;
+        }
+
+        self.alignment = match optional_str(style, "textAlign")? {
+            Some("left") => TextAlignment::Left,
+            Some("center") => TextAlignment::Center,
+            Some("right") => TextAlignment::Right,
+            Some("justify") => TextAlignment::Justified,
+            Some("natural") => TextAlignment::Natural,
+            Some(other) => { return Err(StyleError::new(format!("unknown text alignment \"{}\"", other)).at("textAlign")); },
+            None => self.alignment
+        };
+
+        if let Some(limit) = optional_number(style, "lineLimit")? {
+            if limit < 0. || limit.fract() != 0. {
+                return Err(StyleError::new(format!("expected a whole number of lines, found {}", limit)).at("lineLimit"));
+            }
+
+            self.line_limit = limit as u32;
         }
+
+        self.truncation = match optional_str(style, "truncation")? {
+            Some("none") => Truncation::None,
+            Some("clip") => Truncation::Clip,
+            Some("head") => Truncation::Head,
+            Some("middle") => Truncation::Middle,
+            Some("tail") => Truncation::Tail,
+            Some(other) => { return Err(StyleError::new(format!("unknown truncation mode \"{}\"", other)).at("truncation")); },
+            None => self.truncation
+        };
+
+        Ok(())
     }
 
-    pub fn set_text_color(&self, color: &Color) {
-        unsafe {
-            msg_send![&*self.backing_node, setTextColor:color.into_platform_specific_color()];
+    /// Puts a single property back to its default.
+    pub fn reset(&mut self, property: &str) {
+        let default = Typography::default();
+        match property {
+            "fontFamily" => { self.font_family = default.font_family; },
+            "fontSize" => { self.font_size = default.font_size; },
+            "fontWeight" => { self.font_weight = default.font_weight; },
+            "fontStyle" => { self.italic = default.italic; },
+            "lineHeight" => { self.line_height = default.line_height; },
+            "letterSpacing" => { self.letter_spacing = default.letter_spacing; },
+            "textAlign" => { self.alignment = default.alignment; },
+            "lineLimit" => { self.line_limit = default.line_limit; },
+            "truncation" => { self.truncation = default.truncation; },
+            _ => {}
         }
     }
 }
 
-fn register_text_class() -> *const Class {
-    static mut text_class: *const Class = 0 as *const Class;
-    static INIT: Once = ONCE_INIT;
-
-    INIT.call_once(|| unsafe {
-        let superclass = Class::get("NSTextField").unwrap();
-        let mut decl = ClassDecl::new("ShineKitLabel", superclass).unwrap();
-        //decl.add_method(sel!(wantsUpdateLayer), enforce_normalcy as extern fn(&Object, _) -> BOOL);
-        //decl.add_method(sel!(updateLayer), update_layer as extern fn(&Object, _));
-        add_autolayout_ivars(&mut decl);
-        text_class = decl.register();
-    });
-
-    unsafe {
-        text_class
+pub struct Label;
+
+impl Label {
+    pub fn named(name: &str) -> View {
+        View::named_of_kind(name, ViewKind::Label)
+    }
+}
+
+impl View {
+    pub fn set_text(&self, text: &str) {
+        Platform::set_text(&self.backing_node, text);
+    }
+
+    pub fn set_text_color(&mut self, color: &Color) {
+        Platform::set_text_color(&self.backing_node, Some(color));
+        self.text_color = Some(*color);
+    }
+
+    /// Puts the text color back to the platform's default label color.
+    pub fn reset_text_color(&mut self) {
+        Platform::set_text_color(&self.backing_node, None);
+        self.text_color = None;
+    }
+
+    pub fn set_typography(&mut self, typography: Typography) {
+        Platform::set_typography(&self.backing_node, &typography);
+        self.typography = typography;
+    }
+
+    /// The Label half of `apply_styles`: typography and text color.
+    pub fn apply_text_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if self.kind != ViewKind::Label {
+            return Ok(());
+        }
+
+        if let Some(color) = style.get("textColor") {
+            let text_color = Color::from_json(color).map_err(|e| e.at("textColor"))?;
+            self.set_text_color(&text_color);
+        }
+
+        if TYPOGRAPHY_PROPERTIES.iter().any(|property| style.get(property).is_some()) {
+            let mut typography = self.typography.clone();
+            typography.apply_style(style)?;
+            self.set_typography(typography);
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,514 @@
+//! layout/flex.rs
+//!
+//! Flexbox, for rows and columns of views that shouldn't each need anchoring
+//! by hand. A view with `"display": "flex"` lays its children out itself:
+//!
+//! ```json
+//! "toolbar": {"display": "flex", "justifyContent": "space-between", "alignItems": "center", "gap": 8},
+//! "search": {"flexGrow": 1, "height": 28}
+//! ```
+//!
+//! Containers take `flexDirection` ("row", "row-reverse", "column",
+//! "column-reverse"), `flexWrap` ("nowrap", "wrap"), `justifyContent`
+//! ("flex-start", "flex-end", "center", "space-between", "space-around",
+//! "space-evenly"), `alignItems` ("stretch", "flex-start", "flex-end",
+//! "center") and `gap`. Children take `flexGrow`, `flexShrink`, `flexBasis`
+//! (a number, or "auto" for their width/height) and `alignSelf`. Their own
+//! anchors are ignored, but their width, height and margin still count.
+//!
+//! This is a subset of the CSS algorithm, worked out in plain Rust: there's
+//! no intrinsic content size, so anything without a basis or a size starts
+//! out at 0 and relies on growing or stretching.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/20/2018
+
+use serde_json::Value;
+
+use layout::{EdgeInsets, Rect};
+use stylesheet::{StyleError, optional_number, optional_str};
+use view::View;
+
+/// Every flex property a stylesheet can set, container and child alike.
+pub const FLEX_PROPERTIES: [&str; 9] = [
+    "flexDirection", "flexWrap", "justifyContent", "alignItems", "gap",
+    "flexGrow", "flexShrink", "flexBasis", "alignSelf"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse
+}
+
+impl Direction {
+    pub fn parse(direction: &str) -> Result<Direction, StyleError> {
+        match direction {
+            "row" => Ok(Direction::Row),
+            "row-reverse" => Ok(Direction::RowReverse),
+            "column" => Ok(Direction::Column),
+            "column-reverse" => Ok(Direction::ColumnReverse),
+            other => Err(StyleError::new(format!("'{}' isn't a direction, expected row, row-reverse, column or column-reverse", other)))
+        }
+    }
+
+    fn is_row(&self) -> bool {
+        matches!(*self, Direction::Row | Direction::RowReverse)
+    }
+
+    fn is_reversed(&self) -> bool {
+        matches!(*self, Direction::RowReverse | Direction::ColumnReverse)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Justify {
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly
+}
+
+impl Justify {
+    pub fn parse(justify: &str) -> Result<Justify, StyleError> {
+        match justify {
+            "flex-start" | "start" => Ok(Justify::Start),
+            "flex-end" | "end" => Ok(Justify::End),
+            "center" => Ok(Justify::Center),
+            "space-between" => Ok(Justify::SpaceBetween),
+            "space-around" => Ok(Justify::SpaceAround),
+            "space-evenly" => Ok(Justify::SpaceEvenly),
+            other => Err(StyleError::new(format!("'{}' isn't a justification, expected flex-start, flex-end, center, space-between, space-around or space-evenly", other)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Stretch,
+    Start,
+    End,
+    Center
+}
+
+impl Align {
+    pub fn parse(align: &str) -> Result<Align, StyleError> {
+        match align {
+            "stretch" => Ok(Align::Stretch),
+            "flex-start" | "start" => Ok(Align::Start),
+            "flex-end" | "end" => Ok(Align::End),
+            "center" => Ok(Align::Center),
+            other => Err(StyleError::new(format!("'{}' isn't an alignment, expected stretch, flex-start, flex-end or center", other)))
+        }
+    }
+}
+
+/// A view's flex settings: the first half apply when it's a flex container,
+/// the second half when it's inside one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flex {
+    pub direction: Direction,
+    pub wrap: bool,
+    pub justify: Justify,
+    pub align_items: Align,
+    pub gap: f64,
+
+    pub grow: f64,
+    pub shrink: f64,
+
+    /// Starting size along the main axis. None means "auto": the width or
+    /// height, whichever runs along the main axis.
+    pub basis: Option<f64>,
+
+    pub align_self: Option<Align>
+}
+
+impl Default for Flex {
+    fn default() -> Self {
+        Flex {
+            direction: Direction::Row,
+            wrap: false,
+            justify: Justify::Start,
+            align_items: Align::Stretch,
+            gap: 0.,
+            grow: 0.,
+            shrink: 1.,
+            basis: None,
+            align_self: None
+        }
+    }
+}
+
+impl Flex {
+    /// Updates this from whichever flex properties a style object sets.
+    pub fn apply_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if let Some(direction) = optional_str(style, "flexDirection")? {
+            self.direction = Direction::parse(direction).map_err(|e| e.at("flexDirection"))?;
+        }
+
+        if let Some(wrap) = optional_str(style, "flexWrap")? {
+            self.wrap = match wrap {
+                "wrap" => true,
+                "nowrap" => false,
+                other => { return Err(StyleError::new(format!("'{}' isn't a wrap mode, expected wrap or nowrap", other)).at("flexWrap")); }
+            };
+        }
+
+        if let Some(justify) = optional_str(style, "justifyContent")? {
+            self.justify = Justify::parse(justify).map_err(|e| e.at("justifyContent"))?;
+        }
+
+        if let Some(align) = optional_str(style, "alignItems")? {
+            self.align_items = Align::parse(align).map_err(|e| e.at("alignItems"))?;
+        }
+
+        if let Some(align) = optional_str(style, "alignSelf")? {
+            self.align_self = match align {
+                "auto" => None,
+                align => Some(Align::parse(align).map_err(|e| e.at("alignSelf"))?)
+            };
+        }
+
+        for (property, field) in [("gap", &mut self.gap), ("flexGrow", &mut self.grow), ("flexShrink", &mut self.shrink)] {
+            if let Some(value) = optional_number(style, property)? {
+                if value < 0. {
+                    return Err(StyleError::new(format!("expected 0 or more, found {}", value)).at(property));
+                }
+
+                *field = value;
+            }
+        }
+
+        match style.get("flexBasis") {
+            Some(Value::String(auto)) if auto == "auto" => { self.basis = None; },
+            Some(basis) if !basis.is_null() => {
+                self.basis = Some(basis.as_f64().filter(|&basis| basis >= 0.).ok_or_else(|| {
+                    StyleError::new(format!("expected \"auto\" or a number of 0 or more, found {}", basis)).at("flexBasis")
+                })?);
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Puts a single property back to what `base` has for it (usually the
+    /// default).
+    pub fn reset(&mut self, property: &str, base: &Flex) {
+        match property {
+            "flexDirection" => { self.direction = base.direction; },
+            "flexWrap" => { self.wrap = base.wrap; },
+            "justifyContent" => { self.justify = base.justify; },
+            "alignItems" => { self.align_items = base.align_items; },
+            "gap" => { self.gap = base.gap; },
+            "flexGrow" => { self.grow = base.grow; },
+            "flexShrink" => { self.shrink = base.shrink; },
+            "flexBasis" => { self.basis = base.basis; },
+            "alignSelf" => { self.align_self = base.align_self; },
+            _ => {}
+        }
+    }
+}
+
+/// A child, as far as laying it out goes.
+pub struct FlexChild<'a> {
+    pub flex: &'a Flex,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub margin: EdgeInsets
+}
+
+/// Where each child goes: one (border box) rect per child, in order, relative
+/// to the container's content box - which is `width` by `height`.
+pub fn layout(container: &Flex, width: f64, height: f64, children: &[FlexChild]) -> Vec<Rect> {
+    let row = container.direction.is_row();
+    let (main, cross) = if row { (width, height) } else { (height, width) };
+    let gap = container.gap;
+
+    let mut items: Vec<Item> = children.iter().map(|child| Item::new(child, row)).collect();
+
+    // Break items into lines (just the one, unless wrapping).
+    let mut lines: Vec<Vec<usize>> = vec![];
+    let mut used = 0.;
+    for (index, item) in items.iter().enumerate() {
+        let outer = item.basis + item.main_margins();
+        match lines.last_mut() {
+            Some(ref mut line) if !container.wrap || used + gap + outer <= main => {
+                line.push(index);
+                used += gap + outer;
+            },
+            _ => {
+                lines.push(vec![index]);
+                used = outer;
+            }
+        }
+    }
+
+    // Main axis: grow or shrink to fit, then justify whatever's left over.
+    for line in &lines {
+        let gaps = gap * (line.len() as f64 - 1.);
+        let free = main - gaps - line.iter().map(|&i| items[i].basis + items[i].main_margins()).sum::<f64>();
+        let total_grow: f64 = line.iter().map(|&i| items[i].grow).sum();
+        let total_shrink: f64 = line.iter().map(|&i| items[i].shrink * items[i].basis).sum();
+
+        for &i in line {
+            let item = &mut items[i];
+            item.size = item.basis;
+            if free > 0. && total_grow > 0. {
+                item.size += free * item.grow / total_grow;
+            } else if free < 0. && total_shrink > 0. {
+                item.size = (item.basis + free * item.shrink * item.basis / total_shrink).max(0.);
+            }
+        }
+
+        let remaining = main - gaps - line.iter().map(|&i| items[i].size + items[i].main_margins()).sum::<f64>();
+        let count = line.len() as f64;
+        let (mut position, between) = match container.justify {
+            Justify::Start => (0., 0.),
+            Justify::End => (remaining, 0.),
+            Justify::Center => (remaining / 2., 0.),
+            Justify::SpaceBetween if remaining > 0. && count > 1. => (0., remaining / (count - 1.)),
+            Justify::SpaceAround if remaining > 0. => (remaining / count / 2., remaining / count),
+            Justify::SpaceEvenly if remaining > 0. => (remaining / (count + 1.), remaining / (count + 1.)),
+            _ => (0., 0.)
+        };
+
+        for &i in line {
+            let item = &mut items[i];
+            position += item.margin.0;
+            item.position = position;
+            position += item.size + item.margin.1 + gap + between;
+        }
+    }
+
+    // Cross axis: lines are as big as their biggest sized item, and share out
+    // whatever space is left (so a single line fills the container).
+    let mut line_sizes: Vec<f64> = lines.iter().map(|line| {
+        line.iter().map(|&i| items[i].cross_size.unwrap_or(0.) + items[i].cross_margins()).fold(0., f64::max)
+    }).collect();
+
+    let extra = cross - gap * (lines.len() as f64 - 1.) - line_sizes.iter().sum::<f64>();
+    if extra > 0. && !lines.is_empty() {
+        let share = extra / lines.len() as f64;
+        for size in &mut line_sizes {
+            *size += share;
+        }
+    }
+
+    let mut line_start = 0.;
+    for (line, &line_size) in lines.iter().zip(line_sizes.iter()) {
+        for &i in line {
+            let item = &mut items[i];
+            let align = item.align_self.unwrap_or(container.align_items);
+            let space = line_size - item.cross_margins();
+            item.cross_length = match (item.cross_size, align) {
+                (Some(size), _) => size,
+                (None, Align::Stretch) => space.max(0.),
+                (None, _) => 0.
+            };
+
+            let offset = match align {
+                Align::Stretch | Align::Start => 0.,
+                Align::End => space - item.cross_length,
+                Align::Center => (space - item.cross_length) / 2.
+            };
+
+            item.cross_position = line_start + item.cross_margin.0 + offset;
+        }
+
+        line_start += line_size + gap;
+    }
+
+    items.iter().map(|item| {
+        let position = if container.direction.is_reversed() { main - item.position - item.size } else { item.position };
+        if row {
+            Rect::new(position, item.cross_position, item.size, item.cross_length)
+        } else {
+            Rect::new(item.cross_position, position, item.cross_length, item.size)
+        }
+    }).collect()
+}
+
+/// A child mid-layout, in main/cross axis terms rather than x/y.
+struct Item {
+    basis: f64,
+    grow: f64,
+    shrink: f64,
+    align_self: Option<Align>,
+    cross_size: Option<f64>,
+
+    /// (start, end) margins along each axis.
+    margin: (f64, f64),
+    cross_margin: (f64, f64),
+
+    size: f64,
+    position: f64,
+    cross_length: f64,
+    cross_position: f64
+}
+
+impl Item {
+    fn new(child: &FlexChild, row: bool) -> Self {
+        let (main_size, cross_size) = if row { (child.width, child.height) } else { (child.height, child.width) };
+        let margin = child.margin;
+        let (main_margin, cross_margin) = if row {
+            ((margin.left, margin.right), (margin.top, margin.bottom))
+        } else {
+            ((margin.top, margin.bottom), (margin.left, margin.right))
+        };
+
+        Item {
+            basis: child.flex.basis.or(main_size).unwrap_or(0.).max(0.),
+            grow: child.flex.grow,
+            shrink: child.flex.shrink,
+            align_self: child.flex.align_self,
+            cross_size,
+            margin: main_margin,
+            cross_margin,
+            size: 0.,
+            position: 0.,
+            cross_length: 0.,
+            cross_position: 0.
+        }
+    }
+
+    fn main_margins(&self) -> f64 {
+        self.margin.0 + self.margin.1
+    }
+
+    fn cross_margins(&self) -> f64 {
+        self.cross_margin.0 + self.cross_margin.1
+    }
+}
+
+impl View {
+    /// The flex half of `apply_styles`.
+    pub fn apply_flex_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if FLEX_PROPERTIES.iter().any(|property| style.get(property).is_some()) {
+            self.flex.apply_style(style)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out children described the way a stylesheet would, in a container
+    /// `width` by `height`. Children's margins are a single number, if any.
+    fn lay_out(container: Value, children: &[Value], width: f64, height: f64) -> Vec<Rect> {
+        let mut flex = Flex::default();
+        flex.apply_style(&container).unwrap();
+
+        let settings: Vec<Flex> = children.iter().map(|child| {
+            let mut flex = Flex::default();
+            flex.apply_style(child).unwrap();
+            flex
+        }).collect();
+
+        let children: Vec<FlexChild> = children.iter().zip(settings.iter()).map(|(child, flex)| FlexChild {
+            flex,
+            width: child.get("width").and_then(Value::as_f64),
+            height: child.get("height").and_then(Value::as_f64),
+            margin: EdgeInsets::uniform(child.get("margin").and_then(Value::as_f64).unwrap_or(0.))
+        }).collect();
+
+        layout(&flex, width, height, &children)
+    }
+
+    fn pair() -> Vec<Value> {
+        vec![json!({"width": 50}), json!({"width": 100})]
+    }
+
+    #[test]
+    fn lays_out_in_each_direction() {
+        assert_eq!(lay_out(json!({}), &pair(), 300., 100.), vec![Rect::new(0., 0., 50., 100.), Rect::new(50., 0., 100., 100.)]);
+        assert_eq!(lay_out(json!({"flexDirection": "row-reverse"}), &pair(), 300., 100.), vec![Rect::new(250., 0., 50., 100.), Rect::new(150., 0., 100., 100.)]);
+
+        let column = vec![json!({"height": 50}), json!({"height": 100})];
+        assert_eq!(lay_out(json!({"flexDirection": "column"}), &column, 100., 300.), vec![Rect::new(0., 0., 100., 50.), Rect::new(0., 50., 100., 100.)]);
+        assert_eq!(lay_out(json!({"flexDirection": "column-reverse"}), &column, 100., 300.), vec![Rect::new(0., 250., 100., 50.), Rect::new(0., 150., 100., 100.)]);
+    }
+
+    #[test]
+    fn justifies_leftover_space() {
+        let xs = |justify: &str| -> Vec<f64> {
+            lay_out(json!({"justifyContent": justify}), &pair(), 300., 100.).iter().map(|rect| rect.x).collect()
+        };
+
+        assert_eq!(xs("flex-start"), vec![0., 50.]);
+        assert_eq!(xs("flex-end"), vec![150., 200.]);
+        assert_eq!(xs("center"), vec![75., 125.]);
+        assert_eq!(xs("space-between"), vec![0., 200.]);
+        assert_eq!(xs("space-around"), vec![37.5, 162.5]);
+        assert_eq!(xs("space-evenly"), vec![50., 150.]);
+    }
+
+    #[test]
+    fn aligns_across_the_main_axis() {
+        let children = vec![json!({"width": 50, "height": 40}), json!({"width": 50}), json!({"width": 50, "height": 40, "alignSelf": "flex-end"})];
+        let ys = |align: &str| -> Vec<(f64, f64)> {
+            lay_out(json!({"alignItems": align}), &children, 300., 100.).iter().map(|rect| (rect.y, rect.height)).collect()
+        };
+
+        assert_eq!(ys("stretch"), vec![(0., 40.), (0., 100.), (60., 40.)]);
+        assert_eq!(ys("flex-start"), vec![(0., 40.), (0., 0.), (60., 40.)]);
+        assert_eq!(ys("flex-end"), vec![(60., 40.), (100., 0.), (60., 40.)]);
+        assert_eq!(ys("center"), vec![(30., 40.), (50., 0.), (60., 40.)]);
+    }
+
+    #[test]
+    fn spaces_children_by_gap_and_margin() {
+        assert_eq!(lay_out(json!({"gap": 10}), &pair(), 300., 100.), vec![Rect::new(0., 0., 50., 100.), Rect::new(60., 0., 100., 100.)]);
+
+        let children = vec![json!({"width": 50, "margin": 5}), json!({"width": 50})];
+        assert_eq!(lay_out(json!({}), &children, 300., 100.), vec![Rect::new(5., 5., 50., 90.), Rect::new(60., 0., 50., 100.)]);
+    }
+
+    #[test]
+    fn grows_shrinks_and_starts_from_the_basis() {
+        let growing = vec![json!({"flexBasis": 50, "flexGrow": 1}), json!({"width": 50, "flexGrow": 3})];
+        assert_eq!(lay_out(json!({}), &growing, 300., 100.), vec![Rect::new(0., 0., 100., 100.), Rect::new(100., 0., 200., 100.)]);
+
+        let shrinking = vec![json!({"width": 200}), json!({"width": 200})];
+        assert_eq!(lay_out(json!({}), &shrinking, 300., 100.), vec![Rect::new(0., 0., 150., 100.), Rect::new(150., 0., 150., 100.)]);
+
+        let rigid = vec![json!({"width": 200, "flexShrink": 0}), json!({"width": 200})];
+        assert_eq!(lay_out(json!({}), &rigid, 300., 100.), vec![Rect::new(0., 0., 200., 100.), Rect::new(200., 0., 100., 100.)]);
+
+        // The basis wins over the width, and "auto" goes back to the width.
+        let based = vec![json!({"width": 50, "flexBasis": 120}), json!({"width": 50, "flexBasis": "auto"})];
+        assert_eq!(lay_out(json!({}), &based, 300., 100.), vec![Rect::new(0., 0., 120., 100.), Rect::new(120., 0., 50., 100.)]);
+    }
+
+    #[test]
+    fn wraps_onto_new_lines() {
+        let children = vec![json!({"width": 120}), json!({"width": 120}), json!({"width": 120})];
+        assert_eq!(lay_out(json!({"flexWrap": "wrap", "gap": 10}), &children, 300., 100.), vec![
+            Rect::new(0., 0., 120., 45.),
+            Rect::new(130., 0., 120., 45.),
+            Rect::new(0., 55., 120., 45.)
+        ]);
+
+        // Without wrapping they all squeeze onto the one line.
+        let squeezed = lay_out(json!({"gap": 10}), &children, 300., 100.);
+        assert_eq!(squeezed.iter().map(|rect| rect.y).collect::<Vec<f64>>(), vec![0., 0., 0.]);
+        assert!((squeezed[2].x + squeezed[2].width - 300.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_bad_flex_values() {
+        let error = |style: Value| Flex::default().apply_style(&style).unwrap_err().json_path();
+        assert_eq!(error(json!({"flexDirection": "diagonal"})), "flexDirection");
+        assert_eq!(error(json!({"flexWrap": "sometimes"})), "flexWrap");
+        assert_eq!(error(json!({"justifyContent": "left"})), "justifyContent");
+        assert_eq!(error(json!({"gap": -1})), "gap");
+        assert_eq!(error(json!({"flexBasis": "big"})), "flexBasis");
+    }
+}
@@ -0,0 +1,668 @@
+//! layout/grid.rs
+//!
+//! Grids, for layouts that line up in both directions at once - a month of a
+//! calendar, say. A view with `"display": "grid"` places its children into
+//! rows and columns of tracks:
+//!
+//! ```json
+//! "month": {"display": "grid", "gridTemplateColumns": "repeat(7, 1fr)", "gridTemplateRows": "32 repeat(6, 1fr)", "gap": 1},
+//! "monthTitle": {"gridColumn": "1 / -1"}
+//! ```
+//!
+//! Tracks are a fixed size (`100`), a share of what's left over (`1fr`), or
+//! `auto` (as big as the largest sized child sitting only in that track), and
+//! `repeat(count, tracks)` saves writing the same one out seven times. Tracks
+//! can also be given as an array (`[100, "1fr", "auto"]`).
+//!
+//! Children say where they go with `gridColumn` and `gridRow` - a line
+//! (`2`, or `-1` for the last one), a pair of lines (`"1 / 3"`), or a span
+//! (`"span 2"`, `"2 / span 3"`) - or by name with `gridArea`, against the
+//! container's `gridTemplateAreas` (`["header header", "sidebar main"]`, with
+//! `.` for an empty cell). Anything left unplaced fills in the first free
+//! cells, row by row, and extra rows get added as needed.
+//!
+//! Gaps come from `gap` (shared with flex), with `rowGap` and `columnGap` to
+//! set each direction on its own. A child fills its cell, less its margin,
+//! unless it has its own width or height.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/20/2018
+
+use std::collections::HashMap;
+use serde_json::Value;
+
+use layout::{Display, EdgeInsets, Rect};
+use stylesheet::{StyleError, optional_number, optional_str};
+use view::View;
+
+/// Every grid property a stylesheet can set, container and child alike.
+pub const GRID_PROPERTIES: [&str; 8] = [
+    "gridTemplateColumns", "gridTemplateRows", "gridTemplateAreas", "rowGap", "columnGap",
+    "gridColumn", "gridRow", "gridArea"
+];
+
+/// The size of a single row or column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    Fixed(f64),
+    Fraction(f64),
+    Auto
+}
+
+impl Track {
+    pub fn parse(track: &str) -> Result<Track, StyleError> {
+        let error = || StyleError::new(format!("'{}' isn't a track size, expected a number, a fraction like 1fr, or auto", track));
+        let size = if track == "auto" {
+            Track::Auto
+        } else if let Some(fraction) = track.strip_suffix("fr") {
+            Track::Fraction(fraction.trim().parse().map_err(|_| error())?)
+        } else {
+            Track::Fixed(track.parse().map_err(|_| error())?)
+        };
+
+        match size {
+            Track::Fixed(size) | Track::Fraction(size) if size < 0. => Err(error()),
+            size => Ok(size)
+        }
+    }
+
+    /// Parses a whole track list: a number, a string like "100 1fr repeat(2, auto)",
+    /// or an array of numbers and strings.
+    pub fn parse_list(tracks: &Value) -> Result<Vec<Track>, StyleError> {
+        match *tracks {
+            Value::Number(ref size) => Ok(vec![Track::Fixed(size.as_f64().filter(|&size| size >= 0.).ok_or_else(|| {
+                StyleError::new(format!("expected a track size of 0 or more, found {}", size))
+            })?)]),
+
+            Value::String(ref tracks) => parse_track_string(tracks),
+
+            Value::Array(ref tracks) => {
+                let mut list = vec![];
+                for track in tracks {
+                    match *track {
+                        Value::Number(_) | Value::String(_) => list.extend(Track::parse_list(track)?),
+                        _ => { return Err(StyleError::new(format!("expected a track size, found {}", track))); }
+                    }
+                }
+
+                Ok(list)
+            },
+
+            _ => Err(StyleError::new(format!("expected a list of tracks like \"100 1fr auto\", found {}", tracks)))
+        }
+    }
+}
+
+fn parse_track_string(tracks: &str) -> Result<Vec<Track>, StyleError> {
+    let mut list = vec![];
+    let mut rest = tracks.trim();
+    while !rest.is_empty() {
+        if let Some(repeat) = rest.strip_prefix("repeat(") {
+            let close = repeat.find(')').ok_or_else(|| StyleError::new(format!("'{}' is missing a closing bracket", rest)))?;
+            let (count, repeated) = repeat[..close].split_once(',').ok_or_else(|| {
+                StyleError::new(format!("expected repeat(count, tracks), found repeat({})", &repeat[..close]))
+            })?;
+
+            let count: usize = count.trim().parse().map_err(|_| StyleError::new(format!("'{}' isn't a repeat count", count.trim())))?;
+            let repeated = parse_track_string(repeated)?;
+            for _ in 0..count {
+                list.extend_from_slice(&repeated);
+            }
+
+            rest = repeat[close + 1..].trim_start();
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            list.push(Track::parse(&rest[..end])?);
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    Ok(list)
+}
+
+/// One end of a child's placement along an axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridPosition {
+    Auto,
+
+    /// A grid line, counting from 1. Negative lines count back from the end
+    /// of the explicit grid, so -1 is the last line.
+    Line(i32),
+
+    Span(usize)
+}
+
+impl GridPosition {
+    fn parse(position: &str) -> Result<GridPosition, StyleError> {
+        let position = position.trim();
+        let error = || StyleError::new(format!("'{}' isn't a grid position, expected a line number, span <count> or auto", position));
+        if position == "auto" {
+            return Ok(GridPosition::Auto);
+        }
+
+        if let Some(span) = position.strip_prefix("span ") {
+            return match span.trim().parse() {
+                Ok(span) if span > 0 => Ok(GridPosition::Span(span)),
+                _ => Err(error())
+            };
+        }
+
+        match position.parse() {
+            Ok(line) if line != 0 => Ok(GridPosition::Line(line)),
+            _ => Err(error())
+        }
+    }
+}
+
+/// Where a child goes along one axis: `gridColumn` or `gridRow`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridLine {
+    pub start: GridPosition,
+    pub end: GridPosition
+}
+
+impl Default for GridLine {
+    fn default() -> Self {
+        GridLine { start: GridPosition::Auto, end: GridPosition::Auto }
+    }
+}
+
+impl GridLine {
+    /// Parses "2", "1 / 3", "span 2", "2 / span 3", "1 / -1" and the like. A
+    /// bare number works too.
+    pub fn from_json(line: &Value) -> Result<GridLine, StyleError> {
+        let line = match *line {
+            Value::Number(ref number) => number.to_string(),
+            Value::String(ref line) => line.clone(),
+            _ => { return Err(StyleError::new(format!("expected a grid line like \"1 / 3\" or \"span 2\", found {}", line))); }
+        };
+
+        let mut parts = line.split('/');
+        let start = GridPosition::parse(parts.next().unwrap_or(""))?;
+        let end = match parts.next() {
+            Some(end) => GridPosition::parse(end)?,
+            None => GridPosition::Auto
+        };
+
+        if parts.next().is_some() {
+            return Err(StyleError::new(format!("expected at most two positions, found '{}'", line)));
+        }
+
+        Ok(GridLine { start, end })
+    }
+
+    /// The track this starts at (0 based, None if it's up to auto-placement) and
+    /// how many it spans, for a grid with `count` explicit tracks.
+    fn resolve(&self, count: usize) -> (Option<usize>, usize) {
+        let line = |line: i32| if line > 0 {
+            line as usize - 1
+        } else {
+            (count as i32 + 1 + line).max(0) as usize
+        };
+
+        match (self.start, self.end) {
+            (GridPosition::Line(start), GridPosition::Line(end)) => {
+                let (start, end) = (line(start), line(end));
+                (Some(start.min(end)), start.max(end).saturating_sub(start.min(end)).max(1))
+            },
+            (GridPosition::Line(start), GridPosition::Span(span)) => (Some(line(start)), span),
+            (GridPosition::Line(start), GridPosition::Auto) => (Some(line(start)), 1),
+            (GridPosition::Span(span), GridPosition::Line(end)) => (Some(line(end).saturating_sub(span)), span),
+            (GridPosition::Auto, GridPosition::Line(end)) => (Some(line(end).saturating_sub(1)), 1),
+            (GridPosition::Span(span), _) | (GridPosition::Auto, GridPosition::Span(span)) => (None, span),
+            (GridPosition::Auto, GridPosition::Auto) => (None, 1)
+        }
+    }
+}
+
+/// A named rectangle of cells, from `gridTemplateAreas`. All 0 based.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridArea {
+    pub row: usize,
+    pub column: usize,
+    pub rows: usize,
+    pub columns: usize
+}
+
+/// Works out the named areas in a template like ["header header", "side main"].
+/// Every name has to cover a rectangle, and every row has to be as long as the
+/// others.
+pub fn parse_areas(template: &Value) -> Result<(HashMap<String, GridArea>, usize, usize), StyleError> {
+    let rows: Vec<&str> = match *template {
+        Value::String(ref row) => vec![row.as_str()],
+        Value::Array(ref rows) => rows.iter().map(|row| row.as_str()).collect::<Option<_>>().ok_or_else(|| {
+            StyleError::new(format!("expected an array of rows like \"header header\", found {}", template))
+        })?,
+        _ => { return Err(StyleError::new(format!("expected an array of rows like \"header header\", found {}", template))); }
+    };
+
+    let cells: Vec<Vec<&str>> = rows.iter().map(|row| row.split_whitespace().collect()).collect();
+    let columns = cells.first().map(|row| row.len()).unwrap_or(0);
+    if cells.iter().any(|row| row.len() != columns) {
+        return Err(StyleError::new("every row of gridTemplateAreas needs the same number of cells"));
+    }
+
+    let mut areas: HashMap<String, GridArea> = HashMap::new();
+    for (row, names) in cells.iter().enumerate() {
+        for (column, &name) in names.iter().enumerate() {
+            if name == "." || areas.contains_key(name) {
+                continue;
+            }
+
+            let width = names[column..].iter().take_while(|&&other| other == name).count();
+            let height = cells[row..].iter().take_while(|other| other[column] == name).count();
+            areas.insert(name.into(), GridArea { row, column, rows: height, columns: width });
+        }
+    }
+
+    // Anything named outside its area means the area wasn't a rectangle.
+    for (row, names) in cells.iter().enumerate() {
+        for (column, &name) in names.iter().enumerate() {
+            let inside = areas.get(name).is_none_or(|area| {
+                (area.row..area.row + area.rows).contains(&row) && (area.column..area.column + area.columns).contains(&column)
+            });
+
+            if name != "." && !inside {
+                return Err(StyleError::new(format!("the area '{}' isn't a rectangle", name)));
+            }
+        }
+    }
+
+    Ok((areas, cells.len(), columns))
+}
+
+/// A view's grid settings: the first half apply when it's a grid container,
+/// the second half when it's inside one.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Grid {
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    pub areas: HashMap<String, GridArea>,
+
+    /// The size of the area template, as (rows, columns).
+    pub area_size: (usize, usize),
+
+    /// Override `gap` for one direction.
+    pub row_gap: Option<f64>,
+    pub column_gap: Option<f64>,
+
+    pub column: GridLine,
+    pub row: GridLine,
+    pub area: Option<String>
+}
+
+impl Grid {
+    /// Updates this from whichever grid properties a style object sets.
+    pub fn apply_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if let Some(columns) = style.get("gridTemplateColumns").filter(|columns| !columns.is_null()) {
+            self.columns = Track::parse_list(columns).map_err(|e| e.at("gridTemplateColumns"))?;
+        }
+
+        if let Some(rows) = style.get("gridTemplateRows").filter(|rows| !rows.is_null()) {
+            self.rows = Track::parse_list(rows).map_err(|e| e.at("gridTemplateRows"))?;
+        }
+
+        if let Some(template) = style.get("gridTemplateAreas").filter(|template| !template.is_null()) {
+            let (areas, rows, columns) = parse_areas(template).map_err(|e| e.at("gridTemplateAreas"))?;
+            self.areas = areas;
+            self.area_size = (rows, columns);
+        }
+
+        for (property, gap) in [("rowGap", &mut self.row_gap), ("columnGap", &mut self.column_gap)] {
+            if let Some(value) = optional_number(style, property)? {
+                if value < 0. {
+                    return Err(StyleError::new(format!("expected 0 or more, found {}", value)).at(property));
+                }
+
+                *gap = Some(value);
+            }
+        }
+
+        for (property, line) in [("gridColumn", &mut self.column), ("gridRow", &mut self.row)] {
+            if let Some(value) = style.get(property).filter(|value| !value.is_null()) {
+                *line = GridLine::from_json(value).map_err(|e| e.at(property))?;
+            }
+        }
+
+        if let Some(area) = optional_str(style, "gridArea")? {
+            self.area = Some(area.into());
+        }
+
+        Ok(())
+    }
+
+    /// Puts a single property back to its default.
+    pub fn reset(&mut self, property: &str) {
+        match property {
+            "gridTemplateColumns" => { self.columns = vec![]; },
+            "gridTemplateRows" => { self.rows = vec![]; },
+            "gridTemplateAreas" => {
+                self.areas = HashMap::new();
+                self.area_size = (0, 0);
+            },
+            "rowGap" => { self.row_gap = None; },
+            "columnGap" => { self.column_gap = None; },
+            "gridColumn" => { self.column = GridLine::default(); },
+            "gridRow" => { self.row = GridLine::default(); },
+            "gridArea" => { self.area = None; },
+            _ => {}
+        }
+    }
+}
+
+/// A child, as far as laying it out goes.
+pub struct GridChild<'a> {
+    pub grid: &'a Grid,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub margin: EdgeInsets
+}
+
+/// Where each child goes: one (border box) rect per child, in order, relative
+/// to the container's content box - which is `width` by `height`. `gap` is the
+/// container's flex gap, which `rowGap` and `columnGap` override.
+pub fn layout(container: &Grid, gap: f64, width: f64, height: f64, children: &[GridChild]) -> Vec<Rect> {
+    let explicit_rows = container.rows.len().max(container.area_size.0);
+    let explicit_columns = container.columns.len().max(container.area_size.1);
+
+    // (row, column, rows, columns) for each child, as far as they say.
+    let wanted: Vec<(Option<usize>, Option<usize>, usize, usize)> = children.iter().map(|child| {
+        if let Some(area) = child.grid.area.as_ref().and_then(|name| container.areas.get(name)) {
+            return (Some(area.row), Some(area.column), area.rows, area.columns);
+        }
+
+        let (row, rows) = child.grid.row.resolve(explicit_rows);
+        let (column, columns) = child.grid.column.resolve(explicit_columns);
+        (row, column, rows, columns)
+    }).collect();
+
+    let column_count = wanted.iter().map(|&(_, column, _, columns)| column.unwrap_or(0) + columns)
+        .fold(explicit_columns.max(1), usize::max);
+
+    // Children placed on both axes go in first, then ones with a row, then the rest
+    // flow into whatever's free.
+    let mut occupied = Occupied { columns: column_count, cells: vec![] };
+    let mut cells = vec![(0, 0, 1, 1); children.len()];
+    let (mut cursor_row, mut cursor_column) = (0, 0);
+    for pass in 0..3 {
+        for (i, &(row, column, rows, columns)) in wanted.iter().enumerate() {
+            let cell = match (pass, row, column) {
+                (0, Some(row), Some(column)) => (row, column),
+                (1, Some(row), None) => (row, (0..column_count).find(|&column| occupied.fits(row, column, rows, columns)).unwrap_or(0)),
+                (2, None, Some(column)) => (occupied.first_row(column, rows, columns), column),
+                (2, None, None) => {
+                    loop {
+                        if cursor_column + columns > column_count {
+                            cursor_row += 1;
+                            cursor_column = 0;
+                        }
+
+                        if occupied.fits(cursor_row, cursor_column, rows, columns) {
+                            break;
+                        }
+
+                        cursor_column += 1;
+                    }
+
+                    let cell = (cursor_row, cursor_column);
+                    cursor_column += columns;
+                    cell
+                },
+                _ => { continue; }
+            };
+
+            occupied.fill(cell.0, cell.1, rows, columns);
+            cells[i] = (cell.0, cell.1, rows, columns);
+        }
+    }
+
+    let row_count = cells.iter().map(|&(row, _, rows, _)| row + rows).fold(explicit_rows, usize::max);
+    let (row_gap, column_gap) = (container.row_gap.unwrap_or(gap), container.column_gap.unwrap_or(gap));
+
+    let column_sizes = size_tracks(&container.columns, column_count, width, column_gap, cells.iter().zip(children).filter_map(|(&(_, column, _, columns), child)| {
+        child.width.filter(|_| columns == 1).map(|width| (column, width + child.margin.left + child.margin.right))
+    }));
+
+    let row_sizes = size_tracks(&container.rows, row_count, height, row_gap, cells.iter().zip(children).filter_map(|(&(row, _, rows, _), child)| {
+        child.height.filter(|_| rows == 1).map(|height| (row, height + child.margin.top + child.margin.bottom))
+    }));
+
+    let span = |sizes: &[f64], gap: f64, start: usize, count: usize| {
+        let position = sizes[..start].iter().map(|size| size + gap).sum::<f64>();
+        let length = sizes[start..start + count].iter().sum::<f64>() + gap * (count as f64 - 1.);
+        (position, length)
+    };
+
+    cells.iter().zip(children).map(|(&(row, column, rows, columns), child)| {
+        let (x, cell_width) = span(&column_sizes, column_gap, column, columns);
+        let (y, cell_height) = span(&row_sizes, row_gap, row, rows);
+        let margin = child.margin;
+        Rect::new(
+            x + margin.left,
+            y + margin.top,
+            child.width.unwrap_or((cell_width - margin.left - margin.right).max(0.)),
+            child.height.unwrap_or((cell_height - margin.top - margin.bottom).max(0.))
+        )
+    }).collect()
+}
+
+/// Sizes `count` tracks to fit in `available`: fixed ones as given, auto ones
+/// as big as the largest of `sized` (a track, and the size of a child that sits
+/// only in it), and fractions sharing whatever's left.
+fn size_tracks<I: Iterator<Item = (usize, f64)>>(tracks: &[Track], count: usize, available: f64, gap: f64, sized: I) -> Vec<f64> {
+    let mut auto: Vec<f64> = vec![0.; count];
+    for (track, size) in sized {
+        auto[track] = auto[track].max(size);
+    }
+
+    let track = |i: usize| tracks.get(i).cloned().unwrap_or(Track::Auto);
+    let mut sizes: Vec<f64> = (0..count).map(|i| match track(i) {
+        Track::Fixed(size) => size,
+        Track::Auto => auto[i],
+        Track::Fraction(_) => 0.
+    }).collect();
+
+    let fractions: f64 = (0..count).filter_map(|i| match track(i) {
+        Track::Fraction(fraction) => Some(fraction),
+        _ => None
+    }).sum();
+
+    if fractions > 0. {
+        let free = (available - sizes.iter().sum::<f64>() - gap * (count as f64 - 1.).max(0.)).max(0.);
+        for (i, size) in sizes.iter_mut().enumerate() {
+            if let Track::Fraction(fraction) = track(i) {
+                *size = free * fraction / fractions;
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Which cells are taken, for auto-placement. Grows a row at a time.
+struct Occupied {
+    columns: usize,
+    cells: Vec<Vec<bool>>
+}
+
+impl Occupied {
+    fn fits(&self, row: usize, column: usize, rows: usize, columns: usize) -> bool {
+        column + columns <= self.columns && (row..row + rows).all(|row| {
+            self.cells.get(row).is_none_or(|cells| cells[column..column + columns].iter().all(|&taken| !taken))
+        })
+    }
+
+    fn first_row(&self, column: usize, rows: usize, columns: usize) -> usize {
+        (0..).find(|&row| self.fits(row, column, rows, columns)).unwrap_or(0)
+    }
+
+    fn fill(&mut self, row: usize, column: usize, rows: usize, columns: usize) {
+        while self.cells.len() < row + rows {
+            self.cells.push(vec![false; self.columns]);
+        }
+
+        for cells in &mut self.cells[row..row + rows] {
+            for taken in cells.iter_mut().skip(column).take(columns) {
+                *taken = true;
+            }
+        }
+    }
+}
+
+impl View {
+    /// The grid half of `apply_styles`.
+    pub fn apply_grid_style(&mut self, style: &Value) -> Result<(), StyleError> {
+        if GRID_PROPERTIES.iter().any(|property| style.get(property).is_some()) {
+            self.grid.apply_style(style)?;
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure every `gridArea` in this tree names an area its grid actually
+    /// has. This can only be checked once the whole tree is styled, as the name
+    /// and the template are set on different views.
+    pub fn check_grid_areas(&self) -> Result<(), StyleError> {
+        for child in &self.subviews {
+            if let (Display::Grid, Some(area)) = (self.display, child.grid.area.as_ref()) {
+                if !self.grid.areas.contains_key(area) {
+                    let message = format!("'{}' isn't one of the areas in {}'s gridTemplateAreas", area, self.name);
+                    return Err(StyleError::new(message).at("gridArea").at(&child.name));
+                }
+            }
+
+            child.check_grid_areas()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out children described the way a stylesheet would, in a container
+    /// `width` by `height`.
+    fn lay_out(container: Value, children: &[Value], width: f64, height: f64) -> Vec<Rect> {
+        let mut grid = Grid::default();
+        grid.apply_style(&container).unwrap();
+        let gap = container.get("gap").and_then(Value::as_f64).unwrap_or(0.);
+
+        let settings: Vec<Grid> = children.iter().map(|child| {
+            let mut grid = Grid::default();
+            grid.apply_style(child).unwrap();
+            grid
+        }).collect();
+
+        let children: Vec<GridChild> = children.iter().zip(settings.iter()).map(|(child, grid)| GridChild {
+            grid,
+            width: child.get("width").and_then(Value::as_f64),
+            height: child.get("height").and_then(Value::as_f64),
+            margin: EdgeInsets::default()
+        }).collect();
+
+        layout(&grid, gap, width, height, &children)
+    }
+
+    #[test]
+    fn sizes_fixed_fraction_and_auto_tracks() {
+        let children = vec![json!({}), json!({}), json!({"width": 40}), json!({})];
+        assert_eq!(lay_out(json!({"gridTemplateColumns": "100 1fr auto 2fr", "gridTemplateRows": 50}), &children, 500., 300.), vec![
+            Rect::new(0., 0., 100., 50.),
+            Rect::new(100., 0., 120., 50.),
+            Rect::new(220., 0., 40., 50.),
+            Rect::new(260., 0., 240., 50.)
+        ]);
+
+        assert_eq!(Track::parse_list(&json!("repeat(2, 1fr auto) 30")).unwrap(), vec![
+            Track::Fraction(1.), Track::Auto, Track::Fraction(1.), Track::Auto, Track::Fixed(30.)
+        ]);
+        assert_eq!(Track::parse_list(&json!([100, "2fr"])).unwrap(), vec![Track::Fixed(100.), Track::Fraction(2.)]);
+        assert!(Track::parse_list(&json!("100 wide")).is_err());
+        assert!(Track::parse_list(&json!("repeat(2 1fr)")).is_err());
+    }
+
+    #[test]
+    fn leaves_gaps_between_tracks() {
+        let children = vec![json!({}), json!({}), json!({}), json!({})];
+        let container = json!({"gridTemplateColumns": "1fr 1fr", "gridTemplateRows": "50 50", "gap": 10});
+        assert_eq!(lay_out(container, &children, 210., 300.), vec![
+            Rect::new(0., 0., 100., 50.),
+            Rect::new(110., 0., 100., 50.),
+            Rect::new(0., 60., 100., 50.),
+            Rect::new(110., 60., 100., 50.)
+        ]);
+
+        let container = json!({"gridTemplateColumns": "1fr 1fr", "gridTemplateRows": "50 50", "gap": 10, "rowGap": 20, "columnGap": 0});
+        let ys: Vec<(f64, f64)> = lay_out(container, &children, 210., 300.).iter().map(|rect| (rect.x, rect.y)).collect();
+        assert_eq!(ys, vec![(0., 0.), (105., 0.), (0., 70.), (105., 70.)]);
+    }
+
+    #[test]
+    fn places_spans_and_lines() {
+        let children = vec![
+            json!({"gridColumn": "1 / -1"}),
+            json!({"gridColumn": "span 2"}),
+            json!({}),
+            json!({"gridRow": 3, "gridColumn": "2 / span 2"})
+        ];
+
+        let container = json!({"gridTemplateColumns": "repeat(3, 100)", "gridTemplateRows": "repeat(3, 50)"});
+        assert_eq!(lay_out(container, &children, 300., 150.), vec![
+            Rect::new(0., 0., 300., 50.),
+            Rect::new(0., 50., 200., 50.),
+            Rect::new(200., 50., 100., 50.),
+            Rect::new(100., 100., 200., 50.)
+        ]);
+
+        assert!(GridLine::from_json(&json!("1 / 2 / 3")).is_err());
+        assert!(GridLine::from_json(&json!("span 0")).is_err());
+        assert!(GridLine::from_json(&json!(0)).is_err());
+    }
+
+    #[test]
+    fn places_children_by_area() {
+        let children = vec![json!({"gridArea": "main"}), json!({"gridArea": "header"}), json!({"gridArea": "sidebar"})];
+        let container = json!({
+            "gridTemplateAreas": ["header header", "sidebar main"],
+            "gridTemplateColumns": "100 1fr",
+            "gridTemplateRows": "50 1fr"
+        });
+
+        assert_eq!(lay_out(container, &children, 400., 300.), vec![
+            Rect::new(100., 50., 300., 250.),
+            Rect::new(0., 0., 400., 50.),
+            Rect::new(0., 50., 100., 250.)
+        ]);
+    }
+
+    #[test]
+    fn rejects_bad_area_templates() {
+        let ragged = parse_areas(&json!(["header header", "main"])).unwrap_err();
+        assert_eq!(ragged.message, "every row of gridTemplateAreas needs the same number of cells");
+
+        let bent = parse_areas(&json!(["a b", "b b"])).unwrap_err();
+        assert_eq!(bent.message, "the area 'b' isn't a rectangle");
+
+        let (areas, rows, columns) = parse_areas(&json!(["a a .", "b b ."])).unwrap();
+        assert_eq!((areas.len(), rows, columns), (2, 2, 3));
+    }
+
+    #[test]
+    fn rejects_areas_the_grid_doesnt_have() {
+        let mut root = View::named("root").subviews(vec![View::named("header"), View::named("footer")]);
+        let styles = json!({
+            "root": {"display": "grid", "gridTemplateAreas": ["header", "main"]},
+            "header": {"gridArea": "header"},
+            "footer": {"gridArea": "footer"}
+        });
+
+        let error = root.apply_styles(styles.as_object().unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "footer.gridArea: 'footer' isn't one of the areas in root's gridTemplateAreas");
+
+        // Outside a grid, there's nothing to check against.
+        let styles = json!({"footer": {"gridArea": "footer"}});
+        assert!(View::named("root").subviews(vec![View::named("footer")]).apply_styles(styles.as_object().unwrap()).is_ok());
+    }
+}
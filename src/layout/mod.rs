@@ -0,0 +1,531 @@
+//! layout/mod.rs
+//!
+//! Plain Rust descriptions of how views are positioned relative to one another.
+//! Views build these up, and whatever backend is in use turns them into something
+//! real (NSLayoutConstraints on Cocoa, a recorded list when headless). Views are
+//! referenced by name, which lines up with how stylesheets refer to them anyway.
+//!
+//! The same descriptions can be run through the solver in here to work out the
+//! actual frames, no platform required.
+//!
+//! Views can also opt out of anchoring their children: flex, grid and overlay
+//! containers (see `flex`, `grid` and `overlay`) work out where each child goes
+//! themselves, and those placements are handed on as plain constraints like
+//! any other.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/02/2018
+
+use std::fmt;
+use std::collections::HashMap;
+
+pub mod solver;
+use self::solver::{strength, Expression, LinearConstraint, Relation, Solver, SolverError, Variable};
+
+pub mod flex;
+use self::flex::{Align, Flex, FlexChild};
+
+pub mod grid;
+use self::grid::GridChild;
+
+use backend::{Backend, Platform};
+use stylesheet::StyleError;
+use view::View;
+
+/// Rounds of `place_children` before giving up on placements settling down.
+const MAX_PLACEMENT_PASSES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    Width,
+    Height,
+    Top,
+    Leading,
+    Trailing,
+    Bottom
+}
+
+impl Attribute {
+    /// Maps an edge name, as written in a stylesheet, to an attribute. Left and
+    /// right are treated as leading and trailing.
+    pub fn from_edge(edge: &str) -> Option<Attribute> {
+        match edge {
+            "width" => Some(Attribute::Width),
+            "height" => Some(Attribute::Height),
+            "top" => Some(Attribute::Top),
+            "left" | "leading" => Some(Attribute::Leading),
+            "right" | "trailing" => Some(Attribute::Trailing),
+            "bottom" => Some(Attribute::Bottom),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anchor {
+    pub view: String,
+    pub attribute: Attribute
+}
+
+impl Anchor {
+    /// Parses expressions of the form `<viewName>.<edge>`, with an optional
+    /// offset - e.g, `"root.top"` or `"sidebar.right + 8"`. Returns the anchor
+    /// along with the offset (0 if none was given).
+    pub fn parse(expression: &str) -> Result<(Anchor, f64), AnchorError> {
+        let expression = expression.trim();
+        let malformed = || AnchorError::Malformed(expression.into());
+
+        let dot = expression.find('.').ok_or_else(malformed)?;
+        let view = &expression[..dot];
+        let rest = &expression[dot + 1..];
+        if view.is_empty() || view.contains(char::is_whitespace) {
+            return Err(malformed());
+        }
+
+        // View names can have dashes in them, so only look for an operator after the dot.
+        let (edge, offset) = match rest.find(['+', '-']) {
+            Some(index) => {
+                let (edge, offset) = rest.split_at(index);
+                let sign = if offset.starts_with('-') { -1. } else { 1. };
+                let value: f64 = offset[1..].trim().parse().map_err(|_| malformed())?;
+                (edge.trim(), sign * value)
+            },
+
+            None => (rest.trim(), 0.)
+        };
+
+        match Attribute::from_edge(edge) {
+            Some(attribute) => Ok((Anchor { view: view.into(), attribute }, offset)),
+            None => Err(AnchorError::UnknownEdge(edge.into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnchorError {
+    Malformed(String),
+    UnknownEdge(String),
+    UnknownView(String)
+}
+
+impl fmt::Display for AnchorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AnchorError::Malformed(ref expression) => write!(f, "could not parse anchor expression '{}', expected something like 'root.top + 8'", expression),
+            AnchorError::UnknownEdge(ref edge) => write!(f, "'{}' is not an edge, expected one of top, bottom, left, right, leading, trailing, width or height", edge),
+            AnchorError::UnknownView(ref view) => write!(f, "no view named '{}' exists in the view tree", view)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub attribute: Attribute,
+    pub target: Option<Anchor>,
+    pub constant: f64
+}
+
+impl Constraint {
+    /// Pins an attribute to a fixed value, e.g, a width of 200.
+    pub fn constant(attribute: Attribute, constant: f64) -> Self {
+        Constraint {
+            attribute,
+            target: None,
+            constant
+        }
+    }
+
+    /// Pins an attribute to an attribute on another view, offset by `constant`.
+    pub fn relative(attribute: Attribute, view: &str, target_attribute: Attribute, constant: f64) -> Self {
+        Constraint {
+            attribute,
+            target: Some(Anchor {
+                view: view.into(),
+                attribute: target_attribute
+            }),
+            constant
+        }
+    }
+}
+
+/// How a view lays out its children.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Display {
+    /// Children position themselves, with anchors.
+    #[default]
+    Constraints,
+
+    /// Children are placed in rows or columns (see `flex`).
+    Flex,
+
+    /// Children are placed in cells of a grid (see `grid`).
+    Grid,
+
+    /// Children are layered on top of one another (see `overlay`).
+    Overlay
+}
+
+impl Display {
+    pub fn parse(display: &str) -> Result<Display, StyleError> {
+        match display {
+            "constraints" => Ok(Display::Constraints),
+            "flex" => Ok(Display::Flex),
+            "grid" => Ok(Display::Grid),
+            "overlay" => Ok(Display::Overlay),
+            other => Err(StyleError::new(format!("'{}' isn't a display mode, expected constraints, flex, grid or overlay", other)))
+        }
+    }
+}
+
+/// Space around the edges of a view - its padding or its margin.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EdgeInsets {
+    pub top: f64,
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64
+}
+
+impl EdgeInsets {
+    pub fn new(top: f64, left: f64, bottom: f64, right: f64) -> Self {
+        EdgeInsets { top, left, bottom, right }
+    }
+
+    pub fn uniform(inset: f64) -> Self {
+        EdgeInsets::new(inset, inset, inset, inset)
+    }
+
+    /// Takes one to four values in CSS order: all edges; vertical and horizontal;
+    /// top, horizontal and bottom; or top, right, bottom and left.
+    pub fn from_values(values: &[f64]) -> Option<Self> {
+        match *values {
+            [all] => Some(EdgeInsets::uniform(all)),
+            [vertical, horizontal] => Some(EdgeInsets::new(vertical, horizontal, vertical, horizontal)),
+            [top, horizontal, bottom] => Some(EdgeInsets::new(top, horizontal, bottom, horizontal)),
+            [top, right, bottom, left] => Some(EdgeInsets::new(top, left, bottom, right)),
+            _ => None
+        }
+    }
+
+    /// How far a constraint on the given edge moves inwards. Trailing and bottom
+    /// edges move the other way, so those come back negative.
+    pub fn offset_for(&self, attribute: Attribute) -> f64 {
+        match attribute {
+            Attribute::Top => self.top,
+            Attribute::Leading => self.left,
+            Attribute::Trailing => -self.right,
+            Attribute::Bottom => -self.bottom,
+            Attribute::Width | Attribute::Height => 0.
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Rect { x, y, width, height }
+    }
+}
+
+/// The solver variables that make up a single view's frame.
+struct FrameVariables {
+    left: Variable,
+    top: Variable,
+    width: Variable,
+    height: Variable
+}
+
+impl FrameVariables {
+    fn new(solver: &mut Solver) -> Self {
+        FrameVariables {
+            left: solver.new_variable(),
+            top: solver.new_variable(),
+            width: solver.new_variable(),
+            height: solver.new_variable()
+        }
+    }
+
+    fn expression_for(&self, attribute: Attribute) -> Expression {
+        match attribute {
+            Attribute::Width => self.width.into(),
+            Attribute::Height => self.height.into(),
+            Attribute::Top => self.top.into(),
+            Attribute::Leading => self.left.into(),
+            Attribute::Trailing => Expression::from(self.left).term(self.width, 1.),
+            Attribute::Bottom => Expression::from(self.top).term(self.height, 1.)
+        }
+    }
+}
+
+fn collect_views<'a>(view: &'a View, parent: Option<usize>, views: &mut Vec<(&'a View, Option<usize>)>) {
+    let index = views.len();
+    views.push((view, parent));
+    for subview in &view.subviews {
+        collect_views(subview, Some(index), views);
+    }
+}
+
+/// Works out a frame for every view in the tree, keyed by view name, given the
+/// size the root view is being laid out in (generally the window's content size).
+/// Frames are in root coordinates, with the origin at the top left.
+///
+/// Constraints recorded on views are treated as strong rather than required, so
+/// a conflicting set (say, a width alongside both a left and right anchor) gets
+/// resolved instead of failing outright - similar to what AppKit does when it
+/// logs and breaks a constraint. Anything left unconstrained weakly defaults to
+/// the parent's origin with a zero size.
+pub fn compute_frames(root: &View, width: f64, height: f64) -> Result<HashMap<String, Rect>, SolverError> {
+    let mut solver = Solver::new();
+    let mut views = vec![];
+    collect_views(root, None, &mut views);
+
+    let variables: Vec<FrameVariables> = views.iter().map(|_| FrameVariables::new(&mut solver)).collect();
+    let indexes: HashMap<&str, usize> = views.iter().enumerate().map(|(i, &(view, _))| (view.name.as_str(), i)).collect();
+    let mut constraints = vec![];
+
+    let root_frame = &variables[0];
+    for &(variable, value) in &[(root_frame.left, 0.), (root_frame.top, 0.), (root_frame.width, width), (root_frame.height, height)] {
+        constraints.push(LinearConstraint::new(variable.into(), Relation::Equal, &Expression::constant(value), strength::REQUIRED));
+    }
+
+    for (i, &(view, parent)) in views.iter().enumerate() {
+        let frame = &variables[i];
+        let zero = Expression::constant(0.);
+        constraints.push(LinearConstraint::new(frame.width.into(), Relation::GreaterOrEqual, &zero, strength::REQUIRED));
+        constraints.push(LinearConstraint::new(frame.height.into(), Relation::GreaterOrEqual, &zero, strength::REQUIRED));
+
+        for constraint in &view.resolved_constraints(parent.map(|parent| views[parent].0)) {
+            let rhs = match constraint.target {
+                Some(ref anchor) => match indexes.get(anchor.view.as_str()) {
+                    Some(&target) => variables[target].expression_for(anchor.attribute),
+                    None => { continue; }
+                },
+
+                None => Expression::constant(0.)
+            };
+
+            let rhs = rhs.offset(constraint.constant);
+            constraints.push(LinearConstraint::new(frame.expression_for(constraint.attribute), Relation::Equal, &rhs, strength::STRONG));
+        }
+
+        if let Some(parent) = parent {
+            let parent_frame = &variables[parent];
+            constraints.push(LinearConstraint::new(frame.left.into(), Relation::Equal, &parent_frame.left.into(), strength::WEAK));
+            constraints.push(LinearConstraint::new(frame.top.into(), Relation::Equal, &parent_frame.top.into(), strength::WEAK));
+            constraints.push(LinearConstraint::new(frame.width.into(), Relation::Equal, &zero, strength::WEAK));
+            constraints.push(LinearConstraint::new(frame.height.into(), Relation::Equal, &zero, strength::WEAK));
+        }
+    }
+
+    for constraint in &constraints {
+        solver.add_constraint(constraint)?;
+    }
+
+    Ok(views.iter().zip(variables.iter()).map(|(&(view, _), frame)| {
+        (view.name.clone(), Rect::new(
+            solver.value_of(frame.left),
+            solver.value_of(frame.top),
+            solver.value_of(frame.width),
+            solver.value_of(frame.height)
+        ))
+    }).collect())
+}
+
+/// Layers children on top of one another, each filling the container (less its
+/// margin) unless it has its own width or height, in which case it sits where
+/// `alignItems` (or its own `alignSelf`) says: at the top left, the middle, or
+/// the bottom right. Rects are relative to the container's content box.
+pub fn overlay(container: &Flex, width: f64, height: f64, children: &[FlexChild]) -> Vec<Rect> {
+    children.iter().map(|child| {
+        let margin = child.margin;
+        let (space_x, space_y) = (width - margin.left - margin.right, height - margin.top - margin.bottom);
+        let (child_width, child_height) = (child.width.unwrap_or(space_x.max(0.)), child.height.unwrap_or(space_y.max(0.)));
+        let (x, y) = match child.flex.align_self.unwrap_or(container.align_items) {
+            Align::Stretch | Align::Start => (0., 0.),
+            Align::Center => ((space_x - child_width) / 2., (space_y - child_height) / 2.),
+            Align::End => (space_x - child_width, space_y - child_height)
+        };
+
+        Rect::new(margin.left + x, margin.top + y, child_width, child_height)
+    }).collect()
+}
+
+/// Works out where the children of every flex, grid and overlay container in
+/// the tree go, and records it on them as their `placement`. Containers can be
+/// sized by anchors that depend on views placed in here, so this repeats until
+/// nothing moves. Returns whether any placement changed.
+pub fn place_children(root: &mut View, width: f64, height: f64) -> Result<bool, SolverError> {
+    let mut changed = false;
+    for _ in 0..MAX_PLACEMENT_PASSES {
+        let frames = compute_frames(root, width, height)?;
+        if !place_within(root, &frames, None) {
+            break;
+        }
+
+        changed = true;
+    }
+
+    Ok(changed)
+}
+
+/// Places the children of `view` (if it lays them out itself) and then theirs.
+/// `size` is the view's own size when it was just placed, which is more up to
+/// date than anything in `frames`.
+fn place_within(view: &mut View, frames: &HashMap<String, Rect>, size: Option<(f64, f64)>) -> bool {
+    let (width, height) = size.or_else(|| frames.get(&view.name).map(|frame| (frame.width, frame.height))).unwrap_or((0., 0.));
+    let inner_width = (width - view.padding.left - view.padding.right).max(0.);
+    let inner_height = (height - view.padding.top - view.padding.bottom).max(0.);
+
+    let placements = match view.display {
+        Display::Constraints => None,
+        Display::Flex | Display::Overlay => {
+            let children: Vec<FlexChild> = view.subviews.iter().map(|child| FlexChild {
+                flex: &child.flex,
+                width: child.constant_for(Attribute::Width),
+                height: child.constant_for(Attribute::Height),
+                margin: child.margin
+            }).collect();
+
+            Some(match view.display {
+                Display::Overlay => overlay(&view.flex, inner_width, inner_height, &children),
+                _ => flex::layout(&view.flex, inner_width, inner_height, &children)
+            })
+        },
+
+        Display::Grid => {
+            let children: Vec<GridChild> = view.subviews.iter().map(|child| GridChild {
+                grid: &child.grid,
+                width: child.constant_for(Attribute::Width),
+                height: child.constant_for(Attribute::Height),
+                margin: child.margin
+            }).collect();
+
+            Some(grid::layout(&view.grid, view.flex.gap, inner_width, inner_height, &children))
+        }
+    };
+
+    let mut changed = false;
+    for (i, child) in view.subviews.iter_mut().enumerate() {
+        let placement = placements.as_ref().map(|placements| placements[i]);
+        if child.placement != placement {
+            // Whatever was installed before doesn't all get replaced - e.g, placed
+            // views have no trailing constraint - so clear the slate first.
+            if child.placement.is_none() || placement.is_none() {
+                for &attribute in &[Attribute::Width, Attribute::Height, Attribute::Top, Attribute::Leading, Attribute::Trailing, Attribute::Bottom] {
+                    Platform::deactivate_constraint(&child.backing_node, attribute);
+                }
+            }
+
+            child.placement = placement;
+            changed = true;
+        }
+
+        changed |= place_within(child, frames, placement.map(|placement| (placement.width, placement.height)));
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_anchor_expressions() {
+        let sidebar_right = Anchor { view: "sidebar".into(), attribute: Attribute::Trailing };
+        assert_eq!(Anchor::parse("root.top"), Ok((Anchor { view: "root".into(), attribute: Attribute::Top }, 0.)));
+        assert_eq!(Anchor::parse("sidebar.right + 8"), Ok((sidebar_right.clone(), 8.)));
+        assert_eq!(Anchor::parse(" sidebar.right-8 "), Ok((sidebar_right, -8.)));
+        assert_eq!(Anchor::parse("main-content.leading").map(|(anchor, _)| anchor.view), Ok("main-content".into()));
+    }
+
+    #[test]
+    fn rejects_bad_anchor_expressions() {
+        assert_eq!(Anchor::parse("root"), Err(AnchorError::Malformed("root".into())));
+        assert_eq!(Anchor::parse("root.top + lots"), Err(AnchorError::Malformed("root.top + lots".into())));
+        assert_eq!(Anchor::parse("root.middle"), Err(AnchorError::UnknownEdge("middle".into())));
+    }
+
+    /// The sidebar and content from the layout example, at its default window size.
+    #[test]
+    fn computes_frames() {
+        let mut root = View::named("root").subviews(vec![View::named("sidebar"), View::named("content")]);
+        let mut styles = json!({
+            "root": {"backgroundColor": {"r": 0, "g": 0, "b": 0}},
+            "sidebar": {"backgroundColor": {"r": 0, "g": 0, "b": 0}, "width": 200, "top": "root.top", "left": "root.left", "bottom": "root.bottom"},
+            "content": {
+                "backgroundColor": {"r": 0, "g": 0, "b": 0},
+                "top": "root.top", "left": "sidebar.right", "right": "root.right", "bottom": "root.bottom"
+            }
+        });
+        root.apply_styles(styles.as_object_mut().unwrap()).unwrap();
+
+        let frames = compute_frames(&root, 800., 600.).unwrap();
+        assert_eq!(frames["root"], Rect::new(0., 0., 800., 600.));
+        assert_eq!(frames["sidebar"], Rect::new(0., 0., 200., 600.));
+        assert_eq!(frames["content"], Rect::new(200., 0., 600., 600.));
+    }
+
+    #[test]
+    fn reads_edge_insets_in_css_order() {
+        assert_eq!(EdgeInsets::from_values(&[4.]), Some(EdgeInsets::uniform(4.)));
+        assert_eq!(EdgeInsets::from_values(&[4., 8.]), Some(EdgeInsets::new(4., 8., 4., 8.)));
+        assert_eq!(EdgeInsets::from_values(&[1., 2., 3.]), Some(EdgeInsets::new(1., 2., 3., 2.)));
+        assert_eq!(EdgeInsets::from_values(&[1., 2., 3., 4.]), Some(EdgeInsets::new(1., 4., 3., 2.)));
+        assert_eq!(EdgeInsets::from_values(&[]), None);
+        assert_eq!(EdgeInsets::new(1., 2., 3., 4.).offset_for(Attribute::Bottom), -3.);
+    }
+
+    /// Padding only applies to edges pinned to the same edge of the parent;
+    /// margins push out from whatever the edge is pinned to.
+    #[test]
+    fn folds_padding_and_margin_into_frames() {
+        let mut root = View::named("root").subviews(vec![View::named("sidebar"), View::named("content")]);
+        let styles = json!({
+            "root": {"padding": 10},
+            "sidebar": {"width": 200, "top": "root.top", "left": "root.left", "bottom": "root.bottom"},
+            "content": {"marginLeft": 8, "top": "root.top", "left": "sidebar.right", "right": "root.right", "bottom": "root.bottom"}
+        });
+        root.apply_styles(styles.as_object().unwrap()).unwrap();
+
+        let frames = compute_frames(&root, 800., 600.).unwrap();
+        assert_eq!(frames["sidebar"], Rect::new(10., 10., 200., 580.));
+        assert_eq!(frames["content"], Rect::new(218., 10., 572., 580.));
+    }
+
+    /// Flex containers place their children inside their padding, and children
+    /// of children get placed once their parent's size is known.
+    #[test]
+    fn places_children_of_flex_containers() {
+        let mut root = View::named("root").subviews(vec![
+            View::named("sidebar"),
+            View::named("content").subviews(vec![View::named("header"), View::named("body")])
+        ]);
+
+        let styles = json!({
+            "root": {"display": "flex", "gap": 10, "padding": 10},
+            "sidebar": {"width": 100, "top": "root.top"},
+            "content": {"flexGrow": 1, "display": "flex", "flexDirection": "column"},
+            "header": {"height": 50},
+            "body": {"flexGrow": 1}
+        });
+        root.apply_styles(styles.as_object().unwrap()).unwrap();
+
+        assert!(place_children(&mut root, 800., 600.).unwrap());
+        let frames = compute_frames(&root, 800., 600.).unwrap();
+        assert_eq!(frames["sidebar"], Rect::new(10., 10., 100., 580.));
+        assert_eq!(frames["content"], Rect::new(120., 10., 670., 580.));
+        assert_eq!(frames["header"], Rect::new(120., 10., 670., 50.));
+        assert_eq!(frames["body"], Rect::new(120., 60., 670., 530.));
+
+        // Nothing moves the second time around.
+        assert!(!place_children(&mut root, 800., 600.).unwrap());
+    }
+}
@@ -0,0 +1,644 @@
+//! solver.rs
+//!
+//! A small Cassowary constraint solver, more or less a port of the approach
+//! Kiwi takes (incremental simplex, with constraint strengths handled as error
+//! terms in the objective). It's what lets us figure out where views end up
+//! without asking AppKit - handy for headless backends, and for asserting on
+//! layout in tests.
+//!
+//! This only implements what layout needs: adding constraints, reading back
+//! values, and edit variables - suggesting a new value for a variable (say, a
+//! width being dragged) and re-solving from where things were, rather than
+//! from scratch. There's no constraint removal; a layout pass just builds a
+//! fresh Solver each time.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/04/2018
+
+use std::fmt;
+use std::collections::{BTreeMap, HashMap};
+
+/// Strengths, same scale Kiwi and friends use. Anything below `REQUIRED` can be
+/// violated if need be; `REQUIRED` constraints that conflict are an error.
+pub mod strength {
+    pub const REQUIRED: f64 = 1_001_001_000.;
+    pub const STRONG: f64 = 1_000_000.;
+    pub const MEDIUM: f64 = 1_000.;
+    pub const WEAK: f64 = 1.;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Variable(usize);
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expression {
+    pub terms: Vec<(Variable, f64)>,
+    pub constant: f64
+}
+
+impl Expression {
+    pub fn constant(constant: f64) -> Self {
+        Expression { terms: vec![], constant }
+    }
+
+    pub fn term(mut self, variable: Variable, coefficient: f64) -> Self {
+        self.terms.push((variable, coefficient));
+        self
+    }
+
+    pub fn plus(mut self, other: &Expression) -> Self {
+        self.terms.extend(other.terms.iter().cloned());
+        self.constant += other.constant;
+        self
+    }
+
+    pub fn minus(mut self, other: &Expression) -> Self {
+        self.terms.extend(other.terms.iter().map(|&(variable, coefficient)| (variable, -coefficient)));
+        self.constant -= other.constant;
+        self
+    }
+
+    pub fn offset(mut self, constant: f64) -> Self {
+        self.constant += constant;
+        self
+    }
+}
+
+impl From<Variable> for Expression {
+    fn from(variable: Variable) -> Self {
+        Expression { terms: vec![(variable, 1.)], constant: 0. }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual
+}
+
+/// A linear constraint, stored as `expression <relation> 0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearConstraint {
+    pub expression: Expression,
+    pub relation: Relation,
+    pub strength: f64
+}
+
+impl LinearConstraint {
+    pub fn new(lhs: Expression, relation: Relation, rhs: &Expression, strength: f64) -> Self {
+        LinearConstraint {
+            expression: lhs.minus(rhs),
+            relation,
+            strength: strength.clamp(0., strength::REQUIRED)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolverError {
+    /// A required constraint conflicts with the ones already added.
+    Unsatisfiable,
+
+    /// The objective is unbounded, which shouldn't happen with sane input.
+    Unbounded,
+
+    /// `add_edit_variable` was called twice for the same variable.
+    DuplicateEditVariable,
+
+    /// `suggest_value` was called for a variable that isn't an edit variable.
+    UnknownEditVariable
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SolverError::Unsatisfiable => write!(f, "a required constraint could not be satisfied"),
+            SolverError::Unbounded => write!(f, "the layout objective is unbounded"),
+            SolverError::DuplicateEditVariable => write!(f, "the variable is already an edit variable"),
+            SolverError::UnknownEditVariable => write!(f, "the variable isn't an edit variable")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SymbolKind {
+    Invalid,
+    External,
+    Slack,
+    Error,
+    Dummy
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Symbol(usize, SymbolKind);
+
+impl Symbol {
+    fn invalid() -> Symbol { Symbol(0, SymbolKind::Invalid) }
+    fn kind(&self) -> SymbolKind { self.1 }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tag {
+    marker: Symbol,
+    other: Symbol
+}
+
+fn near_zero(value: f64) -> bool {
+    value.abs() < 1.0e-8
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    cells: BTreeMap<Symbol, f64>,
+    constant: f64
+}
+
+impl Row {
+    fn new(constant: f64) -> Row {
+        Row { cells: BTreeMap::new(), constant }
+    }
+
+    fn insert_symbol(&mut self, symbol: Symbol, coefficient: f64) {
+        let remove = {
+            let cell = self.cells.entry(symbol).or_insert(0.);
+            *cell += coefficient;
+            near_zero(*cell)
+        };
+
+        if remove {
+            self.cells.remove(&symbol);
+        }
+    }
+
+    fn insert_row(&mut self, other: &Row, coefficient: f64) {
+        self.constant += other.constant * coefficient;
+        for (&symbol, &value) in &other.cells {
+            self.insert_symbol(symbol, value * coefficient);
+        }
+    }
+
+    fn remove(&mut self, symbol: Symbol) {
+        self.cells.remove(&symbol);
+    }
+
+    /// Adds to the constant, handing back the new one.
+    fn add(&mut self, value: f64) -> f64 {
+        self.constant += value;
+        self.constant
+    }
+
+    fn reverse_sign(&mut self) {
+        self.constant = -self.constant;
+        for value in self.cells.values_mut() {
+            *value = -*value;
+        }
+    }
+
+    /// Rearranges the row so that `symbol` is its subject, i.e, it's removed and
+    /// everything else is scaled to express it.
+    fn solve_for_symbol(&mut self, symbol: Symbol) {
+        let coefficient = -1. / self.cells.remove(&symbol).unwrap_or(1.);
+        self.constant *= coefficient;
+        for value in self.cells.values_mut() {
+            *value *= coefficient;
+        }
+    }
+
+    fn solve_for_symbols(&mut self, lhs: Symbol, rhs: Symbol) {
+        self.insert_symbol(lhs, -1.);
+        self.solve_for_symbol(rhs);
+    }
+
+    fn coefficient_for(&self, symbol: Symbol) -> f64 {
+        self.cells.get(&symbol).cloned().unwrap_or(0.)
+    }
+
+    fn substitute(&mut self, symbol: Symbol, row: &Row) {
+        if let Some(coefficient) = self.cells.remove(&symbol) {
+            self.insert_row(row, coefficient);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Objective {
+    Main,
+    Artificial
+}
+
+/// An edit variable: the constraint tying it to its suggested value, and what
+/// that value currently is.
+struct Edit {
+    tag: Tag,
+    constant: f64
+}
+
+pub struct Solver {
+    variables: HashMap<Variable, Symbol>,
+    rows: BTreeMap<Symbol, Row>,
+    objective: Row,
+    artificial: Option<Row>,
+    edits: HashMap<Variable, Edit>,
+
+    /// Rows a suggested value pushed negative, for `dual_optimize` to fix up.
+    infeasible: Vec<Symbol>,
+    next_variable: usize,
+    next_symbol: usize
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            variables: HashMap::new(),
+            rows: BTreeMap::new(),
+            objective: Row::new(0.),
+            artificial: None,
+            edits: HashMap::new(),
+            infeasible: vec![],
+            next_variable: 0,
+            next_symbol: 1
+        }
+    }
+
+    pub fn new_variable(&mut self) -> Variable {
+        self.next_variable += 1;
+        Variable(self.next_variable)
+    }
+
+    /// The current value of a variable. Variables the solver has never seen (or
+    /// that nothing pins down) come back as 0.
+    pub fn value_of(&self, variable: Variable) -> f64 {
+        self.variables.get(&variable)
+            .and_then(|symbol| self.rows.get(symbol))
+            .map(|row| row.constant)
+            .unwrap_or(0.)
+    }
+
+    pub fn add_constraint(&mut self, constraint: &LinearConstraint) -> Result<(), SolverError> {
+        self.add(constraint).map(|_| ())
+    }
+
+    /// Makes a variable something values can be suggested for, starting out at 0.
+    /// Suggestions can't be required (they'd conflict with the next one), so the
+    /// strength is capped at `strength::STRONG`.
+    pub fn add_edit_variable(&mut self, variable: Variable, edit_strength: f64) -> Result<(), SolverError> {
+        if self.edits.contains_key(&variable) {
+            return Err(SolverError::DuplicateEditVariable);
+        }
+
+        let constraint = LinearConstraint::new(variable.into(), Relation::Equal, &Expression::constant(0.), edit_strength.min(strength::STRONG));
+        let tag = self.add(&constraint)?;
+        self.edits.insert(variable, Edit { tag, constant: 0. });
+        Ok(())
+    }
+
+    /// Asks for an edit variable to be `value`, as strongly as it was added with,
+    /// and re-solves.
+    pub fn suggest_value(&mut self, variable: Variable, value: f64) -> Result<(), SolverError> {
+        let (tag, delta) = match self.edits.get_mut(&variable) {
+            Some(edit) => {
+                let delta = value - edit.constant;
+                edit.constant = value;
+                (edit.tag, delta)
+            },
+
+            None => { return Err(SolverError::UnknownEditVariable); }
+        };
+
+        // If either error variable is basic, only its row needs to change. Otherwise
+        // every row it shows up in does.
+        if let Some(row) = self.rows.get_mut(&tag.marker) {
+            if row.add(-delta) < 0. {
+                self.infeasible.push(tag.marker);
+            }
+        } else if let Some(row) = self.rows.get_mut(&tag.other) {
+            if row.add(delta) < 0. {
+                self.infeasible.push(tag.other);
+            }
+        } else {
+            for (&symbol, row) in &mut self.rows {
+                let coefficient = row.coefficient_for(tag.marker);
+                if coefficient != 0. && row.add(delta * coefficient) < 0. && symbol.kind() != SymbolKind::External {
+                    self.infeasible.push(symbol);
+                }
+            }
+        }
+
+        self.dual_optimize()
+    }
+
+    fn add(&mut self, constraint: &LinearConstraint) -> Result<Tag, SolverError> {
+        let (mut row, tag) = self.create_row(constraint);
+        let mut subject = choose_subject(&row, &tag);
+
+        if subject.kind() == SymbolKind::Invalid && row.cells.keys().all(|s| s.kind() == SymbolKind::Dummy) {
+            if !near_zero(row.constant) {
+                return Err(SolverError::Unsatisfiable);
+            }
+
+            subject = tag.marker;
+        }
+
+        if subject.kind() == SymbolKind::Invalid {
+            if !self.add_with_artificial_variable(row)? {
+                return Err(SolverError::Unsatisfiable);
+            }
+        } else {
+            row.solve_for_symbol(subject);
+            self.substitute(subject, &row);
+            self.rows.insert(subject, row);
+        }
+
+        self.optimize(Objective::Main)?;
+        Ok(tag)
+    }
+
+    fn new_symbol(&mut self, kind: SymbolKind) -> Symbol {
+        self.next_symbol += 1;
+        Symbol(self.next_symbol, kind)
+    }
+
+    fn symbol_for(&mut self, variable: Variable) -> Symbol {
+        if let Some(symbol) = self.variables.get(&variable) {
+            return *symbol;
+        }
+
+        let symbol = self.new_symbol(SymbolKind::External);
+        self.variables.insert(variable, symbol);
+        symbol
+    }
+
+    fn create_row(&mut self, constraint: &LinearConstraint) -> (Row, Tag) {
+        let expression = &constraint.expression;
+        let mut row = Row::new(expression.constant);
+
+        for &(variable, coefficient) in &expression.terms {
+            if near_zero(coefficient) {
+                continue;
+            }
+
+            let symbol = self.symbol_for(variable);
+            match self.rows.get(&symbol) {
+                Some(basic) => row.insert_row(basic, coefficient),
+                None => row.insert_symbol(symbol, coefficient)
+            }
+        }
+
+        let mut tag = Tag { marker: Symbol::invalid(), other: Symbol::invalid() };
+        let required = constraint.strength >= strength::REQUIRED;
+
+        match constraint.relation {
+            Relation::LessOrEqual | Relation::GreaterOrEqual => {
+                let coefficient = if constraint.relation == Relation::LessOrEqual { 1. } else { -1. };
+                let slack = self.new_symbol(SymbolKind::Slack);
+                tag.marker = slack;
+                row.insert_symbol(slack, coefficient);
+
+                if !required {
+                    let error = self.new_symbol(SymbolKind::Error);
+                    tag.other = error;
+                    row.insert_symbol(error, -coefficient);
+                    self.objective.insert_symbol(error, constraint.strength);
+                }
+            },
+
+            Relation::Equal => {
+                if required {
+                    let dummy = self.new_symbol(SymbolKind::Dummy);
+                    tag.marker = dummy;
+                    row.insert_symbol(dummy, 1.);
+                } else {
+                    let plus = self.new_symbol(SymbolKind::Error);
+                    let minus = self.new_symbol(SymbolKind::Error);
+                    tag.marker = plus;
+                    tag.other = minus;
+                    row.insert_symbol(plus, -1.);
+                    row.insert_symbol(minus, 1.);
+                    self.objective.insert_symbol(plus, constraint.strength);
+                    self.objective.insert_symbol(minus, constraint.strength);
+                }
+            }
+        }
+
+        if row.constant < 0. {
+            row.reverse_sign();
+        }
+
+        (row, tag)
+    }
+
+    fn add_with_artificial_variable(&mut self, row: Row) -> Result<bool, SolverError> {
+        let artificial = self.new_symbol(SymbolKind::Slack);
+        self.rows.insert(artificial, row.clone());
+        self.artificial = Some(row);
+
+        self.optimize(Objective::Artificial)?;
+        let success = self.artificial.take().map(|row| near_zero(row.constant)).unwrap_or(false);
+
+        if let Some(mut row) = self.rows.remove(&artificial) {
+            if row.cells.is_empty() {
+                return Ok(success);
+            }
+
+            let entering = row.cells.keys().cloned()
+                .find(|s| s.kind() == SymbolKind::Slack || s.kind() == SymbolKind::Error);
+
+            match entering {
+                Some(entering) => {
+                    row.solve_for_symbols(artificial, entering);
+                    self.substitute(entering, &row);
+                    self.rows.insert(entering, row);
+                },
+
+                None => { return Ok(false); }
+            }
+        }
+
+        for row in self.rows.values_mut() {
+            row.remove(artificial);
+        }
+
+        self.objective.remove(artificial);
+        Ok(success)
+    }
+
+    fn substitute(&mut self, symbol: Symbol, row: &Row) {
+        for (&basic_symbol, basic) in &mut self.rows {
+            basic.substitute(symbol, row);
+            if basic_symbol.kind() != SymbolKind::External && basic.constant < 0. {
+                self.infeasible.push(basic_symbol);
+            }
+        }
+
+        self.objective.substitute(symbol, row);
+        if let Some(ref mut artificial) = self.artificial {
+            artificial.substitute(symbol, row);
+        }
+    }
+
+    fn optimize(&mut self, objective: Objective) -> Result<(), SolverError> {
+        loop {
+            let entering = {
+                let row = match objective {
+                    Objective::Main => &self.objective,
+                    Objective::Artificial => match self.artificial {
+                        Some(ref row) => row,
+                        None => { return Ok(()); }
+                    }
+                };
+
+                row.cells.iter()
+                    .find(|&(symbol, &value)| symbol.kind() != SymbolKind::Dummy && value < 0.)
+                    .map(|(symbol, _)| *symbol)
+            };
+
+            let entering = match entering {
+                Some(entering) => entering,
+                None => { return Ok(()); }
+            };
+
+            let mut ratio = f64::MAX;
+            let mut leaving = None;
+            for (symbol, row) in &self.rows {
+                if symbol.kind() == SymbolKind::External {
+                    continue;
+                }
+
+                let coefficient = row.coefficient_for(entering);
+                if coefficient < 0. {
+                    let candidate = -row.constant / coefficient;
+                    if candidate < ratio {
+                        ratio = candidate;
+                        leaving = Some(*symbol);
+                    }
+                }
+            }
+
+            let leaving = leaving.ok_or(SolverError::Unbounded)?;
+            let mut row = self.rows.remove(&leaving).unwrap();
+            row.solve_for_symbols(leaving, entering);
+            self.substitute(entering, &row);
+            self.rows.insert(entering, row);
+        }
+    }
+}
+
+impl Solver {
+    /// Gets rows a suggested value made infeasible (negative, for a row that
+    /// isn't a variable) back to feasible, keeping the objective optimal.
+    fn dual_optimize(&mut self) -> Result<(), SolverError> {
+        while let Some(leaving) = self.infeasible.pop() {
+            let entering = match self.rows.get(&leaving) {
+                Some(row) if row.constant < 0. => {
+                    let objective = &self.objective;
+                    let mut ratio = f64::MAX;
+                    let mut entering = None;
+                    for (&symbol, &coefficient) in &row.cells {
+                        if coefficient > 0. && symbol.kind() != SymbolKind::Dummy {
+                            let candidate = objective.coefficient_for(symbol) / coefficient;
+                            if candidate < ratio {
+                                ratio = candidate;
+                                entering = Some(symbol);
+                            }
+                        }
+                    }
+
+                    entering.ok_or(SolverError::Unsatisfiable)?
+                },
+
+                _ => { continue; }
+            };
+
+            let mut row = self.rows.remove(&leaving).unwrap();
+            row.solve_for_symbols(leaving, entering);
+            self.substitute(entering, &row);
+            self.rows.insert(entering, row);
+        }
+
+        Ok(())
+    }
+}
+
+fn choose_subject(row: &Row, tag: &Tag) -> Symbol {
+    if let Some(symbol) = row.cells.keys().find(|s| s.kind() == SymbolKind::External) {
+        return *symbol;
+    }
+
+    for &candidate in &[tag.marker, tag.other] {
+        let kind = candidate.kind();
+        if (kind == SymbolKind::Slack || kind == SymbolKind::Error) && row.coefficient_for(candidate) < 0. {
+            return candidate;
+        }
+    }
+
+    Symbol::invalid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equal(variable: Variable, value: f64, strength: f64) -> LinearConstraint {
+        LinearConstraint::new(variable.into(), Relation::Equal, &Expression::constant(value), strength)
+    }
+
+    #[test]
+    fn solves_required_constraints() {
+        let mut solver = Solver::new();
+        let (left, width) = (solver.new_variable(), solver.new_variable());
+        let right = Expression::from(left).term(width, 1.);
+        solver.add_constraint(&equal(left, 20., strength::REQUIRED)).unwrap();
+        solver.add_constraint(&LinearConstraint::new(right, Relation::Equal, &Expression::constant(120.), strength::REQUIRED)).unwrap();
+
+        assert_eq!(solver.value_of(left), 20.);
+        assert_eq!(solver.value_of(width), 100.);
+    }
+
+    #[test]
+    fn weak_constraints_give_way() {
+        let mut solver = Solver::new();
+        let width = solver.new_variable();
+        solver.add_constraint(&equal(width, 0., strength::WEAK)).unwrap();
+        assert_eq!(solver.value_of(width), 0.);
+
+        solver.add_constraint(&equal(width, 50., strength::STRONG)).unwrap();
+        solver.add_constraint(&LinearConstraint::new(width.into(), Relation::LessOrEqual, &Expression::constant(40.), strength::REQUIRED)).unwrap();
+        assert_eq!(solver.value_of(width), 40.);
+    }
+
+    #[test]
+    fn suggests_values_for_edit_variables() {
+        let mut solver = Solver::new();
+        let (width, half) = (solver.new_variable(), solver.new_variable());
+        solver.add_constraint(&LinearConstraint::new(Expression::from(half).term(width, -0.5), Relation::Equal, &Expression::constant(0.), strength::REQUIRED)).unwrap();
+        solver.add_constraint(&LinearConstraint::new(width.into(), Relation::LessOrEqual, &Expression::constant(300.), strength::REQUIRED)).unwrap();
+        solver.add_edit_variable(width, strength::STRONG).unwrap();
+
+        solver.suggest_value(width, 200.).unwrap();
+        assert_eq!((solver.value_of(width), solver.value_of(half)), (200., 100.));
+
+        solver.suggest_value(width, 80.).unwrap();
+        assert_eq!((solver.value_of(width), solver.value_of(half)), (80., 40.));
+
+        solver.suggest_value(width, 500.).unwrap();
+        assert_eq!((solver.value_of(width), solver.value_of(half)), (300., 150.));
+
+        assert_eq!(solver.add_edit_variable(width, strength::WEAK), Err(SolverError::DuplicateEditVariable));
+        assert_eq!(solver.suggest_value(half, 1.), Err(SolverError::UnknownEditVariable));
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_required_constraints() {
+        let mut solver = Solver::new();
+        let width = solver.new_variable();
+        solver.add_constraint(&equal(width, 100., strength::REQUIRED)).unwrap();
+        assert_eq!(solver.add_constraint(&equal(width, 200., strength::REQUIRED)), Err(SolverError::Unsatisfiable));
+    }
+}
@@ -10,15 +10,27 @@
 #![allow(dead_code)]
 #![allow(non_upper_case_globals)]
 
+#[cfg(feature = "cocoa")]
 #[macro_use]
 extern crate objc;
+#[cfg(feature = "cocoa")]
 extern crate cocoa;
+#[cfg(feature = "cocoa")]
 extern crate objc_id;
+#[cfg(feature = "cocoa")]
 extern crate core_graphics;
 
 #[macro_use]
-extern crate serde_json;
-use serde_json::Value;
+pub extern crate serde_json;
+use serde_json::{Map, Value};
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Instant;
+
+pub mod backend;
+use backend::{Backend, Platform};
+pub mod layout;
 
 pub mod application;
 pub use application::App;
@@ -29,15 +41,170 @@ pub use window::Window;
 pub mod color;
 pub mod util;
 pub mod stylesheet;
-use stylesheet::load_styles;
-pub use stylesheet::StyleSheet;
+use stylesheet::load_sources;
+pub use stylesheet::{StyleSheet, StyleError, StyleSource};
+pub use stylesheet::watch::StyleWatcher;
 
 pub mod view;
 pub use view::View;
 
-pub fn run(user_styles: Vec<(String, Value)>, mut application: App) {
-    let mut styles = load_styles(user_styles); 
-    let mut current_style = styles["default"].as_object_mut().unwrap();
-    application.apply_styles(&mut current_style); 
-    application.run();
+pub mod label;
+pub use label::Label;
+
+pub mod scrollview;
+pub use scrollview::ScrollView;
+
+pub mod listview;
+pub use listview::{ListView, ListViewData, ListViewRow};
+
+pub mod appearance;
+
+pub mod stack;
+pub use stack::{HStack, VStack, ZStack};
+
+pub mod animation;
+pub use animation::{Animation, Easing, Timing};
+
+/// How often (in seconds) `run_watching` checks stylesheet files for changes.
+const RELOAD_INTERVAL: f64 = 0.5;
+
+/// How often (in seconds) running animations get stepped.
+const FRAME_INTERVAL: f64 = 1. / 60.;
+
+/// Loads the given stylesheets, applies the app's active theme and runs it.
+/// Stylesheets that failed to parse are logged and skipped, and if the active
+/// theme can't be applied, this logs why and falls back to "default".
+pub fn run(user_styles: Vec<Result<(String, Value), StyleError>>, mut application: App) {
+    let sources = user_styles.into_iter().map(|stylesheet| -> Result<StyleSource, StyleError> {
+        let mut source = StyleSource::inline(stylesheet?);
+        source.resolve_imports(application.asset_root.as_deref(), &mut vec![])?;
+        Ok(source)
+    }).collect();
+
+    load_or_log(&mut application, sources);
+    apply_theme_or_default(&mut application);
+    launch(&share(application));
+}
+
+/// Like `run`, but for development: stylesheets come from files on disk, and
+/// whenever one changes it's re-read and whatever changed gets re-applied to the
+/// running app. Broken edits are logged and the app keeps its last good styles.
+pub fn run_watching(mut watcher: StyleWatcher, mut application: App) {
+    load_or_log(&mut application, watcher.load());
+    apply_theme_or_default(&mut application);
+
+    let application = share(application);
+    let app = application.clone();
+    Platform::schedule_repeating(RELOAD_INTERVAL, Box::new(move || {
+        if !watcher.has_changed() {
+            return;
+        }
+
+        let mut loaded = vec![];
+        for stylesheet in watcher.load().into_iter() {
+            match stylesheet {
+                Ok(stylesheet) => loaded.push(stylesheet),
+                Err(e) => {
+                    eprintln!("shinekit: not reloading styles, {}", e);
+                    return;
+                }
+            }
+        }
+
+        let mut app = app.borrow_mut();
+        match app.reload_styles(loaded) {
+            Ok(()) => warn_about(&app),
+            Err(e) => eprintln!("shinekit: could not reload styles, {}", e)
+        }
+    }));
+
+    launch(&application);
+}
+
+/// Puts the app somewhere platform callbacks can get at it, starts listening
+/// for window resizes so breakpoints get re-evaluated, and starts the clock
+/// animations run on.
+fn share(application: App) -> Rc<RefCell<App>> {
+    let window = application.window.window.clone();
+    let application = Rc::new(RefCell::new(application));
+
+    let app = application.clone();
+    Platform::on_window_resize(&window, Box::new(move |frame| {
+        // Resizes the app makes itself (e.g, a theme's defaultWidth) land here while
+        // it's already borrowed. It re-evaluates breakpoints on its own in that case.
+        if let Ok(mut app) = app.try_borrow_mut() {
+            if let Err(e) = app.window_did_resize(frame) {
+                eprintln!("shinekit: could not restyle after resize, {}", e);
+            }
+        }
+    }));
+
+    let app = application.clone();
+    let mut last_frame = Instant::now();
+    Platform::schedule_repeating(FRAME_INTERVAL, Box::new(move || {
+        let now = Instant::now();
+        let elapsed = now.duration_since(last_frame);
+        last_frame = now;
+
+        if let Ok(mut app) = app.try_borrow_mut() {
+            if let Err(e) = app.tick(elapsed.as_secs_f64()) {
+                eprintln!("shinekit: could not step animations, {}", e);
+            }
+        }
+    }));
+
+    application
+}
+
+/// Runs the event loop. Callbacks need to borrow the app while it's running, so
+/// this doesn't hold onto it any longer than it takes to get going.
+fn launch(application: &Rc<RefCell<App>>) {
+    let handle = {
+        let application = application.borrow();
+        application.window.make_key();
+        application.app.clone()
+    };
+
+    Platform::run_app(&handle);
+}
+
+fn load_or_log(application: &mut App, user_styles: Vec<Result<StyleSource, StyleError>>) {
+    let mut loaded = vec![];
+    for stylesheet in user_styles.into_iter() {
+        match stylesheet {
+            Ok(stylesheet) => loaded.push(stylesheet),
+            Err(e) => eprintln!("shinekit: skipping stylesheet, {}", e)
+        }
+    }
+
+    application.styles = match load_sources(&loaded) {
+        Ok(styles) => styles,
+        Err(e) => {
+            eprintln!("shinekit: could not load styles, {}", e);
+            Map::new()
+        }
+    };
+
+    application.sources = loaded;
+    warn_about(application);
+}
+
+/// Logs anything in the loaded styles that won't do what it looks like it does.
+fn warn_about(application: &App) {
+    for warning in application.validate_styles() {
+        eprintln!("shinekit: warning, {}", warning);
+    }
+}
+
+fn apply_theme_or_default(application: &mut App) {
+    if let Err(e) = application.apply_theme() {
+        eprintln!("shinekit: could not apply styles, {}", e);
+
+        if application.active_theme != "default" {
+            application.active_theme = "default".into();
+            if let Err(e) = application.apply_theme() {
+                eprintln!("shinekit: could not apply default styles either, {}", e);
+            }
+        }
+    }
 }
@@ -1,93 +1,67 @@
-//! tableview_delegate_internal.rs
+//! datasource.rs
 //!
-//! This is a struct that handles the nitty gritty implementation of [NS/UI]TableViews.
-//! These are classes that have a lot of delegate-based implementation, so this is
-//! essentially bridging that gap for Rust.
+//! ListViews don't own their data; they ask a `ListViewData` for it, one row at a
+//! time, as rows need showing. `Rows` sits between that and the backend, keeping
+//! hold of every row view it's built so the platform can hand them back for reuse.
 //!
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use std::sync::{Once, ONCE_INIT};
+use backend::{Backend, Node, Platform, RowSource};
+use listview::row::ListViewRow;
+use view::View;
 
-use objc::declare::ClassDecl;
-use objc::runtime::{Class, Object, Sel};
-use cocoa::base::{id, nil, NO, YES};
-use cocoa::foundation::{NSRect, NSPoint, NSSize, NSString, NSInteger};
-
-use shinekit::tableview::row::TableViewRow;
-use shinekit::view::register_view_class;
-
-pub trait TableViewData {
+pub trait ListViewData {
     fn number_of_items(&self) -> usize { 0 }
-    fn configure_item(&mut self, view: &TableViewRow, row: usize);
-}
 
-extern fn number_of_items<T: TableViewData>(this: &Object, _: Sel, _: id) -> NSInteger {
-    println!("number_of_items");
-    let count: usize;
+    /// Called once for each row view the list builds, before it's first configured.
+    /// Subviews that every row has belong here.
+    fn layout_item(&mut self, _row: &mut ListViewRow) {}
 
-    unsafe {
-        let state: usize = *this.get_ivar("shinekitDataSourceAndDelegate");
-        let state = state as *mut T;
-        count = (*state).number_of_items();
-    }
-    
-    return count as NSInteger;
-} 
-
-extern fn make_view<T: TableViewData>(this: &Object, _: Sel, table_view: id, _: id, row: NSInteger) -> id {
-    println!("make_view");
-    let mut cell: id;
-    let view: TableViewRow;
-    let mut requires_layout = false;
+    fn configure_item(&mut self, row: &mut ListViewRow);
+}
 
-    unsafe {       
-        let state: usize = *this.get_ivar("shinekitgSourceAndDelegate");
-        let state = state as *mut T;
+pub struct Rows<T: ListViewData> {
+    list: String,
+    datasource: T,
+    rows: Vec<ListViewRow>
+}
 
-        let title = NSString::alloc(nil).init_str("WHAT");
-        cell = msg_send![table_view, makeViewWithIdentifier:title owner:nil];
-        if cell == nil {
-            println!("    Creating new cell...");
-            requires_layout = true;
-            let default_frame = NSRect::new(NSPoint::new(0., 0.), NSSize::new(0., 0.));
-            let cls: id = msg_send![register_view_class(), alloc];
-            cell = msg_send![cls, initWithFrame:default_frame];
-            msg_send![cell, setIdentifier:title];
-            msg_send![cell, setWantsLayer:YES];
-            msg_send![cell, setTranslatesAutoresizingMaskIntoConstraints:NO];
+impl<T: ListViewData> Rows<T> {
+    pub fn new(list: &str, datasource: T) -> Self {
+        Rows {
+            list: list.into(),
+            datasource,
+            rows: vec![]
         }
-        
-        view = TableViewRow {
-            row: row as usize,
-            view: cell
-        };
-        println!("Configuring cell...");
-        (*state).configure_item(&view, row as usize);
-        println!("Configured Cell");
     }
-
-    return cell;
 }
 
-pub fn register_listview_delegate_class<T: TableViewData>() -> *const Class {
-    static mut delegate_class: *const Class = 0 as *const Class;
-    static INIT: Once = ONCE_INIT;
-
-    INIT.call_once(|| unsafe {
-        let superclass = Class::get("NSObject").unwrap();
-        let mut decl = ClassDecl::new("shinekitDataSourceAndDelegate", superclass).unwrap();
+impl<T: ListViewData> RowSource<Node> for Rows<T> {
+    fn number_of_rows(&self) -> usize {
+        self.datasource.number_of_items()
+    }
 
-        // Add callback methods
-        decl.add_method(sel!(numberOfRowsInTableView:), number_of_items::<T> as extern fn(&Object, _, id) -> NSInteger);
-        decl.add_method(sel!(tableView:viewForTableColumn:row:), make_view::<T> as extern fn(&Object, _, id, id, NSInteger) -> id);
+    fn row(&mut self, index: usize, reusing: Option<&Node>) -> &Node {
+        let position = reusing.and_then(|node| {
+            self.rows.iter().position(|row| Platform::same_node(&row.view.backing_node, node))
+        });
 
-        // Store internal state as user data
-        decl.add_ivar::<usize>("shinekitDataSourceAndDelegate");
-        delegate_class = decl.register();
-    });
+        let position = match position {
+            Some(position) => position,
+            None => {
+                let name = format!("{}-row-{}", self.list, self.rows.len());
+                let mut row = ListViewRow { index, view: View::named(&name) };
+                self.datasource.layout_item(&mut row);
+                self.rows.push(row);
+                self.rows.len() - 1
+            }
+        };
 
-    unsafe {
-        delegate_class
+        let row = &mut self.rows[position];
+        row.index = index;
+        self.datasource.configure_item(row);
+        row.view.activate_constraints();
+        &row.view.backing_node
     }
 }
@@ -1,23 +1,18 @@
 //! row.rs
 //!
-//! A default implementation for a TableView row, which... well, depending
-//! on the platform and/or environment, requires a few different things. This
-//! ensures that we at least have the following:
-//!
-//!     - Properly flipped coordinates, because it's {CURRENT_YEAR} and who
-//!         the hell judges from the bottom left. Confuses all newcomers.
+//! A row in a ListView. Rows are plain Views, named after the list they belong to,
+//! and get reused as the list scrolls - so a row might still have whatever the
+//! last item it showed put in it. Data sources should treat `configure_item` as
+//! "make this row show this item", not "fill in a blank row".
 //!
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use cocoa::base::{id};
-
-pub trait TableViewUI {
-    fn layout(&self, view: &TableViewRow);
-    fn update(&self, view: &TableViewRow);
-}
+use view::View;
 
-pub struct TableViewRow {
-    pub row: usize,
-    pub view: id
+#[derive(Debug)]
+pub struct ListViewRow {
+    /// The item this row is currently showing.
+    pub index: usize,
+    pub view: View
 }
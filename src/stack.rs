@@ -0,0 +1,109 @@
+//! stack.rs
+//!
+//! Stacks: views that arrange their subviews along an axis, so siblings don't
+//! need chaining together with `leading_relative_to` and friends. An HStack
+//! lays them out left to right, a VStack top to bottom, and a ZStack layers
+//! them on top of one another.
+//!
+//! Under the hood an HStack or VStack is just a flex container, and a ZStack an
+//! overlay one (see `layout`), so anything set here can be overridden from a
+//! stylesheet with the usual `gap`, `alignItems`, `justifyContent` and so on.
+//! Children can still use `flexGrow` and friends to share out the space.
+//!
+//! @author Ryan McGrath <ryan@rymc.io>
+//! @created 06/20/2018
+
+use backend::{Backend, Platform};
+use layout::Display;
+use layout::flex::{Align, Direction, Justify};
+use view::View;
+
+pub struct HStack;
+
+impl HStack {
+    /// A stack laying its subviews out in a row, centered vertically.
+    pub fn named(name: &str) -> View {
+        View::stack(name, Display::Flex, Direction::Row)
+    }
+}
+
+pub struct VStack;
+
+impl VStack {
+    /// A stack laying its subviews out in a column, centered horizontally.
+    pub fn named(name: &str) -> View {
+        View::stack(name, Display::Flex, Direction::Column)
+    }
+}
+
+pub struct ZStack;
+
+impl ZStack {
+    /// A stack layering its subviews, later ones on top. Subviews fill it unless
+    /// they have a size of their own, in which case they sit in the middle.
+    pub fn named(name: &str) -> View {
+        View::stack(name, Display::Overlay, Direction::Row)
+    }
+}
+
+impl View {
+    fn stack(name: &str, display: Display, direction: Direction) -> View {
+        let mut view = View::named(name);
+        view.display = display;
+        view.flex.direction = direction;
+        view.flex.align_items = Align::Center;
+        view.base_display = display;
+        view.base_flex = view.flex.clone();
+        view
+    }
+
+    /// Sets the space between a stack's subviews.
+    pub fn spacing(mut self, spacing: f64) -> Self {
+        self.flex.gap = spacing;
+        self.base_flex.gap = spacing;
+        self
+    }
+
+    /// Sets where a stack's subviews sit across its axis (e.g, top, middle or
+    /// bottom of an HStack). `Align::Stretch` makes them fill it.
+    pub fn alignment(mut self, alignment: Align) -> Self {
+        self.flex.align_items = alignment;
+        self.base_flex.align_items = alignment;
+        self
+    }
+
+    /// Sets how a stack spreads its subviews out along its axis when they don't
+    /// fill it.
+    pub fn distribution(mut self, distribution: Justify) -> Self {
+        self.flex.justify = distribution;
+        self.base_flex.justify = distribution;
+        self
+    }
+
+    /// Adds a subview to the end of this view's subviews. Nothing moves on screen
+    /// until the app lays out again; on a running app, `App::add_arranged_subview`
+    /// takes care of that.
+    pub fn add_arranged_subview(&mut self, view: View) {
+        Platform::add_subview(&self.backing_node, &view.backing_node);
+        self.subviews.push(view);
+    }
+
+    /// Like `add_arranged_subview`, but puts it at `index` (or at the end, if
+    /// that's past it).
+    pub fn insert_arranged_subview(&mut self, index: usize, view: View) {
+        let index = index.min(self.subviews.len());
+        Platform::insert_subview(&self.backing_node, &view.backing_node, index);
+        self.subviews.insert(index, view);
+    }
+
+    /// Takes the named subview out, handing it back. Returns None if this view has
+    /// no direct subview by that name. As with adding, see
+    /// `App::remove_arranged_subview` for a running app.
+    pub fn remove_arranged_subview(&mut self, name: &str) -> Option<View> {
+        let index = self.subviews.iter().position(|view| view.name == name)?;
+        let mut view = self.subviews.remove(index);
+        Platform::remove_subview(&self.backing_node, &view.backing_node);
+        view.placement = None;
+        Some(view)
+    }
+}
@@ -0,0 +1,200 @@
+//  compile.rs
+//
+//  Checking stylesheets at build time. Call `compile_stylesheets` from your
+//  crate's build.rs (with shinekit as a build-dependency too), listing the
+//  files in the order they'd be handed to `shinekit::run`:
+//
+//      extern crate shinekit;
+//
+//      fn main() {
+//          shinekit::stylesheet::compile::compile_stylesheets(&[
+//              ("default", "src/styles/default.json"),
+//              ("dark", "src/styles/dark.json")
+//          ]);
+//      }
+//
+//  Files ending in .css use the CSS-like syntax, anything else is JSON, and
+//  "@import"s get merged in here, so the compiled output stands on its own.
+//  Each file is parsed, loaded the same way `load_styles` would (so tokens
+//  shared across files resolve) and checked against the schema. Anything
+//  wrong fails the build. What passes gets written out to OUT_DIR as Rust
+//  code that builds the value directly, which `stylesheet!` then includes:
+//
+//      shinekit::run(vec![
+//          stylesheet!("src/styles/default.json"),
+//          stylesheet!("dark", "src/styles/dark.json")
+//      ], app);
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/15/2018
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+
+use stylesheet::{StyleError, StyleSheet, load_styles};
+use stylesheet::import::resolve_imports;
+use stylesheet::schema::validate_styles;
+
+/// Validates the given (theme, path) pairs and writes out the Rust for each,
+/// panicking with every problem found if anything's wrong. Meant for build.rs.
+pub fn compile_stylesheets(stylesheets: &[(&str, &str)]) {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("compile_stylesheets should be called from a build script"));
+
+    for &(_, path) in stylesheets {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
+    let written = match write_stylesheets(stylesheets, &out_dir) {
+        Ok(written) => written,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|e| format!("    {}", e)).collect();
+            panic!("shinekit: stylesheets failed to validate:\n{}", errors.join("\n"));
+        }
+    };
+
+    // `stylesheet!` finds the compiled file through this, since a macro can't
+    // turn the path into the file name itself.
+    for (&(_, path), (destination, imports)) in stylesheets.iter().zip(written.iter()) {
+        for import in imports {
+            println!("cargo:rerun-if-changed={}", import.display());
+        }
+
+        println!("cargo:rustc-env={}{}={}", ENV_PREFIX, path, destination.display());
+    }
+}
+
+/// Where `stylesheet!` looks for a compiled stylesheet, keyed by its path.
+#[doc(hidden)]
+pub const ENV_PREFIX: &str = "SHINEKIT_STYLESHEET_";
+
+/// Checks the stylesheets and writes the Rust for each into `out_dir/shinekit`,
+/// returning where each one went along with the files it imported.
+pub fn write_stylesheets(stylesheets: &[(&str, &str)], out_dir: &Path) -> Result<Vec<(PathBuf, Vec<PathBuf>)>, Vec<StyleError>> {
+    let compiled = check_stylesheets(stylesheets)?;
+    let directory = out_dir.join("shinekit");
+    fs::create_dir_all(&directory).map_err(|e| {
+        vec![StyleError::new(format!("could not create {}: {}", directory.display(), e))]
+    })?;
+
+    stylesheets.iter().zip(compiled).map(|(&(theme, path), (value, imports))| {
+        let destination = directory.join(format!("{}.rs", file_stem(path)));
+        fs::write(&destination, to_rust(&value)).map_err(|e| {
+            vec![StyleError::new(format!("could not write {}: {}", destination.display(), e)).in_theme(theme)]
+        })?;
+
+        Ok((destination, imports))
+    }).collect()
+}
+
+/// Turns a stylesheet's path into a file name that stays inside OUT_DIR, however
+/// it was written ("../shared/base.json", "/abs/path.json", "C:\\styles.json").
+/// Anything that isn't alphanumeric gets escaped as "_" plus its hex code, so
+/// two different paths never end up with the same name.
+pub fn file_stem(path: &str) -> String {
+    path.chars().fold(String::new(), |mut stem, c| {
+        if c.is_ascii_alphanumeric() {
+            stem.push(c);
+        } else {
+            stem.push_str(&format!("_{:x}_", c as u32));
+        }
+
+        stem
+    })
+}
+
+/// The checking half of `compile_stylesheets`, without touching OUT_DIR.
+/// Returns each file's parsed value (in order, with its imports merged in but
+/// otherwise untouched) along with the files it imported, or every error found.
+pub fn check_stylesheets(stylesheets: &[(&str, &str)]) -> Result<Vec<(Value, Vec<PathBuf>)>, Vec<StyleError>> {
+    let mut parsed = vec![];
+    let mut imports = vec![];
+    for &(theme, path) in stylesheets {
+        let contents = fs::read_to_string(Path::new(path)).map_err(|e| {
+            vec![StyleError::new(format!("could not read {}: {}", path, e)).in_theme(theme)]
+        })?;
+
+        let (theme, mut styles) = StyleSheet::file(theme, Path::new(path), &contents).map_err(|e| vec![e])?;
+        let read = resolve_imports(&mut styles, Some(Path::new(path)), None).map_err(|e| vec![e.in_theme(&theme)])?;
+        imports.push(read.into_iter().map(|import| import.path).collect());
+        parsed.push((theme, styles));
+    }
+
+    // There's no view tree at build time, so this checks the stylesheets alone.
+    let loaded = load_styles(parsed.clone()).map_err(|e| vec![e])?;
+    let errors = validate_styles(&loaded, None);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(parsed.into_iter().map(|(_, value)| value).zip(imports).collect())
+}
+
+/// Writes a JSON value out as a Rust expression that builds it. Paths go through
+/// `::shinekit::serde_json`, so the crate including it doesn't need serde_json.
+pub fn to_rust(value: &Value) -> String {
+    match *value {
+        Value::Null => "::shinekit::serde_json::Value::Null".into(),
+        Value::Bool(b) => format!("::shinekit::serde_json::Value::Bool({})", b),
+
+        Value::Number(ref number) => match (number.as_u64(), number.as_i64(), number.as_f64()) {
+            (Some(n), _, _) => format!("::shinekit::serde_json::Value::from({}u64)", n),
+            (_, Some(n), _) => format!("::shinekit::serde_json::Value::from({}i64)", n),
+            (_, _, Some(n)) => format!("::shinekit::serde_json::Value::from({:?}f64)", n),
+            _ => "::shinekit::serde_json::Value::Null".into()
+        },
+
+        Value::String(ref string) => format!("::shinekit::serde_json::Value::String(String::from({:?}))", string),
+
+        Value::Array(ref values) => {
+            let values: Vec<String> = values.iter().map(to_rust).collect();
+            format!("::shinekit::serde_json::Value::Array(vec![{}])", values.join(", "))
+        },
+
+        Value::Object(ref map) => {
+            let mut rust = String::from("{ let mut map = ::shinekit::serde_json::Map::new(); ");
+            for (key, value) in map {
+                rust.push_str(&format!("map.insert(String::from({:?}), {}); ", key, to_rust(value)));
+            }
+
+            rust.push_str("::shinekit::serde_json::Value::Object(map) }");
+            rust
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn keeps_compiled_files_inside_out_dir() {
+        assert_eq!(file_stem("src/styles/default.json"), "src_2f_styles_2f_default_2e_json");
+        assert!(!file_stem("../../etc/passwd").contains('/'));
+        assert_ne!(file_stem("a/b.json"), file_stem("a_b.json"));
+    }
+
+    #[test]
+    fn compiles_a_fixture_into_out_dir() {
+        let root = env::temp_dir().join(format!("shinekit-compile-{}", process::id()));
+        let styles = root.join("styles");
+        fs::create_dir_all(&styles).unwrap();
+        fs::write(styles.join("default.json"), r#"{"root": {"backgroundColor": "red", "width": 100}}"#).unwrap();
+        fs::write(styles.join("broken.json"), r#"{"root": {"width": "wide"}}"#).unwrap();
+
+        let fixture = styles.join("default.json");
+        let fixture = fixture.to_str().unwrap();
+        let out_dir = root.join("out");
+        let written = write_stylesheets(&[("default", fixture)], &out_dir).unwrap();
+        assert_eq!(written, vec![(out_dir.join("shinekit").join(format!("{}.rs", file_stem(fixture))), vec![])]);
+        assert_eq!(fs::read_to_string(&written[0].0).unwrap(), to_rust(&json!({"root": {"backgroundColor": "red", "width": 100}})));
+
+        let broken = styles.join("broken.json");
+        let errors = write_stylesheets(&[("default", broken.to_str().unwrap())], &out_dir).unwrap_err();
+        assert_eq!(errors.iter().map(|e| e.json_path()).collect::<Vec<_>>(), vec!["default.root.width"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+//  css.rs
+//
+//  A CSS-ish way of writing stylesheets, for when nested JSON gets noisy (and
+//  because JSON doesn't do comments). It parses into exactly the same Value a
+//  JSON stylesheet would, so everything downstream is none the wiser:
+//
+//      /* Tokens keep their names as written. */
+//      tokens {
+//          brandBlue: #236cda;
+//      }
+//
+//      sidebar {
+//          background-color: #050505;
+//          width: 200;
+//          top: root.top;
+//          shadow { color: rgba(0, 0, 0, 0.3); offset-y: 2; blur: 4; }
+//      }
+//
+//      @media (max-width: 700) {
+//          sidebar { width: 0; }
+//      }
+//
+//      @import "colors.css", "typography.json";
+//
+//  Property names can be written kebab-case, and get turned into the camelCase
+//  the JSON uses. Values are numbers if they look like one, null/true/false as
+//  you'd expect, quoted strings if quoted (an array of them if there are a few,
+//  like `"header header" "sidebar main"`), and otherwise the text as written.
+//  Both /* block */ and // line comments work.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/16/2018
+
+use serde_json::{Deserializer, Map, Value, from_str};
+use stylesheet::StyleError;
+use stylesheet::import::IMPORT;
+
+/// Parses a CSS-like stylesheet into the Value `load_styles` expects. Errors
+/// say where they happened, as "line 3, column 12: ...".
+pub fn parse(source: &str) -> Result<Value, StyleError> {
+    let mut parser = Parser { chars: source.chars().collect(), position: 0 };
+    let styles = parser.block(Context::Sheet, None)?;
+    Ok(Value::Object(styles))
+}
+
+/// What the entries in a block are, which decides what happens to their names.
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    /// The top of the sheet, or a media block: entries are selectors.
+    Sheet,
+
+    /// Inside a rule: entries are properties, so names become camelCase.
+    Rule,
+
+    /// The tokens block: names are left exactly as written.
+    Tokens
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl Parser {
+    /// Reads entries until the closing brace (or the end, at the top level).
+    /// `opened_at` is where the opening brace was, for reporting it unclosed.
+    fn block(&mut self, context: Context, opened_at: Option<usize>) -> Result<Map<String, Value>, StyleError> {
+        let mut entries = Map::new();
+
+        loop {
+            self.skip_whitespace_and_comments()?;
+            match (self.peek(), opened_at) {
+                (None, None) => { return Ok(entries); },
+                (None, Some(opened_at)) => { return Err(self.error_at(opened_at, "this '{' is never closed")); },
+                (Some('}'), Some(_)) => {
+                    self.position += 1;
+                    return Ok(entries);
+                },
+                (Some('}'), None) => { return Err(self.error("unexpected '}'")); },
+                _ => {}
+            }
+
+            let start = self.position;
+            let stops: &[char] = if self.peek() == Some('@') { &['{', ';', '}'] } else { &['{', ':', ';', '}'] };
+            let written = self.text(stops)?;
+            let key = written.split_whitespace().collect::<Vec<&str>>().join(" ");
+            if key.is_empty() {
+                return Err(self.error("expected a selector or property name"));
+            }
+
+            let key = match context {
+                Context::Rule => camel_case(&key),
+                Context::Sheet | Context::Tokens => key
+            };
+
+            match self.peek() {
+                Some('{') => {
+                    let brace = self.position;
+                    self.position += 1;
+
+                    let inner = match context {
+                        Context::Sheet if key.starts_with('@') => Context::Sheet,
+                        Context::Sheet if key == "tokens" => Context::Tokens,
+                        Context::Sheet | Context::Rule => Context::Rule,
+                        Context::Tokens => Context::Tokens
+                    };
+
+                    let block = self.block(inner, Some(brace)).map_err(|e| e.at(&key))?;
+                    entries.insert(key, Value::Object(block));
+                },
+
+                Some(';') if context == Context::Sheet && key.starts_with(IMPORT) => {
+                    self.position += 1;
+                    let files = self.imports(&written.trim_start()[IMPORT.len()..], start);
+                    if let Value::Array(ref mut imports) = *entries.entry(IMPORT).or_insert_with(|| json!([])) {
+                        imports.extend(files.map_err(|e| e.at(IMPORT))?);
+                    }
+                },
+
+                Some(':') => {
+                    self.position += 1;
+                    let value_start = self.position;
+                    let text = self.text(&[';', '}'])?;
+                    let value = self.value(text.trim(), value_start).map_err(|e| e.at(&key))?;
+                    if self.peek() == Some(';') {
+                        self.position += 1;
+                    }
+
+                    entries.insert(key, value);
+                },
+
+                _ => { return Err(self.error_at(start, &format!("expected '{{' or ':' after \"{}\"", key))); }
+            }
+        }
+    }
+
+    /// Reads up to (but not including) one of `stops`, skipping comments and
+    /// anything inside quotes or parentheses - so "rgba(0, 0, 0, 0.3)" and
+    /// "\"a; b\"" come through whole.
+    fn text(&mut self, stops: &[char]) -> Result<String, StyleError> {
+        let mut text = String::new();
+        let mut depth = 0;
+
+        while let Some(c) = self.peek() {
+            if depth == 0 && stops.contains(&c) {
+                break;
+            }
+
+            if self.at_comment() {
+                self.skip_whitespace_and_comments()?;
+                text.push(' ');
+                continue;
+            }
+
+            match c {
+                '"' => {
+                    text.push_str(&self.quoted()?);
+                    continue;
+                },
+                '(' => { depth += 1; },
+                ')' if depth > 0 => { depth -= 1; },
+                _ => {}
+            }
+
+            text.push(c);
+            self.position += 1;
+        }
+
+        Ok(text)
+    }
+
+    /// Reads a quoted string, quotes and escapes included, as written.
+    fn quoted(&mut self) -> Result<String, StyleError> {
+        let start = self.position;
+        let mut quoted = String::from("\"");
+        self.position += 1;
+
+        while let Some(c) = self.peek() {
+            quoted.push(c);
+            self.position += 1;
+
+            match c {
+                '\\' => if let Some(escaped) = self.peek() {
+                    quoted.push(escaped);
+                    self.position += 1;
+                },
+                '"' => { return Ok(quoted); },
+                '\n' => break,
+                _ => {}
+            }
+        }
+
+        Err(self.error_at(start, "this string is never closed"))
+    }
+
+    fn value(&self, text: &str, start: usize) -> Result<Value, StyleError> {
+        let start = start + self.chars[start..].iter().take_while(|c| c.is_whitespace()).count();
+
+        match text {
+            "" => Err(self.error_at(start, "expected a value")),
+            "null" => Ok(Value::Null),
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+
+            // One quoted string is a string; several in a row (as gridTemplateAreas
+            // takes them) are an array of strings.
+            quoted if quoted.starts_with('"') => {
+                let strings: Result<Vec<String>, _> = Deserializer::from_str(quoted).into_iter::<String>().collect();
+                match strings {
+                    Ok(ref strings) if strings.len() == 1 => Ok(Value::String(strings[0].clone())),
+                    Ok(strings) => Ok(Value::Array(strings.into_iter().map(Value::String).collect())),
+                    Err(_) => Err(self.error_at(start, &format!("expected one or more quoted strings, found {}", quoted)))
+                }
+            },
+
+            text => match from_str::<Value>(text) {
+                Ok(number @ Value::Number(_)) => Ok(number),
+                _ => Ok(Value::String(text.into()))
+            }
+        }
+    }
+
+    /// The files after an "@import", split on the commas between them - not
+    /// ones inside quotes, so a file name can have commas (or any spacing) in it.
+    fn imports(&self, text: &str, start: usize) -> Result<Vec<Value>, StyleError> {
+        let mut files = vec![String::new()];
+        let (mut quoted, mut escaped) = (false, false);
+
+        for c in text.chars() {
+            match c {
+                ',' if !quoted => {
+                    files.push(String::new());
+                    continue;
+                },
+                '"' if !escaped => { quoted = !quoted; },
+                _ => {}
+            }
+
+            escaped = c == '\\' && !escaped;
+            if let Some(file) = files.last_mut() {
+                file.push(c);
+            }
+        }
+
+        files.iter().map(|file| self.value(file.trim(), start)).collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+
+    fn at_comment(&self) -> bool {
+        self.peek() == Some('/') && matches!(self.chars.get(self.position + 1), Some('*') | Some('/'))
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), StyleError> {
+        loop {
+            while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) {
+                self.position += 1;
+            }
+
+            if !self.at_comment() {
+                return Ok(());
+            }
+
+            let start = self.position;
+            if self.chars[self.position + 1] == '/' {
+                while self.peek().map(|c| c != '\n').unwrap_or(false) {
+                    self.position += 1;
+                }
+            } else {
+                self.position += 2;
+                loop {
+                    match self.peek() {
+                        Some('*') if self.chars.get(self.position + 1) == Some(&'/') => {
+                            self.position += 2;
+                            break;
+                        },
+                        Some(_) => { self.position += 1; },
+                        None => { return Err(self.error_at(start, "this comment is never closed")); }
+                    }
+                }
+            }
+        }
+    }
+
+    fn error(&self, message: &str) -> StyleError {
+        self.error_at(self.position, message)
+    }
+
+    /// An error pointing at a character, counting lines and columns from 1.
+    fn error_at(&self, position: usize, message: &str) -> StyleError {
+        let before = &self.chars[..position.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        StyleError::new(format!("line {}, column {}: {}", line, column, message))
+    }
+}
+
+/// "background-color" -> "backgroundColor". Names already in camelCase pass
+/// through untouched.
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+
+    for c in name.chars() {
+        match c {
+            '-' if !camel.is_empty() => { upper = true; },
+            c if upper => {
+                camel.extend(c.to_uppercase());
+                upper = false;
+            },
+            c => camel.push(c)
+        }
+    }
+
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_into_the_same_shape_as_json() {
+        let styles = parse(r#"
+            /* Tokens keep their names as written. */
+            tokens {
+                brand-blue: #236cda;
+            }
+
+            sidebar {
+                background-color: $brand-blue; // the token, not a color yet
+                width: 200;
+                top: root.top + 8;
+                shadow { color: rgba(0, 0, 0, 0.3); offset-y: 2; }
+            }
+
+            @media (max-width: 700) {
+                sidebar { width: 0; font-family: "Helvetica; Neue"; }
+            }
+
+            .card { grid-template-areas: "header header" "sidebar main"; opacity: null }
+        "#).unwrap();
+
+        assert_eq!(styles, json!({
+            "tokens": {"brand-blue": "#236cda"},
+            "sidebar": {
+                "backgroundColor": "$brand-blue",
+                "width": 200,
+                "top": "root.top + 8",
+                "shadow": {"color": "rgba(0, 0, 0, 0.3)", "offsetY": 2}
+            },
+            "@media (max-width: 700)": {"sidebar": {"width": 0, "fontFamily": "Helvetica; Neue"}},
+            ".card": {"gridTemplateAreas": ["header header", "sidebar main"], "opacity": null}
+        }));
+    }
+
+    #[test]
+    fn says_where_a_bad_value_is() {
+        let error = parse("sidebar {\n  width: 200;\n  height: ;\n}").unwrap_err();
+        assert_eq!(error.to_string(), "sidebar.height: line 3, column 11: expected a value");
+
+        let error = parse("title {\n  font-family: \"Helvetica\" Neue;\n}").unwrap_err();
+        assert_eq!(error.to_string(), "title.fontFamily: line 2, column 16: expected one or more quoted strings, found \"Helvetica\" Neue");
+    }
+
+    #[test]
+    fn says_where_an_unclosed_block_opened() {
+        let error = parse("sidebar { width: 200; }\n\n@media (max-width: 700) {\n  sidebar {\n    width: 0;\n}").unwrap_err();
+        assert_eq!(error.to_string(), "@media (max-width: 700): line 3, column 25: this '{' is never closed");
+
+        assert_eq!(parse("/* never\nclosed").unwrap_err().to_string(), "line 1, column 1: this comment is never closed");
+        assert_eq!(parse("}").unwrap_err().to_string(), "line 1, column 1: unexpected '}'");
+    }
+}
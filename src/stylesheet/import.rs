@@ -0,0 +1,198 @@
+//  import.rs
+//
+//  Splitting a stylesheet across files. A stylesheet can list other files to
+//  pull in under an "@import" key:
+//
+//      { "@import": ["colors.json", "typography.css"], "sidebar": { ... } }
+//
+//  Imports are read relative to the file doing the importing, merged together
+//  in the order they're listed, and then the importing stylesheet is merged
+//  over the top - so whatever a file says itself wins over what it imports.
+//  Nulls survive this, so an imported file can still unset something the
+//  theme inherits.
+//
+//  Imported files can import files of their own. Stylesheets that weren't read
+//  from a file (e.g, via `include_str!`) import relative to the app's asset
+//  root; see `App::asset_root`.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/17/2018
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+
+use stylesheet::{StyleError, StyleSheet};
+use util::layer_json_values;
+
+/// The key a stylesheet lists its imports under.
+pub const IMPORT: &str = "@import";
+
+/// A file that was imported, and what it says itself - without "@import" and
+/// whatever that pulled in.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: PathBuf,
+    pub styles: Value
+}
+
+/// Merges everything a stylesheet imports underneath it, taking the "@import"
+/// key out along the way. `file` is where the stylesheet was read from, if it
+/// was; imports are relative to that, or to `asset_root` if not. Returns every
+/// file that was read, in the order they were merged (a file's own imports come
+/// before it), so callers can watch them for changes and `inspect` can tell
+/// which one a property came from.
+pub fn resolve_imports(styles: &mut Value, file: Option<&Path>, asset_root: Option<&Path>) -> Result<Vec<Import>, StyleError> {
+    resolve_imports_watching(styles, file, asset_root, &mut vec![])
+}
+
+/// Like `resolve_imports`, but also notes every file it goes to read in
+/// `watched` - including one it couldn't read or parse - so a watcher can
+/// notice it being created or fixed.
+pub fn resolve_imports_watching(styles: &mut Value, file: Option<&Path>, asset_root: Option<&Path>, watched: &mut Vec<PathBuf>) -> Result<Vec<Import>, StyleError> {
+    let base = file.and_then(Path::parent).or(asset_root);
+
+    // The file itself goes on the stack first, so importing it is caught
+    // right away rather than after it's been merged in once.
+    let mut stack: Vec<PathBuf> = file.map(canonical).into_iter().collect();
+    let mut read = vec![];
+    resolve(styles, base, &mut stack, &mut read, watched)?;
+    Ok(read)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Does the work for `resolve_imports`, handing back what `styles` said before
+/// its imports were merged in.
+fn resolve(styles: &mut Value, base: Option<&Path>, stack: &mut Vec<PathBuf>, read: &mut Vec<Import>, watched: &mut Vec<PathBuf>) -> Result<Value, StyleError> {
+    let imports = match styles.as_object_mut().and_then(|styles| styles.shift_remove(IMPORT)) {
+        Some(imports) => files(&imports).map_err(|e| e.at(IMPORT))?,
+        None => { return Ok(styles.clone()); }
+    };
+
+    let written = styles.clone();
+    let base = match base {
+        Some(base) => base,
+        None if imports.is_empty() => { return Ok(written); },
+        None => {
+            return Err(StyleError::new("this stylesheet wasn't read from a file, so imports need an asset root to be relative to (see App::asset_root)").at(IMPORT));
+        }
+    };
+
+    let mut merged = json!({});
+    for import in imports {
+        let path = base.join(&import);
+        let key = canonical(&path);
+        if stack.contains(&key) {
+            return Err(StyleError::new(format!("{} ends up importing itself", path.display())).at(IMPORT));
+        }
+
+        watched.push(path.clone());
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            StyleError::new(format!("could not read {}: {}", path.display(), e)).at(IMPORT)
+        })?;
+
+        let (_, mut imported) = StyleSheet::file("", &path, &contents).map_err(|e| {
+            StyleError::new(format!("in {}, {}", path.display(), e)).at(IMPORT)
+        })?;
+
+        stack.push(key);
+        let imported_written = resolve(&mut imported, path.parent(), stack, read, watched)?;
+        stack.pop();
+
+        layer_json_values(&mut merged, &imported);
+        read.push(Import { path, styles: imported_written });
+    }
+
+    layer_json_values(&mut merged, styles);
+    *styles = merged;
+    Ok(written)
+}
+
+/// "@import" takes a single file, or a list of them.
+fn files(imports: &Value) -> Result<Vec<String>, StyleError> {
+    let error = || StyleError::new(format!("expected a file name or a list of them, found {}", imports));
+
+    match *imports {
+        Value::String(ref file) => Ok(vec![file.clone()]),
+        Value::Array(ref files) => files.iter().map(|file| file.as_str().map(String::from).ok_or_else(error)).collect(),
+        _ => Err(error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("shinekit-import-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for &(file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn merges_imports_in_order_under_the_importing_sheet() {
+        let dir = fixture("order", &[
+            ("main.json", r#"{"@import": ["colors.json", "sizes.css"], "sidebar": {"width": 300}}"#),
+            ("colors.json", r#"{"@import": "base.json", "sidebar": {"backgroundColor": "red"}}"#),
+            ("base.json", r#"{"sidebar": {"backgroundColor": "white", "opacity": 0.5}}"#),
+            ("sizes.css", "sidebar { width: 200; height: 100; opacity: null; }")
+        ]);
+
+        let main = dir.join("main.json");
+        let mut styles = StyleSheet::file("default", &main, &fs::read_to_string(&main).unwrap()).unwrap().1;
+        let read = resolve_imports(&mut styles, Some(&main), None).unwrap();
+
+        // Nulls are kept, so they can still unset what the theme inherits.
+        assert_eq!(styles, json!({"sidebar": {"backgroundColor": "red", "opacity": null, "width": 300, "height": 100}}));
+        let files: Vec<PathBuf> = read.into_iter().map(|import| import.path).collect();
+        assert_eq!(files, vec![dir.join("base.json"), dir.join("colors.json"), dir.join("sizes.css")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_files_that_import_themselves() {
+        let dir = fixture("cycles", &[
+            ("self.json", r#"{"@import": "self.json"}"#),
+            ("a.json", r#"{"@import": "b.json"}"#),
+            ("b.json", r#"{"@import": "a.json"}"#)
+        ]);
+
+        // Caught before the file is read a second time.
+        let (mut styles, mut watched) = (json!({"@import": "self.json"}), vec![]);
+        let error = resolve_imports_watching(&mut styles, Some(&dir.join("self.json")), None, &mut watched).unwrap_err();
+        assert_eq!(error.message, format!("{} ends up importing itself", dir.join("self.json").display()));
+        assert!(watched.is_empty());
+
+        let mut styles = json!({"@import": "b.json"});
+        let error = resolve_imports(&mut styles, Some(&dir.join("a.json")), None).unwrap_err();
+        assert_eq!(error.message, format!("{} ends up importing itself", dir.join("a.json").display()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imports_inline_sheets_from_the_asset_root() {
+        let dir = fixture("assets", &[("colors, dark.json", r#"{"sidebar": {"backgroundColor": "black"}}"#)]);
+
+        let mut styles = StyleSheet::css("dark", "@import \"colors, dark.json\";\nsidebar { width: 10; }").unwrap().1;
+        assert_eq!(styles[IMPORT], json!(["colors, dark.json"]));
+        resolve_imports(&mut styles, None, Some(&dir)).unwrap();
+        assert_eq!(styles, json!({"sidebar": {"backgroundColor": "black", "width": 10}}));
+
+        let mut styles = json!({"@import": "colors, dark.json"});
+        assert_eq!(resolve_imports(&mut styles, None, None).unwrap_err().json_path(), IMPORT);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
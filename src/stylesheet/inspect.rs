@@ -0,0 +1,165 @@
+//  inspect.rs
+//
+//  Answers "where did this value come from?" for a view's computed style. By
+//  the time styles are applied, themes have been merged over default, tokens
+//  swapped out and media blocks folded in, so the answer can't be read off the
+//  merged JSON. Instead, this keeps the stylesheets as they were handed in and
+//  replays the cascade to find which one last wrote each property.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/12/2018
+
+use std::fmt::Write;
+use serde_json::{Map, Value};
+
+use stylesheet::{StyleSource, lineage};
+use stylesheet::media::MediaQuery;
+use stylesheet::selectors::{Element, Rules};
+
+/// One property of a view's computed style, and where it was written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    pub property: String,
+
+    /// The value as applied, with tokens resolved.
+    pub value: Value,
+
+    /// The value as written in the stylesheet, e.g "$brandBlue".
+    pub written: Value,
+
+    pub theme: String,
+    pub file: Option<String>,
+
+    /// The rule the property sits in. Rules inside a media block are written as
+    /// "@media (max-width: 700) > sidebar".
+    pub rule: String
+}
+
+/// The stylesheets that feed into a theme, in the order they were merged. This
+/// mirrors `load_styles`: the theme sits on top of the theme it extends, and so
+/// on down, with the last stylesheet given for each theme being the one used.
+/// Stylesheets that import others count as each of the files they were built
+/// from, so properties get traced to the file that actually set them.
+fn layers<'a>(sources: &'a [StyleSource], theme: &str) -> Vec<&'a StyleSource> {
+    let mut stylesheets = Map::new();
+    for source in sources {
+        stylesheets.insert(source.theme.clone(), source.styles.clone());
+    }
+
+    let names = match lineage(&stylesheets, theme) {
+        Ok(lineage) => lineage,
+        Err(_) => { return vec![]; }
+    };
+
+    names.iter().filter_map(|name| sources.iter().rev().find(|source| &source.theme == name)).flat_map(|source| {
+        if source.layers.is_empty() { vec![source] } else { source.layers.iter().collect() }
+    }).collect()
+}
+
+/// Traces every property in a view's computed style back to the stylesheet
+/// that set it. `theme` is the merged theme (as `load_styles` produced it),
+/// `effective` is that theme resolved for the window's current size, and `path`
+/// runs from the root view down to the one being inspected.
+pub fn trace(sources: &[StyleSource], name: &str, theme: &Map<String, Value>, effective: &Map<String, Value>, size: (f64, f64), path: &[Element]) -> Vec<Provenance> {
+    let layers = layers(sources, name);
+    let (style, origins) = match Rules::new(effective).computed_style(path) {
+        Some(computed) => computed,
+        None => { return vec![]; }
+    };
+
+    let media: Vec<&String> = theme.keys().filter(|key| match MediaQuery::parse(key) {
+        Some(Ok(query)) => query.matches(size.0, size.1),
+        _ => false
+    }).collect();
+
+    let mut trace = vec![];
+    for (property, value) in style.as_object().into_iter().flat_map(|style| style.iter()) {
+        let rule = match origins.get(property) {
+            Some(rule) => rule,
+            None => { continue; }
+        };
+
+        // Media blocks get merged in after everything else, the last one winning,
+        // so check those first - then fall back to the rule itself.
+        let places = media.iter().rev().map(|query| vec![query.as_str(), rule.as_str()]).chain(Some(vec![rule.as_str()]));
+        let found = places.filter_map(|place| {
+            layers.iter().rev().filter_map(|layer| {
+                lookup(&layer.styles, &place).and_then(|rule| rule.get(property)).map(|written| (layer, place.clone(), written))
+            }).next()
+        }).next();
+
+        if let Some((layer, place, written)) = found {
+            trace.push(Provenance {
+                property: property.clone(),
+                value: value.clone(),
+                written: written.clone(),
+                theme: layer.theme.clone(),
+                file: layer.file.clone(),
+                rule: place.join(" > ")
+            });
+        }
+    }
+
+    trace
+}
+
+fn lookup<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+/// Lays a trace out for humans, one property per line.
+pub fn report(view: &str, trace: &[Provenance]) -> String {
+    let mut report = format!("{}\n", view);
+    if trace.is_empty() {
+        report.push_str("  (no styles apply)\n");
+        return report;
+    }
+
+    let width = trace.iter().map(|entry| entry.property.len()).max().unwrap_or(0);
+    for entry in trace {
+        let file = entry.file.as_ref().map(|file| format!(" ({})", file)).unwrap_or_default();
+        let _ = write!(report, "  {:width$}  {}", entry.property, entry.value, width = width);
+        if entry.written != entry.value {
+            let _ = write!(report, " (written as {})", entry.written);
+        }
+
+        let _ = writeln!(report, "\n  {:width$}    from \"{}\" in {}{}", "", entry.rule, entry.theme, file, width = width);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+    use stylesheet::{StyleSheet, load_sources};
+    use view::ViewKind;
+
+    #[test]
+    fn traces_properties_to_the_file_that_imported_them() {
+        let dir = env::temp_dir().join(format!("shinekit-inspect-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("colors.json"), r##"{"sidebar": {"backgroundColor": "#236cda", "width": 100}}"##).unwrap();
+
+        let mut source = StyleSource::inline(StyleSheet::default(r#"{"@import": "colors.json", "sidebar": {"width": 200}}"#).unwrap());
+        source.resolve_imports(Some(&dir), &mut vec![]).unwrap();
+        let sources = vec![source];
+        let styles = load_sources(&sources).unwrap();
+        let theme = styles["default"].as_object().unwrap();
+
+        let path = vec![
+            Element { name: "root".into(), kind: ViewKind::View, classes: vec![] },
+            Element { name: "sidebar".into(), kind: ViewKind::View, classes: vec![] }
+        ];
+
+        let trace = trace(&sources, "default", theme, theme, (800., 600.), &path);
+        let file_for = |property: &str| trace.iter().find(|entry| entry.property == property).map(|entry| entry.file.clone());
+        assert_eq!(file_for("backgroundColor"), Some(Some(dir.join("colors.json").display().to_string())));
+        assert_eq!(file_for("width"), Some(None));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
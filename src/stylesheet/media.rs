@@ -0,0 +1,173 @@
+//  media.rs
+//
+//  Breakpoints, a la CSS media queries. A theme can hold blocks like
+//
+//      "@media (max-width: 700)": { "sidebar": { "width": 0 } }
+//
+//  and whenever the window is a size the query matches, the block's rules are
+//  merged over the theme's own (in the order the blocks are written). Supported
+//  features are min-width, max-width, min-height and max-height, in points, and
+//  can be combined with "and". These get re-evaluated as the window resizes.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/11/2018
+
+use serde_json::{Map, Value};
+
+use stylesheet::StyleError;
+use util::merge_json_values;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Feature {
+    MinWidth,
+    MaxWidth,
+    MinHeight,
+    MaxHeight
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    conditions: Vec<(Feature, f64)>
+}
+
+impl MediaQuery {
+    /// Parses a stylesheet key like "@media (min-width: 400) and (max-width: 700)".
+    /// Returns None for keys that aren't media queries at all.
+    pub fn parse(key: &str) -> Option<Result<MediaQuery, StyleError>> {
+        if !is_media_query(key) {
+            return None;
+        }
+
+        let mut conditions = vec![];
+        for condition in key["@media".len()..].split(" and ") {
+            match parse_condition(condition.trim()) {
+                Ok(condition) => conditions.push(condition),
+                Err(e) => { return Some(Err(e)); }
+            }
+        }
+
+        Some(Ok(MediaQuery { conditions }))
+    }
+
+    pub fn matches(&self, width: f64, height: f64) -> bool {
+        self.conditions.iter().all(|&(feature, value)| match feature {
+            Feature::MinWidth => width >= value,
+            Feature::MaxWidth => width <= value,
+            Feature::MinHeight => height >= value,
+            Feature::MaxHeight => height <= value
+        })
+    }
+}
+
+pub fn is_media_query(key: &str) -> bool {
+    key.starts_with("@media")
+}
+
+fn parse_condition(condition: &str) -> Result<(Feature, f64), StyleError> {
+    let inner = match condition.strip_prefix('(').and_then(|c| c.strip_suffix(')')) {
+        Some(inner) => inner,
+        None => { return Err(StyleError::new(format!("expected a condition like (max-width: 700), found '{}'", condition))); }
+    };
+
+    let mut parts = inner.splitn(2, ':');
+    let feature = match parts.next().unwrap_or("").trim() {
+        "min-width" => Feature::MinWidth,
+        "max-width" => Feature::MaxWidth,
+        "min-height" => Feature::MinHeight,
+        "max-height" => Feature::MaxHeight,
+        other => { return Err(StyleError::new(format!("unknown media feature '{}'", other))); }
+    };
+
+    let value = parts.next().unwrap_or("").trim();
+    match value.parse::<f64>() {
+        Ok(value) => Ok((feature, value)),
+        Err(_) => Err(StyleError::new(format!("expected a number of points in '{}', found '{}'", condition, value)))
+    }
+}
+
+/// Checks every media block in a theme up front, so a typo shows up when the
+/// stylesheet loads rather than the first time the window crosses a breakpoint.
+pub fn validate_media(theme: &Map<String, Value>) -> Result<(), StyleError> {
+    for (key, value) in theme {
+        if let Some(query) = MediaQuery::parse(key) {
+            query.map_err(|e| e.at(key))?;
+            if !value.is_object() {
+                return Err(StyleError::new(format!("expected an object of rules, found {}", value)).at(key));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The styles that are in effect for a window of the given size: the theme,
+/// minus its media blocks, with the ones that match merged in.
+pub fn resolve_media(theme: &Map<String, Value>, width: f64, height: f64) -> Map<String, Value> {
+    let mut resolved = Value::Object(theme.iter().filter(|&(key, _)| !is_media_query(key)).map(|(key, value)| {
+        (key.clone(), value.clone())
+    }).collect());
+
+    for (key, value) in theme {
+        if let Some(Ok(query)) = MediaQuery::parse(key) {
+            if query.matches(width, height) {
+                merge_json_values(&mut resolved, value);
+            }
+        }
+    }
+
+    match resolved {
+        Value::Object(resolved) => resolved,
+        _ => Map::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(key: &str) -> MediaQuery {
+        MediaQuery::parse(key).unwrap().unwrap()
+    }
+
+    #[test]
+    fn evaluates_breakpoints() {
+        let narrow = query("@media (max-width: 700)");
+        assert!(narrow.matches(700., 500.));
+        assert!(narrow.matches(320., 500.));
+        assert!(!narrow.matches(701., 500.));
+
+        let medium = query("@media (min-width: 400) and (max-width: 700)");
+        assert!(medium.matches(400., 0.));
+        assert!(!medium.matches(399., 0.));
+        assert!(!medium.matches(800., 0.));
+
+        let short = query("@media (min-height: 200) and (max-height: 300)");
+        assert!(short.matches(1000., 250.));
+        assert!(!short.matches(1000., 301.));
+    }
+
+    #[test]
+    fn rejects_bad_queries() {
+        assert!(MediaQuery::parse("sidebar").is_none());
+        assert_eq!(MediaQuery::parse("@media max-width: 700").unwrap().unwrap_err().message, "expected a condition like (max-width: 700), found 'max-width: 700'");
+        assert_eq!(MediaQuery::parse("@media (max-depth: 700)").unwrap().unwrap_err().message, "unknown media feature 'max-depth'");
+        assert_eq!(MediaQuery::parse("@media (max-width: wide)").unwrap().unwrap_err().message, "expected a number of points in '(max-width: wide)', found 'wide'");
+
+        let theme = json!({"@media (min-width: 10)": {}, "@media (max-width: 700)": 5});
+        assert_eq!(validate_media(theme.as_object().unwrap()).unwrap_err().json_path(), "@media (max-width: 700)");
+    }
+
+    #[test]
+    fn merges_matching_blocks_in_order() {
+        let theme = json!({
+            "sidebar": {"width": 200, "backgroundColor": "red"},
+            "@media (max-width: 700)": {"sidebar": {"width": 80}},
+            "@media (max-width: 400)": {"sidebar": {"width": 0}}
+        });
+        let theme = theme.as_object().unwrap();
+
+        assert_eq!(Value::Object(resolve_media(theme, 800., 600.)), json!({"sidebar": {"width": 200, "backgroundColor": "red"}}));
+        assert_eq!(Value::Object(resolve_media(theme, 600., 600.)), json!({"sidebar": {"width": 80, "backgroundColor": "red"}}));
+        assert_eq!(Value::Object(resolve_media(theme, 300., 600.)), json!({"sidebar": {"width": 0, "backgroundColor": "red"}}));
+    }
+}
@@ -0,0 +1,358 @@
+//  stylesheet/mod.rs
+//
+//  A basic struct for implementing namespaced stylesheets. Useful
+//  so themes are built-in from the beginning. Currently mostly a
+//  fun little JSON based thing, but I could see this being made
+//  better in the future.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 05/30/2018
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use serde_json::{Map, Value, from_str};
+use util::merge_json_values;
+
+pub mod compile;
+pub mod css;
+pub mod import;
+pub mod inspect;
+pub mod media;
+pub mod schema;
+use self::import::{IMPORT, resolve_imports_watching};
+use self::media::validate_media;
+pub mod selectors;
+pub mod tokens;
+pub mod watch;
+use self::tokens::resolve_tokens;
+
+/// Something wrong with a stylesheet. Carries the theme it came from and the
+/// path of keys leading to the bad value, so it can be reported as something
+/// like `dark.calendar.backgroundColor: expected a number`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleError {
+    pub theme: String,
+    pub path: Vec<String>,
+    pub message: String
+}
+
+impl StyleError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        StyleError {
+            theme: String::new(),
+            path: vec![],
+            message: message.into()
+        }
+    }
+
+    /// Prepends a key to the path. Errors are built from the inside out, so each
+    /// level tacks on its own key as the error bubbles up.
+    pub fn at(mut self, key: &str) -> Self {
+        self.path.insert(0, key.into());
+        self
+    }
+
+    pub fn in_theme(mut self, theme: &str) -> Self {
+        self.theme = theme.into();
+        self
+    }
+
+    /// The full dotted path, theme first - e.g, `dark.calendar.backgroundColor`.
+    pub fn json_path(&self) -> String {
+        let mut keys = vec![];
+        if !self.theme.is_empty() {
+            keys.push(self.theme.as_str());
+        }
+
+        keys.extend(self.path.iter().map(|key| key.as_str()));
+        keys.join(".")
+    }
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = self.json_path();
+        if path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", path, self.message)
+        }
+    }
+}
+
+/// Includes a stylesheet that `compile::compile_stylesheets` checked and
+/// compiled from build.rs, so a broken stylesheet fails the build rather than
+/// the launch. Takes the same path that was given to the build script, and
+/// optionally a theme name first (it's "default" otherwise). The build script
+/// tells us where the compiled file went, as the path is no good as a file name.
+#[macro_export]
+macro_rules! stylesheet {
+    ($path:expr) => {
+        stylesheet!("default", $path)
+    };
+
+    ($theme:expr, $path:expr) => {
+        $crate::StyleSheet::preparsed($theme, include!(env!(
+            concat!("SHINEKIT_STYLESHEET_", $path),
+            concat!("shinekit: ", $path, " wasn't compiled; list it in build.rs's compile_stylesheets call")
+        )))
+    };
+}
+
+pub struct StyleSheet {}
+
+impl StyleSheet {
+    pub fn default(styles: &str) -> Result<(String, Value), StyleError> {
+        StyleSheet::theme("default", styles)
+    }
+
+    /// For stylesheets that were parsed and checked at build time; see `stylesheet!`.
+    pub fn preparsed(name: &str, styles: Value) -> Result<(String, Value), StyleError> {
+        Ok((name.into(), styles))
+    }
+
+    pub fn theme(name: &str, styles: &str) -> Result<(String, Value), StyleError> {
+        let value: Value = from_str(styles).map_err(|e| {
+            StyleError::new(format!("could not parse stylesheet: {}", e)).in_theme(name)
+        })?;
+
+        if !value.is_object() {
+            return Err(StyleError::new("a stylesheet should be a JSON object").in_theme(name));
+        }
+
+        Ok((name.into(), value))
+    }
+
+    /// Like `default`, but for the CSS-like syntax (see `css`).
+    pub fn default_css(styles: &str) -> Result<(String, Value), StyleError> {
+        StyleSheet::css("default", styles)
+    }
+
+    /// Like `theme`, but for the CSS-like syntax (see `css`).
+    pub fn css(name: &str, styles: &str) -> Result<(String, Value), StyleError> {
+        let value = css::parse(styles).map_err(|e| e.in_theme(name))?;
+        Ok((name.into(), value))
+    }
+
+    /// Parses the contents of a stylesheet file, going by its extension: ".css"
+    /// files use the CSS-like syntax, anything else is JSON.
+    pub fn file(name: &str, path: &Path, styles: &str) -> Result<(String, Value), StyleError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("css") => StyleSheet::css(name, styles),
+            _ => StyleSheet::theme(name, styles)
+        }
+    }
+}
+
+/// A stylesheet as it was loaded, before any merging. `file` is where it was
+/// read from, if it was read from a file at all.
+#[derive(Debug, Clone)]
+pub struct StyleSource {
+    pub theme: String,
+    pub file: Option<String>,
+    pub styles: Value,
+
+    /// If this stylesheet imports others, the files `styles` was built from, in
+    /// the order they were merged: the imports, then this file as written. Only
+    /// used to answer `inspect`.
+    pub layers: Vec<StyleSource>
+}
+
+impl StyleSource {
+    pub fn inline(stylesheet: (String, Value)) -> Self {
+        StyleSource { theme: stylesheet.0, file: None, styles: stylesheet.1, layers: vec![] }
+    }
+
+    /// Merges in whatever this stylesheet imports (see `import`), relative to
+    /// its file, or `asset_root` if it doesn't have one. Every file it goes to
+    /// read ends up in `watched`, whether or not that worked.
+    pub fn resolve_imports(&mut self, asset_root: Option<&Path>, watched: &mut Vec<PathBuf>) -> Result<(), StyleError> {
+        let mut written = self.styles.clone();
+        let file = self.file.as_ref().map(Path::new);
+        let imports = resolve_imports_watching(&mut self.styles, file, asset_root, watched).map_err(|e| e.in_theme(&self.theme))?;
+        if imports.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(written) = written.as_object_mut() {
+            written.shift_remove(IMPORT);
+        }
+
+        let theme = self.theme.clone();
+        self.layers = imports.into_iter().map(|import| StyleSource {
+            theme: theme.clone(),
+            file: Some(import.path.display().to_string()),
+            styles: import.styles,
+            layers: vec![]
+        }).collect();
+
+        self.layers.push(StyleSource { theme, file: self.file.clone(), styles: written, layers: vec![] });
+        Ok(())
+    }
+}
+
+/// `load_styles`, for sources that should stick around for inspection.
+pub fn load_sources(sources: &[StyleSource]) -> Result<Map<String, Value>, StyleError> {
+    load_styles(sources.iter().map(|source| (source.theme.clone(), source.styles.clone())).collect())
+}
+
+/// The key a stylesheet uses to name the theme it builds on.
+pub const EXTENDS: &str = "extends";
+
+/// Merges each theme over the theme it extends (all the way down), then
+/// resolves any tokens so every theme comes out as plain, fully specified
+/// values. If the same theme is given twice, the later one wins.
+pub fn load_styles(user_styles: Vec<(String, Value)>) -> Result<Map<String, Value>, StyleError> {
+    let mut stylesheets = Map::new();
+    for (name, value) in user_styles.into_iter() {
+        if !value.is_object() {
+            return Err(StyleError::new("a stylesheet should be a JSON object").in_theme(&name));
+        }
+
+        stylesheets.insert(name, value);
+    }
+
+    let mut styles = Map::new();
+    for name in stylesheets.keys() {
+        let mut style = json!({});
+        for ancestor in lineage(&stylesheets, name)? {
+            merge_json_values(&mut style, &stylesheets[&ancestor]);
+        }
+
+        if let Some(style) = style.as_object_mut() {
+            style.shift_remove(EXTENDS);
+        }
+
+        styles.insert(name.clone(), style);
+    }
+
+    for (name, theme) in styles.iter_mut() {
+        resolve_tokens(theme).map_err(|e| e.in_theme(name))?;
+        if let Some(theme) = theme.as_object() {
+            validate_media(theme).map_err(|e| e.in_theme(name))?;
+        }
+    }
+
+    Ok(styles)
+}
+
+/// The themes a theme is built from, starting with the one at the bottom and
+/// ending with the theme itself. Themes extend "default" unless they say
+/// otherwise; `"extends": null` builds on nothing at all.
+pub fn lineage(stylesheets: &Map<String, Value>, theme: &str) -> Result<Vec<String>, StyleError> {
+    let mut lineage = vec![theme.to_string()];
+    let mut current = theme;
+
+    while let Some(parent) = extends(stylesheets, current)? {
+        if !stylesheets.contains_key(parent) {
+            return Err(StyleError::new(format!("extends \"{}\", which isn't a loaded theme", parent)).at(EXTENDS).in_theme(current));
+        }
+
+        if lineage.iter().any(|name| name == parent) {
+            let cycle: Vec<&str> = lineage.iter().rev().map(|name| name.as_str()).chain(Some(parent)).collect();
+            return Err(StyleError::new(format!("themes extend each other in a loop ({})", cycle.join(" -> "))).at(EXTENDS).in_theme(theme));
+        }
+
+        lineage.insert(0, parent.to_string());
+        current = parent;
+    }
+
+    Ok(lineage)
+}
+
+fn extends<'a>(stylesheets: &'a Map<String, Value>, theme: &str) -> Result<Option<&'a str>, StyleError> {
+    match stylesheets[theme].get(EXTENDS) {
+        None if theme != "default" && stylesheets.contains_key("default") => Ok(Some("default")),
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(parent)) => Ok(Some(parent)),
+        Some(value) => Err(StyleError::new(format!("expected the name of a theme, found {}", value)).at(EXTENDS).in_theme(theme))
+    }
+}
+
+/// Reads an optional number out of a style object. Missing is fine; present but
+/// not a number is an error.
+pub fn optional_number(style: &Value, key: &str) -> Result<Option<f64>, StyleError> {
+    match style.get(key) {
+        Some(&Value::Null) | None => Ok(None),
+        Some(value) => value.as_f64().map(Some).ok_or_else(|| {
+            StyleError::new(format!("expected a number, found {}", value)).at(key)
+        })
+    }
+}
+
+/// Reads an optional string out of a style object. Same deal as `optional_number`.
+pub fn optional_str<'a>(style: &'a Value, key: &str) -> Result<Option<&'a str>, StyleError> {
+    match style.get(key) {
+        Some(&Value::Null) | None => Ok(None),
+        Some(value) => value.as_str().map(Some).ok_or_else(|| {
+            StyleError::new(format!("expected a string, found {}", value)).at(key)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes(stylesheets: Vec<(&str, Value)>) -> Result<Map<String, Value>, StyleError> {
+        load_styles(stylesheets.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+    }
+
+    #[test]
+    fn builds_themes_along_extends_chains() {
+        let styles = themes(vec![
+            ("default", json!({"sidebar": {"width": 200, "backgroundColor": "white"}, "title": {"fontSize": 14}})),
+            ("dark", json!({"sidebar": {"backgroundColor": "black"}})),
+            ("dark-high-contrast", json!({"extends": "dark", "title": {"fontSize": 18}})),
+            ("bare", json!({"extends": null, "title": {"fontSize": 12}}))
+        ]).unwrap();
+
+        assert_eq!(styles["dark-high-contrast"], json!({
+            "sidebar": {"width": 200, "backgroundColor": "black"},
+            "title": {"fontSize": 18}
+        }));
+        assert_eq!(styles["bare"], json!({"title": {"fontSize": 12}}));
+    }
+
+    #[test]
+    fn nulls_remove_inherited_properties() {
+        let styles = themes(vec![
+            ("default", json!({"sidebar": {"width": 200, "top": "root.top"}})),
+            ("dark", json!({"sidebar": {"top": null}, "content": {"width": 10, "top": null}}))
+        ]).unwrap();
+
+        // Even in rules default never had, a null shouldn't survive the merge.
+        assert_eq!(styles["dark"], json!({"sidebar": {"width": 200}, "content": {"width": 10}}));
+    }
+
+    #[test]
+    fn rejects_extends_cycles_and_unknown_themes() {
+        let error = themes(vec![
+            ("default", json!({})),
+            ("a", json!({"extends": "b"})),
+            ("b", json!({"extends": "a"}))
+        ]).unwrap_err();
+        assert_eq!(error.to_string(), "a.extends: themes extend each other in a loop (a -> b -> a)");
+
+        let error = themes(vec![("dark", json!({"extends": "light"}))]).unwrap_err();
+        assert_eq!(error.json_path(), "dark.extends");
+
+        let error = themes(vec![("dark", json!({"extends": 3}))]).unwrap_err();
+        assert_eq!(error.json_path(), "dark.extends");
+    }
+
+    #[test]
+    fn extends_regardless_of_load_order() {
+        let default = ("default", json!({"sidebar": {"width": 200}}));
+        let dark = ("dark", json!({"sidebar": {"backgroundColor": "black"}}));
+        let contrast = ("contrast", json!({"extends": "dark", "sidebar": {"width": 300}}));
+
+        let forwards = themes(vec![default.clone(), dark.clone(), contrast.clone()]).unwrap();
+        let backwards = themes(vec![contrast, dark, default]).unwrap();
+        for theme in &["default", "dark", "contrast"] {
+            assert_eq!(forwards[*theme], backwards[*theme]);
+        }
+
+        assert_eq!(forwards["contrast"], json!({"sidebar": {"width": 300, "backgroundColor": "black"}}));
+    }
+}
@@ -0,0 +1,442 @@
+//  schema.rs
+//
+//  What a stylesheet is allowed to say: every property we know how to apply,
+//  which kinds of view it applies to, and what sort of value it takes. Values
+//  are checked with the same parsing code that applies them, so the two can't
+//  drift apart.
+//
+//  Validation can run with or without a view tree. Without one (at build time)
+//  it checks keys, property names and values. With one, it also checks that
+//  view names used in selectors and anchors exist, and that Label-only
+//  properties are only set on rules that match Labels.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/15/2018
+
+use std::collections::HashSet;
+use serde_json::{Map, Value};
+
+use animation::check_transition;
+use appearance::Appearance;
+use color::Color;
+use label::Typography;
+use layout::{Anchor, Display};
+use layout::flex::Flex;
+use layout::grid::Grid;
+use stylesheet::StyleError;
+use stylesheet::media::{MediaQuery, is_media_query};
+use stylesheet::selectors::{Element, Selector};
+use view::{View, ViewKind, insets_from_json};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyType {
+    Color,
+    Number,
+
+    /// An anchor expression, like "sidebar.right + 8".
+    Anchor,
+
+    /// Padding/margin shorthand: one to four numbers.
+    Insets,
+
+    /// Checked by Typography, which knows its own fonts, weights and modes.
+    Typography,
+
+    /// Checked by Appearance, for borders, corners, opacity and shadows.
+    Appearance,
+
+    /// "constraints", "flex", "grid" or "overlay".
+    Display,
+
+    /// Checked by Flex, for flex containers and their children.
+    Flex,
+
+    /// Checked by Grid, for tracks, areas and placements.
+    Grid,
+
+    /// An object of property timings, like {"width": "200ms ease-out"}.
+    Transition
+}
+
+/// Properties any kind of view can set.
+pub const VIEW_PROPERTIES: [(&str, PropertyType); 43] = [
+    ("backgroundColor", PropertyType::Color),
+    ("width", PropertyType::Number),
+    ("height", PropertyType::Number),
+
+    ("top", PropertyType::Anchor),
+    ("left", PropertyType::Anchor),
+    ("leading", PropertyType::Anchor),
+    ("right", PropertyType::Anchor),
+    ("trailing", PropertyType::Anchor),
+    ("bottom", PropertyType::Anchor),
+
+    ("padding", PropertyType::Insets),
+    ("paddingTop", PropertyType::Number),
+    ("paddingLeft", PropertyType::Number),
+    ("paddingBottom", PropertyType::Number),
+    ("paddingRight", PropertyType::Number),
+    ("margin", PropertyType::Insets),
+    ("marginTop", PropertyType::Number),
+    ("marginLeft", PropertyType::Number),
+    ("marginBottom", PropertyType::Number),
+    ("marginRight", PropertyType::Number),
+
+    ("borderWidth", PropertyType::Appearance),
+    ("borderColor", PropertyType::Appearance),
+    ("cornerRadius", PropertyType::Appearance),
+    ("opacity", PropertyType::Appearance),
+    ("shadow", PropertyType::Appearance),
+
+    ("display", PropertyType::Display),
+    ("flexDirection", PropertyType::Flex),
+    ("flexWrap", PropertyType::Flex),
+    ("justifyContent", PropertyType::Flex),
+    ("alignItems", PropertyType::Flex),
+    ("gap", PropertyType::Flex),
+    ("flexGrow", PropertyType::Flex),
+    ("flexShrink", PropertyType::Flex),
+    ("flexBasis", PropertyType::Flex),
+    ("alignSelf", PropertyType::Flex),
+    ("gridTemplateColumns", PropertyType::Grid),
+    ("gridTemplateRows", PropertyType::Grid),
+    ("gridTemplateAreas", PropertyType::Grid),
+    ("rowGap", PropertyType::Grid),
+    ("columnGap", PropertyType::Grid),
+    ("gridColumn", PropertyType::Grid),
+    ("gridRow", PropertyType::Grid),
+    ("gridArea", PropertyType::Grid),
+
+    ("transition", PropertyType::Transition)
+];
+
+/// Properties only Labels can set, on top of `VIEW_PROPERTIES`.
+pub const LABEL_PROPERTIES: [(&str, PropertyType); 10] = [
+    ("textColor", PropertyType::Color),
+    ("fontFamily", PropertyType::Typography),
+    ("fontSize", PropertyType::Typography),
+    ("fontWeight", PropertyType::Typography),
+    ("fontStyle", PropertyType::Typography),
+    ("lineHeight", PropertyType::Typography),
+    ("letterSpacing", PropertyType::Typography),
+    ("textAlign", PropertyType::Typography),
+    ("lineLimit", PropertyType::Typography),
+    ("truncation", PropertyType::Typography)
+];
+
+/// Properties the "window" entry can set.
+pub const WINDOW_PROPERTIES: [(&str, PropertyType); 3] = [
+    ("backgroundColor", PropertyType::Color),
+    ("defaultWidth", PropertyType::Number),
+    ("defaultHeight", PropertyType::Number)
+];
+
+/// Checks a single value against its type. Null is always fine; it means
+/// "not set".
+pub fn check_value(property: &str, property_type: PropertyType, value: &Value) -> Result<(), StyleError> {
+    if value.is_null() {
+        return Ok(());
+    }
+
+    let single = || {
+        let mut style = Map::new();
+        style.insert(property.into(), value.clone());
+        Value::Object(style)
+    };
+
+    match property_type {
+        PropertyType::Color => Color::from_json(value).map(|_| ()),
+        PropertyType::Number => value.as_f64().map(|_| ()).ok_or_else(|| StyleError::new(format!("expected a number, found {}", value))),
+
+        PropertyType::Anchor => match value.as_str() {
+            Some(expression) => Anchor::parse(expression).map(|_| ()).map_err(|e| StyleError::new(e.to_string())),
+            None => Err(StyleError::new(format!("expected an anchor like \"sidebar.right + 8\", found {}", value)))
+        },
+
+        PropertyType::Insets => insets_from_json(value).map(|_| ()),
+
+        PropertyType::Display => match value.as_str() {
+            Some(display) => Display::parse(display).map(|_| ()),
+            None => Err(StyleError::new(format!("expected \"constraints\", \"flex\", \"grid\" or \"overlay\", found {}", value)))
+        },
+
+        // These report the property in the path themselves, so strip it back off.
+        PropertyType::Typography => Typography::default().apply_style(&single()).map_err(strip_property),
+        PropertyType::Appearance => Appearance::default().apply_style(&single()).map_err(strip_property),
+        PropertyType::Flex => Flex::default().apply_style(&single()).map_err(strip_property),
+        PropertyType::Grid => Grid::default().apply_style(&single()).map_err(strip_property),
+        PropertyType::Transition => check_transition(value)
+    }
+}
+
+fn strip_property(mut error: StyleError) -> StyleError {
+    if !error.path.is_empty() {
+        error.path.remove(0);
+    }
+
+    error
+}
+
+/// Looks a property up in the given schema.
+pub fn property_type(schema: &[(&str, PropertyType)], property: &str) -> Option<PropertyType> {
+    schema.iter().find(|&&(name, _)| name == property).map(|&(_, property_type)| property_type)
+}
+
+/// Checks every theme in a set of loaded styles (as `load_styles` returns them),
+/// and against a view tree if there is one. Problems a theme only has because it
+/// inherited them from default are reported once, against default.
+pub fn validate_styles(styles: &Map<String, Value>, root: Option<&View>) -> Vec<StyleError> {
+    let tree = root.map(Tree::new);
+    let mut errors: Vec<StyleError> = vec![];
+
+    for (name, theme) in styles {
+        let theme = match theme.as_object() {
+            Some(theme) => theme,
+            None => { continue; }
+        };
+
+        for error in validate(theme, tree.as_ref()) {
+            if !errors.iter().any(|existing| existing.path == error.path && existing.message == error.message) {
+                errors.push(error.in_theme(name));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Checks every rule in a single theme, returning everything that's wrong
+/// rather than stopping at the first problem: keys that aren't selectors,
+/// unknown properties, and values of the wrong type.
+pub fn validate_theme(theme: &Map<String, Value>) -> Vec<StyleError> {
+    validate(theme, None)
+}
+
+/// The bits of a view tree validation cares about.
+struct Tree {
+    names: HashSet<String>,
+    paths: Vec<Vec<Element>>
+}
+
+impl Tree {
+    fn new(root: &View) -> Self {
+        let paths = root.element_paths();
+        let names = paths.iter().filter_map(|path| path.last()).map(|element| element.name.clone()).collect();
+        Tree { names, paths }
+    }
+
+    /// The kinds of the views a selector matches in this tree.
+    fn kinds_matching(&self, selector: &Selector) -> Vec<ViewKind> {
+        let mut kinds = vec![];
+        for path in &self.paths {
+            let kind = &path[path.len() - 1].kind;
+            if selector.matches(path) && !kinds.contains(kind) {
+                kinds.push(kind.clone());
+            }
+        }
+
+        kinds
+    }
+}
+
+fn validate(theme: &Map<String, Value>, tree: Option<&Tree>) -> Vec<StyleError> {
+    let mut errors = vec![];
+
+    for (key, value) in theme {
+        if key == "window" {
+            let mut window_errors = vec![];
+            validate_rule(value, &WINDOW_PROPERTIES, &[], None, tree, &mut window_errors);
+            errors.extend(window_errors.into_iter().map(|e| e.at(key)));
+            continue;
+        }
+
+        if is_media_query(key) {
+            let mut block_errors = vec![];
+            match (MediaQuery::parse(key), value.as_object()) {
+                (Some(Err(e)), _) => block_errors.push(e),
+                (_, Some(block)) => { block_errors = validate(block, tree); },
+                (_, None) => block_errors.push(StyleError::new(format!("expected an object of rules, found {}", value)))
+            }
+
+            errors.extend(block_errors.into_iter().map(|e| e.at(key)));
+            continue;
+        }
+
+        let selector = match Selector::parse(key) {
+            Some(selector) => selector,
+            None => {
+                errors.push(StyleError::new(format!("'{}' isn't a view name, kind, class or media query", key)).at(key));
+                continue;
+            }
+        };
+
+        let mut rule_errors = vec![];
+        let mut kinds = None;
+        if let Some(tree) = tree {
+            for name in selector.names() {
+                if !tree.names.contains(name) {
+                    rule_errors.push(StyleError::new(format!("no view named '{}' exists in the view tree", name)));
+                }
+            }
+
+            kinds = Some(tree.kinds_matching(&selector));
+        }
+
+        validate_rule(value, &VIEW_PROPERTIES, &LABEL_PROPERTIES, kinds.as_ref(), tree, &mut rule_errors);
+        errors.extend(rule_errors.into_iter().map(|e| e.at(key)));
+    }
+
+    errors
+}
+
+/// `label_only` are properties that only make sense if the rule matches a Label,
+/// and `kinds` are the kinds of view the rule matches, if that's known.
+fn validate_rule(rule: &Value, schema: &[(&str, PropertyType)], label_only: &[(&str, PropertyType)], kinds: Option<&Vec<ViewKind>>, tree: Option<&Tree>, errors: &mut Vec<StyleError>) {
+    let properties = match rule.as_object() {
+        Some(properties) => properties,
+        None => {
+            errors.push(StyleError::new(format!("expected an object of style properties, found {}", rule)));
+            return;
+        }
+    };
+
+    for (property, value) in properties {
+        let property_type = match (property_type(schema, property), property_type(label_only, property)) {
+            (Some(property_type), _) => property_type,
+
+            (None, Some(property_type)) => {
+                let matches_labels = kinds.map(|kinds| kinds.is_empty() || kinds.contains(&ViewKind::Label)).unwrap_or(true);
+                if !matches_labels {
+                    errors.push(StyleError::new(format!("'{}' only applies to Labels, and this rule doesn't match any", property)).at(property));
+                    continue;
+                }
+
+                property_type
+            },
+
+            (None, None) => {
+                let known = schema.iter().chain(label_only.iter()).map(|&(name, _)| name);
+                let message = match closest(property, known) {
+                    Some(suggestion) => format!("unknown property '{}' (did you mean '{}'?)", property, suggestion),
+                    None => format!("unknown property '{}'", property)
+                };
+
+                errors.push(StyleError::new(message).at(property));
+                continue;
+            }
+        };
+
+        if let Err(e) = check_value(property, property_type, value) {
+            errors.push(e.at(property));
+            continue;
+        }
+
+        if let (PropertyType::Anchor, Some(tree), Some(expression)) = (property_type, tree, value.as_str()) {
+            if let Ok((anchor, _)) = Anchor::parse(expression) {
+                if !tree.names.contains(&anchor.view) {
+                    errors.push(StyleError::new(format!("no view named '{}' exists in the view tree", anchor.view)).at(property));
+                }
+            }
+        }
+    }
+}
+
+/// The known name closest to a misspelled one, if any is close enough to be
+/// what was meant.
+fn closest<'a, I: Iterator<Item = &'a str>>(property: &str, known: I) -> Option<&'a str> {
+    known.map(|name| (edit_distance(&property.to_lowercase(), &name.to_lowercase()), name))
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use label::Label;
+
+    fn problems(theme: Value, root: Option<&View>) -> Vec<String> {
+        let mut styles = Map::new();
+        styles.insert("default".into(), theme);
+        validate_styles(&styles, root).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn checks_view_properties() {
+        assert_eq!(problems(json!({"sidebar": {"widht": 100, "hieght": 10, "colour": "red"}}), None), vec![
+            "default.sidebar.widht: unknown property 'widht' (did you mean 'width'?)",
+            "default.sidebar.hieght: unknown property 'hieght' (did you mean 'height'?)",
+            "default.sidebar.colour: unknown property 'colour'"
+        ]);
+
+        let errors = problems(json!({"sidebar": {
+            "width": "wide", "backgroundColor": 12, "top": "root", "padding": [1, 2, 3, 4, 5], "opacity": 3
+        }}), None);
+
+        let paths: Vec<&str> = errors.iter().map(|e| e.split(':').next().unwrap()).collect();
+        assert_eq!(paths, vec![
+            "default.sidebar.width", "default.sidebar.backgroundColor", "default.sidebar.top",
+            "default.sidebar.padding", "default.sidebar.opacity"
+        ]);
+        assert_eq!(errors[0], "default.sidebar.width: expected a number, found \"wide\"");
+    }
+
+    #[test]
+    fn checks_label_properties() {
+        let root = View::named("root").subviews(vec![View::named("sidebar"), Label::named("title")]);
+        assert!(problems(json!({"title": {"textColor": "red", "fontSize": 18, "width": 100}}), Some(&root)).is_empty());
+
+        assert_eq!(problems(json!({"title": {"fontSzie": 18, "fontWeight": "heavyish", "textColor": true}}), Some(&root)).len(), 3);
+        assert_eq!(problems(json!({"title": {"fontSzie": 18}}), Some(&root)), vec![
+            "default.title.fontSzie: unknown property 'fontSzie' (did you mean 'fontSize'?)"
+        ]);
+
+        assert_eq!(problems(json!({"sidebar": {"fontSize": 18}}), Some(&root)), vec![
+            "default.sidebar.fontSize: 'fontSize' only applies to Labels, and this rule doesn't match any"
+        ]);
+    }
+
+    #[test]
+    fn checks_window_properties() {
+        assert_eq!(problems(json!({"window": {"defaultWidth": "big", "fontSize": 18}}), None), vec![
+            "default.window.defaultWidth: expected a number, found \"big\"",
+            "default.window.fontSize: unknown property 'fontSize'"
+        ]);
+    }
+
+    #[test]
+    fn checks_names_against_the_view_tree() {
+        let root = View::named("root").subviews(vec![View::named("sidebar")]);
+        assert_eq!(problems(json!({"sidebr": {"top": "contnet.top"}}), Some(&root)), vec![
+            "default.sidebr: no view named 'sidebr' exists in the view tree",
+            "default.sidebr.top: no view named 'contnet' exists in the view tree"
+        ]);
+
+        // Without a tree, names can't be checked at all.
+        assert!(problems(json!({"sidebr": {"top": "contnet.top"}}), None).is_empty());
+    }
+
+    #[test]
+    fn checks_inside_media_blocks() {
+        assert_eq!(problems(json!({"@media (max-width: 500)": {"sidebar": {"width": "none"}}}), None), vec![
+            "default.@media (max-width: 500).sidebar.width: expected a number, found \"none\""
+        ]);
+    }
+}
@@ -0,0 +1,253 @@
+//  selectors.rs
+//
+//  Lets stylesheet keys be more than just view names. A key can be:
+//
+//      - a view name:   "sidebar"
+//      - a view kind:   "Label", "View", "ListView" (the ViewKind variant names)
+//      - a class:       ".card"
+//      - a compound:    "Label.card", "sidebar.card.selected"
+//      - descendants:   "sidebar .card" (any .card somewhere inside sidebar)
+//
+//  Every rule that matches a view gets merged into its computed style, in
+//  order of specificity - same idea as CSS. Specificity is counted as
+//  (names, classes, kinds), compared in that order, so a name beats any
+//  number of classes, and a class beats any number of kinds. Rules with the
+//  same specificity apply in the order they're written, later ones winning.
+//  A plain per-name rule like "sidebar" therefore wins over ".card" or "View".
+//
+//  Keys that aren't selectors (e.g, anything starting with "@") are ignored.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/09/2018
+
+use std::collections::HashMap;
+use serde_json::{Map, Value};
+
+use util::merge_json_values;
+use view::ViewKind;
+
+/// What selectors get matched against - the bits of a View that matter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub kind: ViewKind,
+    pub classes: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Compound {
+    name: Option<String>,
+    kind: Option<ViewKind>,
+    classes: Vec<String>
+}
+
+impl Compound {
+    fn parse(compound: &str) -> Option<Compound> {
+        let mut segments = compound.split('.');
+        let head = segments.next().unwrap_or("");
+        let classes: Vec<String> = segments.map(|class| class.into()).collect();
+
+        if !head.chars().all(is_identifier_char) || classes.iter().any(|class| class.is_empty() || !class.chars().all(is_identifier_char)) {
+            return None;
+        }
+
+        if head.is_empty() && classes.is_empty() {
+            return None;
+        }
+
+        let (name, kind) = match ViewKind::from_name(head) {
+            Some(kind) => (None, Some(kind)),
+            None if head.is_empty() => (None, None),
+            None => (Some(head.into()), None)
+        };
+
+        Some(Compound { name, kind, classes })
+    }
+
+    fn matches(&self, element: &Element) -> bool {
+        self.name.as_ref().map(|name| *name == element.name).unwrap_or(true) &&
+            self.kind.as_ref().map(|kind| *kind == element.kind).unwrap_or(true) &&
+            self.classes.iter().all(|class| element.classes.contains(class))
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    parts: Vec<Compound>
+}
+
+impl Selector {
+    /// Parses a stylesheet key, returning None if it isn't a selector.
+    pub fn parse(selector: &str) -> Option<Selector> {
+        let mut parts = vec![];
+        for compound in selector.split_whitespace() {
+            parts.push(Compound::parse(compound)?);
+        }
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        Some(Selector { parts })
+    }
+
+    /// Every view name the selector mentions.
+    pub fn names(&self) -> Vec<&str> {
+        self.parts.iter().filter_map(|part| part.name.as_deref()).collect()
+    }
+
+    /// (names, classes, kinds) - compare these as a tuple.
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.parts.iter().fold((0, 0, 0), |(names, classes, kinds), part| {
+            (names + part.name.iter().count(), classes + part.classes.len(), kinds + part.kind.iter().count())
+        })
+    }
+
+    /// Checks the selector against a path of elements, from the root of the
+    /// tree down to (and including) the one being styled.
+    pub fn matches(&self, path: &[Element]) -> bool {
+        let (element, mut ancestors) = match path.split_last() {
+            Some(split) => split,
+            None => { return false; }
+        };
+
+        let (last, rest) = self.parts.split_last().unwrap();
+        if !last.matches(element) {
+            return false;
+        }
+
+        // Work outwards, matching each remaining part against the nearest ancestor
+        // that fits. Only descendant combinators exist, so nearest is always safe.
+        for part in rest.iter().rev() {
+            match ancestors.iter().rposition(|ancestor| part.matches(ancestor)) {
+                Some(index) => { ancestors = &ancestors[..index]; },
+                None => { return false; }
+            }
+        }
+
+        true
+    }
+}
+
+/// The rules in a theme, parsed once and sorted by specificity so computing
+/// a view's style is just a filter and a merge.
+pub struct Rules<'a> {
+    rules: Vec<(Selector, &'a str, &'a Value)>
+}
+
+impl<'a> Rules<'a> {
+    pub fn new(styles: &'a Map<String, Value>) -> Self {
+        let mut rules: Vec<(Selector, &str, &Value)> = styles.iter().filter_map(|(key, value)| {
+            Selector::parse(key).map(|selector| (selector, key.as_str(), value))
+        }).collect();
+
+        // Stable, so rules of equal specificity stay in the order they were written.
+        rules.sort_by_key(|rule| rule.0.specificity());
+        Rules { rules }
+    }
+
+    /// Merges every matching rule into one style object. Also returns, for each
+    /// top level property, the key of the rule it ended up coming from. Returns
+    /// None if no rules match at all.
+    pub fn computed_style(&self, path: &[Element]) -> Option<(Value, HashMap<String, String>)> {
+        let mut style = json!({});
+        let mut origins = HashMap::new();
+        let mut matched = false;
+
+        for &(ref selector, key, value) in &self.rules {
+            if !selector.matches(path) {
+                continue;
+            }
+
+            matched = true;
+            merge_json_values(&mut style, value);
+            if let Value::Object(ref properties) = *value {
+                for property in properties.keys() {
+                    origins.insert(property.clone(), key.to_string());
+                }
+            }
+        }
+
+        if matched { Some((style, origins)) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(name: &str, kind: ViewKind, classes: &[&str]) -> Element {
+        Element { name: name.into(), kind, classes: classes.iter().map(|class| class.to_string()).collect() }
+    }
+
+    fn specificity(selector: &str) -> (usize, usize, usize) {
+        Selector::parse(selector).unwrap().specificity()
+    }
+
+    #[test]
+    fn parses_selectors() {
+        for key in &["sidebar", "Label", ".card", "Label.card", "sidebar.card.selected", "sidebar .card", "main-content_2"] {
+            assert!(Selector::parse(key).is_some(), "{} should be a selector", key);
+        }
+
+        for key in &["@media (max-width: 600px)", "", " ", "sidebar.", "..card", "side#bar", "sidebar > .card"] {
+            assert!(Selector::parse(key).is_none(), "{} shouldn't be a selector", key);
+        }
+    }
+
+    #[test]
+    fn orders_specificity_by_names_then_classes_then_kinds() {
+        assert_eq!(specificity("sidebar"), (1, 0, 0));
+        assert_eq!(specificity("Label.card.selected"), (0, 2, 1));
+        assert_eq!(specificity("root sidebar .card"), (2, 1, 0));
+
+        assert!(specificity("sidebar") > specificity(".card.selected.wide"));
+        assert!(specificity(".card") > specificity("View Label"));
+        assert!(specificity("Label.card") > specificity(".card"));
+    }
+
+    #[test]
+    fn matches_descendants() {
+        let path = vec![
+            element("root", ViewKind::View, &[]),
+            element("sidebar", ViewKind::View, &["panel"]),
+            element("row", ViewKind::View, &["card"]),
+            element("title", ViewKind::Label, &["card", "selected"])
+        ];
+
+        let matches = |selector: &str| Selector::parse(selector).unwrap().matches(&path);
+        assert!(matches("title"));
+        assert!(matches("Label.card.selected"));
+        assert!(matches("sidebar .card"));
+        assert!(matches("root .panel .card Label"));
+        assert!(matches(".card .card"));
+        assert!(!matches("sidebar"));
+        assert!(!matches(".card .panel Label"));
+        assert!(!matches("row .card .card"));
+        assert!(!matches("View.selected"));
+    }
+
+    #[test]
+    fn computes_styles_in_specificity_order() {
+        let styles = json!({
+            "sidebar": {"width": 200},
+            "View": {"width": 10, "height": 10},
+            ".card": {"width": 50, "backgroundColor": "red"},
+            ".card.selected": {"backgroundColor": "blue"}
+        });
+        let rules = Rules::new(styles.as_object().unwrap());
+
+        let path = vec![element("sidebar", ViewKind::View, &["card", "selected"])];
+        let (style, origins) = rules.computed_style(&path).unwrap();
+        assert_eq!(style, json!({"width": 200, "height": 10, "backgroundColor": "blue"}));
+        assert_eq!(origins["width"], "sidebar");
+        assert_eq!(origins["height"], "View");
+        assert_eq!(origins["backgroundColor"], ".card.selected");
+
+        assert_eq!(rules.computed_style(&[element("title", ViewKind::Label, &[])]), None);
+    }
+}
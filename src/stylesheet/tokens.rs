@@ -0,0 +1,210 @@
+//  tokens.rs
+//
+//  Design tokens, a.k.a stylesheet variables. A stylesheet can declare a
+//  top-level "tokens" object, and anywhere else in the sheet can refer to
+//  them as "$name" - either as the whole value, or inside a string like
+//  "darken($brandBlue, 10%)". Tokens can refer to other tokens.
+//
+//  Only a '$' followed by a name (a letter or underscore, then letters,
+//  digits, '_' or '-') is a reference; any other '$' is left alone, so
+//  "$5" stays "$5". "$$" is a literal '$', for text like "$$brandBlue".
+//
+//  Tokens are resolved after a theme has been merged over default, so a
+//  theme can redefine a token and everything using it follows along.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/08/2018
+
+use serde_json::{Map, Value};
+use stylesheet::StyleError;
+
+/// Resolves every token reference in a (merged) theme, and strips the
+/// "tokens" section out afterwards since nothing downstream needs it.
+pub fn resolve_tokens(theme: &mut Value) -> Result<(), StyleError> {
+    let tokens = match theme.as_object_mut().and_then(|theme| theme.shift_remove("tokens")) {
+        Some(Value::Object(tokens)) => tokens,
+        Some(other) => {
+            return Err(StyleError::new(format!("expected an object of tokens, found {}", other)).at("tokens"));
+        },
+        None => Map::new()
+    };
+
+    let mut resolver = Resolver {
+        tokens: &tokens,
+        resolved: Map::new(),
+        stack: vec![]
+    };
+
+    for name in tokens.keys() {
+        resolver.resolve(name).map_err(|e| e.at(name).at("tokens"))?;
+    }
+
+    *theme = resolver.substitute(theme)?;
+    Ok(())
+}
+
+struct Resolver<'a> {
+    tokens: &'a Map<String, Value>,
+    resolved: Map<String, Value>,
+    stack: Vec<String>
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve(&mut self, name: &str) -> Result<Value, StyleError> {
+        if let Some(value) = self.resolved.get(name) {
+            return Ok(value.clone());
+        }
+
+        if self.stack.iter().any(|token| token == name) {
+            let mut cycle: Vec<String> = self.stack.iter().map(|token| format!("${}", token)).collect();
+            cycle.push(format!("${}", name));
+            return Err(StyleError::new(format!("tokens refer to each other in a cycle: {}", cycle.join(" -> "))));
+        }
+
+        let value = match self.tokens.get(name) {
+            Some(value) => value,
+            None => { return Err(StyleError::new(format!("unknown token '${}'", name))); }
+        };
+
+        self.stack.push(name.into());
+        let value = self.substitute(value)?;
+        self.stack.pop();
+
+        self.resolved.insert(name.into(), value.clone());
+        Ok(value)
+    }
+
+    fn substitute(&mut self, value: &Value) -> Result<Value, StyleError> {
+        match *value {
+            Value::String(ref string) => self.substitute_string(string),
+
+            Value::Array(ref values) => {
+                let mut substituted = vec![];
+                for (index, value) in values.iter().enumerate() {
+                    substituted.push(self.substitute(value).map_err(|e| e.at(&index.to_string()))?);
+                }
+
+                Ok(Value::Array(substituted))
+            },
+
+            Value::Object(ref map) => {
+                let mut substituted = Map::new();
+                for (key, value) in map {
+                    substituted.insert(key.clone(), self.substitute(value).map_err(|e| e.at(key))?);
+                }
+
+                Ok(Value::Object(substituted))
+            },
+
+            _ => Ok(value.clone())
+        }
+    }
+
+    fn substitute_string(&mut self, string: &str) -> Result<Value, StyleError> {
+        if !string.contains('$') {
+            return Ok(Value::String(string.into()));
+        }
+
+        // A string that's entirely one reference takes on the token's value as-is,
+        // so tokens can hold numbers and color objects too.
+        if let Some(name) = string.strip_prefix('$') {
+            if token_name(name).len() == name.len() && !name.is_empty() {
+                return self.resolve(name);
+            }
+        }
+
+        let mut output = String::new();
+        let mut rest = string;
+        while let Some(start) = rest.find('$') {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            if let Some(escaped) = after.strip_prefix('$') {
+                output.push('$');
+                rest = escaped;
+                continue;
+            }
+
+            let name = token_name(after);
+            if name.is_empty() {
+                output.push('$');
+            } else {
+                let value = self.resolve(name)?;
+                output.push_str(&embeddable(name, &value)?);
+            }
+
+            rest = &after[name.len()..];
+        }
+
+        output.push_str(rest);
+        Ok(Value::String(output))
+    }
+}
+
+/// The token name at the start of `string`, if there is one (otherwise "").
+fn token_name(string: &str) -> &str {
+    match string.chars().next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {
+            let end = string.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')).unwrap_or(string.len());
+            &string[..end]
+        },
+
+        _ => ""
+    }
+}
+
+/// Renders a token's value so it can be dropped inside a larger string.
+fn embeddable(name: &str, value: &Value) -> Result<String, StyleError> {
+    match *value {
+        Value::String(ref string) => Ok(string.clone()),
+        Value::Number(ref number) => Ok(number.to_string()),
+
+        // Color objects get turned into rgba(), so "darken($brand, 10%)" works when
+        // $brand is written as {"r": 35, "g": 108, "b": 218}.
+        Value::Object(ref map) if map.contains_key("r") && map.contains_key("g") && map.contains_key("b") => {
+            Ok(format!("rgba({}, {}, {}, {})", map["r"], map["g"], map["b"], map.get("a").cloned().unwrap_or(json!(1))))
+        },
+
+        _ => Err(StyleError::new(format!("token '${}' is {}, which can't be used inside a string", name, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(mut theme: Value) -> Result<Value, StyleError> {
+        resolve_tokens(&mut theme).map(|_| theme)
+    }
+
+    #[test]
+    fn resolves_references() {
+        let theme = resolved(json!({
+            "tokens": {"gap": 8, "brand": {"r": 35, "g": 108, "b": 218}, "accent": "$brand"},
+            "sidebar": {"width": "$gap", "backgroundColor": "darken($accent, 10%)"}
+        })).unwrap();
+
+        assert_eq!(theme, json!({
+            "sidebar": {"width": 8, "backgroundColor": "darken(rgba(35, 108, 218, 1), 10%)"}
+        }));
+    }
+
+    #[test]
+    fn leaves_dollars_that_arent_references_alone() {
+        let theme = resolved(json!({
+            "tokens": {"brand": "blue"},
+            "price": {"text": "$5"},
+            "total": {"text": "costs $ 5, or $10.50"},
+            "escaped": {"text": "$$brand is $brand"}
+        })).unwrap();
+
+        assert_eq!(theme["price"]["text"], "$5");
+        assert_eq!(theme["total"]["text"], "costs $ 5, or $10.50");
+        assert_eq!(theme["escaped"]["text"], "$brand is blue");
+    }
+
+    #[test]
+    fn rejects_unknown_tokens() {
+        let error = resolved(json!({"sidebar": {"width": "$missing"}})).unwrap_err();
+        assert_eq!(error.json_path(), "sidebar.width");
+    }
+}
@@ -0,0 +1,118 @@
+//  watch.rs
+//
+//  Development-time stylesheets: instead of baking JSON in with include_str!,
+//  point at files on disk and have them re-read whenever they change. There's
+//  no filesystem notification business here - `shinekit::run_watching` just
+//  polls modification times on a timer, which is plenty for a few small files.
+//
+//  @author Ryan McGrath <ryan@rymc.io>
+//  @created 06/10/2018
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use stylesheet::{StyleError, StyleSheet, StyleSource};
+
+struct Source {
+    theme: String,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+
+    /// Files pulled in through "@import", as of the last `load` - including any
+    /// that couldn't be read, so fixing them triggers a reload.
+    imports: Vec<(PathBuf, Option<SystemTime>)>
+}
+
+/// A set of stylesheet files, each tied to a theme. Themes build on each other
+/// through "extends", so the order they're added in doesn't matter.
+pub struct StyleWatcher {
+    sources: Vec<Source>
+}
+
+impl StyleWatcher {
+    pub fn new() -> Self {
+        StyleWatcher { sources: vec![] }
+    }
+
+    /// Watches a file for the "default" theme.
+    pub fn default_theme<P: AsRef<Path>>(self, path: P) -> Self {
+        self.theme("default", path)
+    }
+
+    pub fn theme<P: AsRef<Path>>(mut self, name: &str, path: P) -> Self {
+        self.sources.push(Source {
+            theme: name.into(),
+            path: path.as_ref().to_path_buf(),
+            modified: None,
+            imports: vec![]
+        });
+
+        self
+    }
+
+    /// Reads every file, noting when each was last modified so `has_changed`
+    /// has something to compare against.
+    pub fn load(&mut self) -> Vec<Result<StyleSource, StyleError>> {
+        self.sources.iter_mut().map(|source| {
+            source.modified = modified(&source.path);
+
+            let contents = fs::read_to_string(&source.path).map_err(|e| {
+                StyleError::new(format!("could not read {}: {}", source.path.display(), e)).in_theme(&source.theme)
+            })?;
+
+            let mut loaded = StyleSource::inline(StyleSheet::file(&source.theme, &source.path, &contents)?);
+            loaded.file = Some(source.path.display().to_string());
+            let mut imports = vec![];
+            let resolved = loaded.resolve_imports(None, &mut imports);
+            source.imports = imports.into_iter().map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            }).collect();
+
+            resolved.map(|_| loaded)
+        }).collect()
+    }
+
+    /// Whether any file (or anything it imports) has been touched, appeared or
+    /// vanished since the last `load`.
+    pub fn has_changed(&self) -> bool {
+        self.sources.iter().any(|source| {
+            modified(&source.path) != source.modified ||
+                source.imports.iter().any(|&(ref path, time)| modified(path) != time)
+        })
+    }
+}
+
+impl Default for StyleWatcher {
+    fn default() -> Self {
+        StyleWatcher::new()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn notices_missing_imports_appearing() {
+        let dir = env::temp_dir().join(format!("shinekit-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("default.json"), r#"{"@import": "colors.json", "sidebar": {"width": 200}}"#).unwrap();
+
+        let mut watcher = StyleWatcher::new().default_theme(dir.join("default.json"));
+        assert!(watcher.load()[0].is_err());
+        assert!(!watcher.has_changed());
+
+        fs::write(dir.join("colors.json"), r##"{"sidebar": {"backgroundColor": "#236cda"}}"##).unwrap();
+        assert!(watcher.has_changed());
+        assert!(watcher.load()[0].is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,23 +6,51 @@
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use serde_json::{Value};
-use cocoa::foundation::{NSRect, NSPoint, NSSize};
+use serde_json::{Map, Value};
 
-pub fn empty_frame() -> NSRect {
-    NSRect::new(NSPoint::new(0., 0.), NSSize::new(0., 0.))
+/// Deep-merges `b` into `a`. A null in `b` deletes that key from `a`, which is
+/// how a theme (or a more specific rule) unsets something it would inherit.
+pub fn merge_json_values(a: &mut Value, b: &Value) {
+    merge(a, b, true);
 }
 
-pub fn merge_json_values(a: &mut Value, b: &Value) {
+/// Like `merge_json_values`, but nulls in `b` are copied over rather than
+/// deleting anything. For stacking up pieces of one stylesheet (e.g, imports),
+/// where the nulls still need to be there to unset things once the whole sheet
+/// is merged over its theme.
+pub fn layer_json_values(a: &mut Value, b: &Value) {
+    merge(a, b, false);
+}
+
+fn merge(a: &mut Value, b: &Value, delete_nulls: bool) {
     match (a, b) {
-        (&mut Value::Object(ref mut a), &Value::Object(ref b)) => {
+        (Value::Object(a), Value::Object(b)) => {
             for (k, v) in b {
-                merge_json_values(a.entry(k.clone()).or_insert(Value::Null), v);
+                if v.is_null() && delete_nulls {
+                    a.shift_remove(k);
+                } else {
+                    merge(a.entry(k.clone()).or_insert(Value::Null), v, delete_nulls);
+                }
             }
         }
 
+        // Nothing to merge into; start empty so nested nulls are still handled
+        // the same way as everywhere else.
+        (a, Value::Object(_)) => {
+            *a = Value::Object(Map::new());
+            merge(a, b, delete_nulls);
+        }
+
         (a, b) => {
             *a = b.clone();
         }
     }
 }
+
+/// Compares two style objects, returning the properties in `b` that are new or
+/// different from `a`, along with the names of any properties `b` dropped.
+pub fn diff_json_objects(a: &Map<String, Value>, b: &Map<String, Value>) -> (Map<String, Value>, Vec<String>) {
+    let changed = b.iter().filter(|&(k, v)| a.get(k) != Some(v)).map(|(k, v)| (k.clone(), v.clone())).collect();
+    let removed = a.keys().filter(|k| !b.contains_key(*k)).cloned().collect();
+    (changed, removed)
+}
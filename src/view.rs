@@ -1,234 +1,659 @@
 //! view.rs
 //!
-//! "Fixes" NSView to be a bit more... how does one say, modern. Flips drawing
-//! and layout coordinates to be fitting for {{CURRENT YEAR}}, layer-backs it all
-//! by default, and does some ivar trickery to make NSColor less of a headache.
+//! The core building block. A View is a named node in a tree, backed by
+//! whatever the current platform backend hands us (a "fixed" NSView on Cocoa).
+//! Layout relationships are recorded by view name and handed to the backend
+//! when constraints are activated.
 //!
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use std::sync::{Once, ONCE_INIT};
+use std::collections::{HashMap, HashSet};
 use serde_json::{Map, Value};
 
-use objc_id::Id;
-use objc::declare::ClassDecl;
-use objc::runtime::{Class, Object, Sel, BOOL};
-use cocoa::foundation::NSArray;
-use cocoa::base::{class, id, nil, YES, NO};
-
+use animation::{AnimatedValue, Animation, Property, transition_for};
+use appearance::{APPEARANCE_PROPERTIES, Appearance};
+use backend::{Backend, Node, Platform};
 use color::Color;
-use util::empty_frame;
+use label::{TYPOGRAPHY_PROPERTIES, Typography};
+use layout::{Anchor, AnchorError, Attribute, Constraint, Display, EdgeInsets, Rect};
+use layout::flex::{FLEX_PROPERTIES, Flex};
+use layout::grid::{GRID_PROPERTIES, Grid};
+use stylesheet::{StyleError, optional_number, optional_str};
+use util::diff_json_objects;
+use stylesheet::selectors::{Element, Rules};
 
-#[derive(Debug)]
+/// The box model properties: a shorthand (one to four numbers, CSS order) and
+/// its per-edge longhands, which win over the shorthand.
+const BOX_PROPERTIES: [(&str, [&str; 4]); 2] = [
+    ("padding", ["paddingTop", "paddingLeft", "paddingBottom", "paddingRight"]),
+    ("margin", ["marginTop", "marginLeft", "marginBottom", "marginRight"])
+];
+
+/// Style properties that take an anchor expression (`"sidebar.right + 8"`),
+/// and the attribute on this view they end up constraining.
+const ANCHOR_PROPERTIES: [(&str, Attribute); 6] = [
+    ("top", Attribute::Top),
+    ("left", Attribute::Leading),
+    ("leading", Attribute::Leading),
+    ("right", Attribute::Trailing),
+    ("trailing", Attribute::Trailing),
+    ("bottom", Attribute::Bottom)
+];
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ViewKind {
     View,
-    Label
+    Label,
+    ScrollView,
+    ListView
+}
+
+impl ViewKind {
+    /// The name stylesheets use to select every view of this kind.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ViewKind::View => "View",
+            ViewKind::Label => "Label",
+            ViewKind::ScrollView => "ScrollView",
+            ViewKind::ListView => "ListView"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ViewKind> {
+        match name {
+            "View" => Some(ViewKind::View),
+            "Label" => Some(ViewKind::Label),
+            "ScrollView" => Some(ViewKind::ScrollView),
+            "ListView" => Some(ViewKind::ListView),
+            _ => None
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct View {
     pub kind: ViewKind,
     pub name: String,
-    pub backing_node: Id<Object>,
+    pub classes: Vec<String>,
+    pub backing_node: Node,
+    pub constraints: Vec<Constraint>,
+    pub background_color: Color,
+    pub typography: Typography,
+
+    /// A Label's text color, or None for the platform's default label color.
+    pub text_color: Option<Color>,
+
+    pub appearance: Appearance,
+
+    /// Insets children get when they pin themselves to this view's edges.
+    pub padding: EdgeInsets,
+
+    /// Space this view keeps from whatever its own edges are pinned to.
+    pub margin: EdgeInsets,
+
+    /// How this view lays out its children, and its flex and grid settings
+    /// (which count both when it's a container and when it's in one).
+    pub display: Display,
+    pub flex: Flex,
+    pub grid: Grid,
+
+    /// What `display` and `flex` go back to when a stylesheet stops setting them.
+    /// These are the defaults, unless code set something else (e.g, a stack's
+    /// direction and alignment).
+    pub base_display: Display,
+    pub base_flex: Flex,
+
+    /// Where this view's container put it, relative to the container's padding,
+    /// if the container lays out its children itself. Overrides constraints.
+    pub placement: Option<Rect>,
+
+    /// The computed style this view was last given, so `restyle` can tell what
+    /// changed since.
+    pub applied_style: Value,
+
     pub subviews: Vec<View>
 }
 
 impl View {
-    pub fn named_of_kind_with_backing_node(name: &str, kind: ViewKind, backing_node: Id<Object>) -> Self {
-        View { 
+    pub fn named_of_kind_with_backing_node(name: &str, kind: ViewKind, backing_node: Node) -> Self {
+        View {
             name: name.into(),
-            kind: kind,
-            backing_node: backing_node,
+            kind,
+            classes: vec![],
+            backing_node,
+            constraints: vec![],
+            background_color: Color::rgba(0, 0, 0, 0.),
+            typography: Typography::default(),
+            text_color: None,
+            appearance: Appearance::default(),
+            padding: EdgeInsets::default(),
+            margin: EdgeInsets::default(),
+            display: Display::default(),
+            flex: Flex::default(),
+            grid: Grid::default(),
+            base_display: Display::default(),
+            base_flex: Flex::default(),
+            placement: None,
+            applied_style: json!({}),
             subviews: vec![]
         }
     }
 
+    pub fn named_of_kind(name: &str, kind: ViewKind) -> Self {
+        let backing_node = Platform::create_node(&kind, name);
+        View::named_of_kind_with_backing_node(name, kind, backing_node)
+    }
+
     pub fn named(name: &str) -> Self {
-        View::named_of_kind_with_backing_node(name, ViewKind::View, unsafe {
-            let alloc: id = msg_send![register_view_class(), alloc];
-            let view: id = msg_send![alloc, initWithFrame:empty_frame()];
-            msg_send![view, setWantsLayer:YES];
-            msg_send![view, setLayerContentsRedrawPolicy:1];
-            msg_send![view, setTranslatesAutoresizingMaskIntoConstraints:NO];
-            Id::from_ptr(view)
-        })
+        View::named_of_kind(name, ViewKind::View)
     }
 
     pub fn subviews(self, views: Vec<View>) -> Self {
         let mut subviews = vec![];
         for view in views.into_iter() {
-            unsafe { msg_send![&*self.backing_node, addSubview:&*view.backing_node]; }
+            Platform::add_subview(&self.backing_node, &view.backing_node);
             subviews.push(view);
         }
-        
-        View { subviews: subviews, ..self }
+
+        View { subviews, ..self }
     }
 
-    pub fn set_background_color(&mut self, color: &Color) {
-        unsafe {
-            self.backing_node.set_ivar("shinekitBackgroundColor", color.into_platform_specific_color());
-            msg_send![&*self.backing_node, setNeedsDisplay:YES];
+    /// Tags this view with classes, which stylesheets can select with ".name".
+    pub fn classes(self, classes: &[&str]) -> Self {
+        View { classes: classes.iter().map(|class| class.to_string()).collect(), ..self }
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.classes.iter().any(|existing| existing == class)
+    }
+
+    /// Adds a class. Styles only change the next time styles are applied.
+    pub fn add_class(&mut self, class: &str) {
+        if !self.has_class(class) {
+            self.classes.push(class.into());
         }
     }
-    
-    pub fn get_root_backing_node(&self) -> &Object { &*self.backing_node }
+
+    pub fn remove_class(&mut self, class: &str) {
+        self.classes.retain(|existing| existing != class);
+    }
+
+    pub fn set_background_color(&mut self, color: &Color) {
+        Platform::set_background_color(&self.backing_node, color);
+        self.background_color = *color;
+    }
+
+    pub fn get_root_backing_node(&self) -> &Node { &self.backing_node }
     pub fn get_subviews(&self) -> &Vec<View> { &self.subviews }
-    pub fn set_ivar(&mut self, ivar: &str, constraint: id) {
-        unsafe {
-            self.backing_node.set_ivar(ivar, constraint);
+
+    /// Finds the view with the given name in this tree, this one included.
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut View> {
+        if self.name == name {
+            return Some(self);
         }
+
+        self.subviews.iter_mut().filter_map(|view| view.find_mut(name)).next()
     }
-    
+
     pub fn add_subview(&self, view: &View) {
-        unsafe {
-            msg_send![self.get_root_backing_node(), addSubview:view.get_root_backing_node()];
-        }
+        Platform::add_subview(&self.backing_node, &view.backing_node);
     }
-    
-    pub fn translates_resizing_mask_into_constraints(&self, translates: bool) {
-        let t: BOOL = if translates { YES } else { NO };
-        unsafe { 
-            msg_send![self.get_root_backing_node(), setTranslatesAutoresizingMaskIntoConstraints:t];
-        }
+
+    /// Records a constraint for this view, replacing any existing one for the
+    /// same attribute. Nothing happens on screen until constraints are activated.
+    pub fn set_constraint(&mut self, constraint: Constraint) {
+        self.constraints.retain(|existing| existing.attribute != constraint.attribute);
+        self.constraints.push(constraint);
+    }
+
+    /// Drops the constraint for an attribute. Unlike `set_constraint`, this takes
+    /// effect on screen right away.
+    pub fn remove_constraint(&mut self, attribute: Attribute) {
+        self.constraints.retain(|existing| existing.attribute != attribute);
+        Platform::deactivate_constraint(&self.backing_node, attribute);
     }
-  
+
     pub fn set_width(&mut self, width: f64) {
-        unsafe {
-            let anchor: id = msg_send![self.get_root_backing_node(), widthAnchor];
-            let constraint: id = msg_send![anchor, constraintEqualToConstant:width];
-            self.set_ivar("shinekitConstraintWidth", constraint);
-        }
+        self.set_constraint(Constraint::constant(Attribute::Width, width));
     }
 
     pub fn set_height(&mut self, height: f64) {
-        unsafe {
-            let anchor: id = msg_send![self.get_root_backing_node(), heightAnchor];
-            let constraint: id = msg_send![anchor, constraintEqualToConstant:height];
-            self.set_ivar("shinekitConstraintHeight", constraint);
+        self.set_constraint(Constraint::constant(Attribute::Height, height));
+    }
+
+    /// The fixed value set for an attribute (e.g, a width of 200), if any.
+    pub fn constant_for(&self, attribute: Attribute) -> Option<f64> {
+        self.constraints.iter().find(|constraint| constraint.attribute == attribute && constraint.target.is_none())
+            .map(|constraint| constraint.constant)
+    }
+
+    pub fn set_padding(&mut self, padding: EdgeInsets) {
+        self.padding = padding;
+    }
+
+    pub fn set_margin(&mut self, margin: EdgeInsets) {
+        self.margin = margin;
+    }
+
+    /// The constraints as they should actually be installed, with margins and
+    /// padding folded into the constants. A relative constraint is pushed out by
+    /// this view's margin on that edge, and if it pins an edge to the same edge of
+    /// the parent, in by the parent's padding too.
+    ///
+    /// A view its parent has placed (see `placement`) gets pinned there instead.
+    pub fn resolved_constraints(&self, parent: Option<&View>) -> Vec<Constraint> {
+        if let (Some(placement), Some(parent)) = (self.placement, parent) {
+            return vec![
+                Constraint::relative(Attribute::Leading, &parent.name, Attribute::Leading, parent.padding.left + placement.x),
+                Constraint::relative(Attribute::Top, &parent.name, Attribute::Top, parent.padding.top + placement.y),
+                Constraint::constant(Attribute::Width, placement.width),
+                Constraint::constant(Attribute::Height, placement.height)
+            ];
         }
+
+        self.constraints.iter().map(|constraint| {
+            let mut constraint = constraint.clone();
+            let inset = match constraint.target {
+                Some(ref anchor) => {
+                    let padding = match parent {
+                        Some(parent) if parent.name == anchor.view && anchor.attribute == constraint.attribute => {
+                            parent.padding.offset_for(constraint.attribute)
+                        },
+                        _ => 0.
+                    };
+
+                    self.margin.offset_for(constraint.attribute) + padding
+                },
+
+                None => 0.
+            };
+
+            constraint.constant += inset;
+            constraint
+        }).collect()
     }
-    
+
     pub fn top_relative_to(&mut self, view: &View, margin: i32) {
-        unsafe {
-            let top_anchor: id = msg_send![self.get_root_backing_node(), topAnchor];
-            let view_top_anchor: id = msg_send![view.get_root_backing_node(), topAnchor];
-            let constraint: id = msg_send![top_anchor, constraintEqualToAnchor:view_top_anchor constant:margin as f64];
-            self.set_ivar("shinekitConstraintTop", constraint);
-        }
+        self.set_constraint(Constraint::relative(Attribute::Top, &view.name, Attribute::Top, margin as f64));
     }
-   
+
     pub fn leading_relative_to(&mut self, view: &View, margin: i32) {
-        unsafe {
-            let leading_anchor: id = msg_send![self.get_root_backing_node(), leadingAnchor];
-            let view_leading_anchor: id = msg_send![view.get_root_backing_node(), leadingAnchor];
-            let constraint: id = msg_send![leading_anchor, constraintEqualToAnchor:view_leading_anchor constant:margin as f64];
-            self.set_ivar("shinekitConstraintLeading", constraint);
-        }
+        self.set_constraint(Constraint::relative(Attribute::Leading, &view.name, Attribute::Leading, margin as f64));
     }
 
     pub fn trailing_relative_to(&mut self, view: &View, margin: i32) {
-        let m = margin as f64 * -1.;
-        unsafe {
-            let trailing_anchor: id = msg_send![self.get_root_backing_node(), trailingAnchor];
-            let view_trailing_anchor: id = msg_send![view.get_root_backing_node(), trailingAnchor];
-            let constraint: id = msg_send![trailing_anchor, constraintEqualToAnchor:view_trailing_anchor constant:m];
-            self.set_ivar("shinekitConstraintTrailing", constraint);
-        }
+        let m = -(margin as f64);
+        self.set_constraint(Constraint::relative(Attribute::Trailing, &view.name, Attribute::Trailing, m));
     }
 
     pub fn bottom_relative_to(&mut self, view: &View, margin: i32) {
-        let m = margin as f64 * -1.;
-        unsafe {
-            let bottom_anchor: id = msg_send![self.get_root_backing_node(), bottomAnchor];
-            let view_bottom_anchor: id = msg_send![view.get_root_backing_node(), bottomAnchor];
-            let constraint: id = msg_send![bottom_anchor, constraintEqualToAnchor:view_bottom_anchor constant:m];
-            self.set_ivar("shinekitConstraintBottom", constraint);
-        }
+        let m = -(margin as f64);
+        self.set_constraint(Constraint::relative(Attribute::Bottom, &view.name, Attribute::Bottom, m));
     }
-    
+
+    /// Activates the recorded constraints for this view and everything below it.
+    /// Relative constraints are resolved by name against this subtree.
     pub fn activate_constraints(&self) {
-        let mut constraints: Vec<id> = vec![];
-        
-        let ivars = [
-            "shinekitConstraintWidth", "shinekitConstraintHeight",
-            "shinekitConstraintTop", "shinekitConstraintLeading",
-            "shinekitConstraintTrailing", "shinekitConstraintBottom"
-        ];
- 
-        for ivar in &ivars {
-            let constraint: id;
-            unsafe { constraint = *self.backing_node.get_ivar(ivar); }
-
-            if constraint != nil {
-                constraints.push(constraint);
-            }
+        let mut nodes = HashMap::new();
+        self.collect_nodes(&mut nodes);
+        self.activate_constraints_with(&nodes, None);
+    }
+
+    fn collect_nodes<'a>(&'a self, nodes: &mut HashMap<&'a str, &'a Node>) {
+        nodes.insert(&self.name, &self.backing_node);
+        for view in &self.subviews {
+            view.collect_nodes(nodes);
+        }
+    }
+
+    fn activate_constraints_with(&self, nodes: &HashMap<&str, &Node>, parent: Option<&View>) {
+        let resolved = self.resolved_constraints(parent);
+        let constraints: Vec<(&Constraint, Option<&Node>)> = resolved.iter().map(|constraint| {
+            let target = constraint.target.as_ref().and_then(|anchor| nodes.get(anchor.view.as_str()).cloned());
+            (constraint, target)
+        }).collect();
+
+        Platform::activate_constraints(&self.backing_node, &constraints);
+
+        for view in &self.subviews {
+            view.activate_constraints_with(nodes, Some(self));
+        }
+    }
+
+    /// Applies styles to this view and everything below it. Each view gets every
+    /// rule whose selector matches it, merged in order of specificity (see
+    /// `stylesheet::selectors`). Anchor expressions are resolved against the names
+    /// in this tree, so this is generally called on the root view. Views that no
+    /// rule matches are left alone.
+    pub fn apply_styles(&mut self, styles: &Map<String, Value>) -> Result<(), StyleError> {
+        let mut names = HashSet::new();
+        self.collect_names(&mut names);
+        let rules = Rules::new(styles);
+        self.apply_styles_with(&rules, &names, &mut vec![])?;
+        self.check_grid_areas()
+    }
+
+    fn collect_names(&self, names: &mut HashSet<String>) {
+        names.insert(self.name.clone());
+        for view in &self.subviews {
+            view.collect_names(names);
+        }
+    }
+
+    /// The elements from this view down to the one with the given name, which is
+    /// what selectors get matched against.
+    pub fn element_path(&self, name: &str) -> Option<Vec<Element>> {
+        if self.name == name {
+            return Some(vec![self.element()]);
         }
-        
-        if constraints.len() > 0 {
-            unsafe {
-                let bundle = NSArray::arrayWithObjects(nil, &constraints);
-                msg_send![class("NSLayoutConstraint"), activateConstraints:bundle];
+
+        self.subviews.iter().filter_map(|view| view.element_path(name)).next().map(|mut path| {
+            path.insert(0, self.element());
+            path
+        })
+    }
+
+    /// The element path to every view in this tree, this one included.
+    pub fn element_paths(&self) -> Vec<Vec<Element>> {
+        let mut paths = vec![vec![self.element()]];
+        for view in &self.subviews {
+            for mut path in view.element_paths() {
+                path.insert(0, self.element());
+                paths.push(path);
             }
         }
+
+        paths
+    }
+
+    fn element(&self) -> Element {
+        Element {
+            name: self.name.clone(),
+            kind: self.kind.clone(),
+            classes: self.classes.clone()
+        }
+    }
+
+    fn apply_styles_with(&mut self, rules: &Rules, names: &HashSet<String>, path: &mut Vec<Element>) -> Result<(), StyleError> {
+        path.push(self.element());
+
+        if let Some((style, origins)) = rules.computed_style(path) {
+            // Point errors at the rule the bad property actually came from.
+            self.apply_style(&style, names).map_err(|e| {
+                let rule = e.path.first().and_then(|property| origins.get(property)).cloned();
+                e.at(&rule.unwrap_or_else(|| self.name.clone()))
+            })?;
+            self.applied_style = style;
+        }
+
+        for view in &mut self.subviews {
+            view.apply_styles_with(rules, names, path)?;
+        }
+
+        path.pop();
+        Ok(())
+    }
+
+    /// Moves this tree from one set of styles to another, touching only the
+    /// properties whose computed values actually differ. Properties that went away
+    /// entirely have their constraints dropped, typography, text color and appearance
+    /// go back to their defaults, and a removed backgroundColor goes back to
+    /// transparent. Used for hot reloading and breakpoints.
+    ///
+    /// Properties the new style has a `transition` for aren't set here. They come
+    /// back as animations instead, for the caller to run.
+    pub fn apply_changed_styles(&mut self, old: &Map<String, Value>, new: &Map<String, Value>) -> Result<Vec<Animation>, StyleError> {
+        let mut names = HashSet::new();
+        self.collect_names(&mut names);
+        let (old_rules, new_rules) = (Rules::new(old), Rules::new(new));
+        let mut animations = vec![];
+        self.apply_changed_styles_with(Some(&old_rules), &new_rules, &names, &mut vec![], &mut animations)?;
+        self.check_grid_areas()?;
+        Ok(animations)
+    }
+
+    /// Like `apply_changed_styles`, but diffs each view against the style it was
+    /// last given rather than against an old set of styles. That way views added
+    /// since get styled from scratch, and views whose classes changed lose what
+    /// no longer matches them.
+    pub fn restyle(&mut self, styles: &Map<String, Value>) -> Result<Vec<Animation>, StyleError> {
+        let mut names = HashSet::new();
+        self.collect_names(&mut names);
+        let rules = Rules::new(styles);
+        let mut animations = vec![];
+        self.apply_changed_styles_with(None, &rules, &names, &mut vec![], &mut animations)?;
+        self.check_grid_areas()?;
+        Ok(animations)
     }
 
-    pub fn apply_styles(&mut self, styles: &mut Map<String, Value>) {
-        let bg_color = Color::from_json(&styles[&self.name]["backgroundColor"]);
-        self.set_background_color(&bg_color);
+    fn apply_changed_styles_with(&mut self, old_rules: Option<&Rules>, new_rules: &Rules, names: &HashSet<String>, path: &mut Vec<Element>, animations: &mut Vec<Animation>) -> Result<(), StyleError> {
+        path.push(self.element());
+
+        let empty = (json!({}), HashMap::new());
+        let old = match old_rules {
+            Some(old_rules) => old_rules.computed_style(path).unwrap_or_else(|| empty.clone()).0,
+            None => self.applied_style.clone()
+        };
+        let (new, origins) = new_rules.computed_style(path).unwrap_or(empty);
+        let name = self.name.clone();
+        let in_rule = |e: StyleError| {
+            let rule = e.path.first().and_then(|property| origins.get(property)).cloned();
+            e.at(&rule.unwrap_or_else(|| name.clone()))
+        };
+
+        let (mut changed, removed) = match (old.as_object(), new.as_object()) {
+            (Some(old), Some(new)) => diff_json_objects(old, new),
+            _ => (new.as_object().cloned().unwrap_or_default(), vec![])
+        };
+
+        // Only properties that had a value to move from get eased into the new one.
+        if let Some(transition) = new.get("transition") {
+            let properties: Vec<String> = changed.keys().filter(|property| old.get(property.as_str()).is_some()).cloned().collect();
+            for property in properties {
+                let animated = match Property::from_name(&property) {
+                    Some(animated) => animated,
+                    None => { continue; }
+                };
+
+                if !animated.can_transition(&old[&property], &changed[&property]) {
+                    continue;
+                }
+
+                let timing = transition_for(transition, &property).map_err(|e| in_rule(e.at("transition")))?;
+                if let (Some(timing), Ok(to)) = (timing, AnimatedValue::from_json(animated, &changed[&property])) {
+                    animations.push(Animation::new(&self.name, animated, to, timing));
+                    changed.shift_remove(&property);
+                }
+            }
+        }
+
+        let box_changed = changed.keys().any(|property| is_box_property(property));
+        if !new.is_object() || !changed.is_empty() {
+            let style = if new.is_object() { Value::Object(changed) } else { new.clone() };
+            self.apply_style(&style, names).map_err(in_rule)?;
+        }
 
-        match styles[&self.name]["width"].as_f64() {
-            Some(width) => self.set_width(width),
-            None => ()
+        // A changed shorthand would otherwise trample unchanged longhands, so work
+        // padding and margin out again from the whole style.
+        if box_changed {
+            self.apply_box_style(&new, true)?;
         }
-        
-        match styles[&self.name]["height"].as_f64() {
-            Some(width) => self.set_width(width),
-            None => ()
+
+        for property in &removed {
+            self.unset_style(property, &new);
         }
-        
+
+        self.applied_style = new;
+
         for view in &mut self.subviews {
-            view.apply_styles(styles);
+            view.apply_changed_styles_with(old_rules, new_rules, names, path, animations)?;
         }
+
+        path.pop();
+        Ok(())
     }
-}
 
-extern fn enforce_normalcy(_: &Object, _: Sel) -> BOOL {
-    return YES;
-}
+    fn unset_style(&mut self, property: &str, style: &Value) {
+        let attribute = match property {
+            "backgroundColor" => {
+                self.set_background_color(&Color::rgba(0, 0, 0, 0.));
+                return;
+            },
+
+            "textColor" => {
+                if self.kind == ViewKind::Label {
+                    self.reset_text_color();
+                }
+
+                return;
+            },
+
+            property if is_box_property(property) => {
+                // Whatever's left in the new style decides; anything unset is 0.
+                let _ = self.apply_box_style(style, true);
+                return;
+            },
+
+            "display" => {
+                self.display = self.base_display;
+                return;
+            },
 
-extern fn update_layer(this: &Object, _: Sel) {
-    unsafe {
-        let background_color: id = *this.get_ivar("shinekitBackgroundColor");
-        if background_color != nil {
-            let layer: id = msg_send![this, layer];
-            let cg: id = msg_send![background_color, CGColor];
-            msg_send![layer, setBackgroundColor:cg];
+            property if FLEX_PROPERTIES.contains(&property) => {
+                let base = self.base_flex.clone();
+                self.flex.reset(property, &base);
+                return;
+            },
+
+            property if GRID_PROPERTIES.contains(&property) => {
+                self.grid.reset(property);
+                return;
+            },
+
+            property if APPEARANCE_PROPERTIES.contains(&property) => {
+                let mut appearance = self.appearance.clone();
+                appearance.reset(property);
+                self.set_appearance(appearance);
+                return;
+            },
+
+            property if TYPOGRAPHY_PROPERTIES.contains(&property) => {
+                if self.kind == ViewKind::Label {
+                    let mut typography = self.typography.clone();
+                    typography.reset(property);
+                    self.set_typography(typography);
+                }
+
+                return;
+            },
+
+            "width" => Attribute::Width,
+            "height" => Attribute::Height,
+            _ => match ANCHOR_PROPERTIES.iter().find(|&&(name, _)| name == property) {
+                Some(&(_, attribute)) => attribute,
+                None => { return; }
+            }
+        };
+
+        // "left" and "leading" (etc) share an attribute; keep it if the other is still set.
+        let still_set = ANCHOR_PROPERTIES.iter().any(|&(name, other)| {
+            other == attribute && name != property && style.get(name).is_some()
+        });
+
+        if !still_set {
+            self.remove_constraint(attribute);
         }
     }
-}
 
-fn register_view_class() -> *const Class {
-    static mut view_class: *const Class = 0 as *const Class;
-    static INIT: Once = ONCE_INIT;
-
-    INIT.call_once(|| unsafe {
-        let superclass = Class::get("NSView").unwrap();
-        let mut decl = ClassDecl::new("shinekitView", superclass).unwrap();
-        decl.add_method(sel!(isFlipped), enforce_normalcy as extern fn(&Object, _) -> BOOL);
-        decl.add_method(sel!(requiresConstraintBasedLayout), enforce_normalcy as extern fn(&Object, _) -> BOOL);
-        decl.add_method(sel!(wantsUpdateLayer), enforce_normalcy as extern fn(&Object, _) -> BOOL);
-        decl.add_method(sel!(updateLayer), update_layer as extern fn(&Object, _));
-        decl.add_ivar::<id>("shinekitBackgroundColor");
-        decl.add_ivar::<id>("shinekitConstraintWidth");
-        decl.add_ivar::<id>("shinekitConstraintHeight");
-        decl.add_ivar::<id>("shinekitConstraintTop");
-        decl.add_ivar::<id>("shinekitConstraintLeading");
-        decl.add_ivar::<id>("shinekitConstraintTrailing");
-        decl.add_ivar::<id>("shinekitConstraintBottom");
-        view_class = decl.register();
-    });
-
-    unsafe {
-        view_class
+    /// Reads padding and margin out of a style. With `reset`, edges the style
+    /// doesn't mention go back to 0 rather than keeping their current value.
+    fn apply_box_style(&mut self, style: &Value, reset: bool) -> Result<(), StyleError> {
+        for &(shorthand, edges) in &BOX_PROPERTIES {
+            let mut insets = match (reset, shorthand) {
+                (true, _) => EdgeInsets::default(),
+                (false, "padding") => self.padding,
+                (false, _) => self.margin
+            };
+
+            if let Some(value) = style.get(shorthand).filter(|value| !value.is_null()) {
+                insets = insets_from_json(value).map_err(|e| e.at(shorthand))?;
+            }
+
+            let [top, left, bottom, right] = edges;
+            insets.top = optional_number(style, top)?.unwrap_or(insets.top);
+            insets.left = optional_number(style, left)?.unwrap_or(insets.left);
+            insets.bottom = optional_number(style, bottom)?.unwrap_or(insets.bottom);
+            insets.right = optional_number(style, right)?.unwrap_or(insets.right);
+
+            if shorthand == "padding" {
+                self.set_padding(insets);
+            } else {
+                self.set_margin(insets);
+            }
+        }
+
+        Ok(())
     }
+
+    fn apply_style(&mut self, style: &Value, names: &HashSet<String>) -> Result<(), StyleError> {
+        if !style.is_object() {
+            return Err(StyleError::new(format!("expected an object of style properties, found {}", style)));
+        }
+
+        if let Some(color) = style.get("backgroundColor") {
+            let bg_color = Color::from_json(color).map_err(|e| e.at("backgroundColor"))?;
+            self.set_background_color(&bg_color);
+        }
+
+        if let Some(width) = optional_number(style, "width")? {
+            self.set_width(width);
+        }
+
+        if let Some(height) = optional_number(style, "height")? {
+            self.set_height(height);
+        }
+
+        self.apply_box_style(style, false)?;
+
+        if let Some(display) = optional_str(style, "display")? {
+            self.display = Display::parse(display).map_err(|e| e.at("display"))?;
+        }
+
+        self.apply_flex_style(style)?;
+        self.apply_grid_style(style)?;
+
+        for &(property, attribute) in &ANCHOR_PROPERTIES {
+            if let Some(expression) = optional_str(style, property)? {
+                let (anchor, offset) = Anchor::parse(expression).map_err(|e| StyleError::new(e.to_string()).at(property))?;
+                if !names.contains(&anchor.view) {
+                    return Err(StyleError::new(AnchorError::UnknownView(anchor.view).to_string()).at(property));
+                }
+
+                self.set_constraint(Constraint::relative(attribute, &anchor.view, anchor.attribute, offset));
+            }
+        }
+
+        self.apply_appearance_style(style)?;
+        self.apply_text_style(style)
+    }
+}
+
+fn is_box_property(property: &str) -> bool {
+    BOX_PROPERTIES.iter().any(|&(shorthand, edges)| shorthand == property || edges.contains(&property))
+}
+
+/// Box shorthands can be a number, an array of numbers, or a string of them
+/// ("8 16"), same as CSS.
+pub fn insets_from_json(value: &Value) -> Result<EdgeInsets, StyleError> {
+    let values: Option<Vec<f64>> = match *value {
+        Value::Number(ref number) => number.as_f64().map(|number| vec![number]),
+        Value::Array(ref values) => values.iter().map(|value| value.as_f64()).collect(),
+        Value::String(ref values) => values.split_whitespace().map(|value| value.parse().ok()).collect(),
+        _ => None
+    };
+
+    values.as_ref().and_then(|values| EdgeInsets::from_values(values)).ok_or_else(|| {
+        StyleError::new(format!("expected one to four numbers, found {}", value))
+    })
 }
@@ -7,68 +7,41 @@
 //! @author Ryan McGrath <ryan@rymc.io>
 //! @created 05/30/2018
 
-use cocoa::base::{id, nil, YES, NO};
-use cocoa::appkit::{NSWindow, NSWindowStyleMask, NSBackingStoreType};
-use cocoa::foundation::{NSRect, NSPoint, NSSize, NSString, NSAutoreleasePool};
-
+use backend::{Backend, Platform, WindowHandle};
 use color::Color;
+use layout::Rect;
 use view::View;
 
 pub struct Window {
-    pub window: id,
-    pub content_view: View
+    pub window: WindowHandle,
+    pub content_view: View,
+
+    /// Where the window is right now, as far as we know. Kept up to date by
+    /// `set_frame`, and by the app when the user resizes the window.
+    pub frame: Rect
 }
 
 impl Window {
     pub fn new(view: View, title: &str, top: i32, left: i32, width: i32, height: i32) -> Self {
-        Window {
-            window: unsafe {
-                let style = NSWindowStyleMask::NSResizableWindowMask |
-                    NSWindowStyleMask::NSUnifiedTitleAndToolbarWindowMask | NSWindowStyleMask::NSMiniaturizableWindowMask |
-                    NSWindowStyleMask::NSClosableWindowMask | NSWindowStyleMask::NSTitledWindowMask;
-                    //NSWindowStyleMask::NSWindowStyleMaskResizable | NSWindowStyleMask::NSWindowStyleMaskMiniaturizable | NSWindowStyleMask::NSWindowStyleMaskClosable | NSWindowStyleMask::NSWindowStyleMaskTitled | NSWindowStyleMask::NSWindowStyleMaskUnifiedTitleAndToolbar
+        let frame = Rect::new(top.into(), left.into(), width.into(), height.into());
 
-                let window = NSWindow::alloc(nil).initWithContentRect_styleMask_backing_defer_(
-                    NSRect::new(NSPoint::new(top.into(), left.into()), NSSize::new(width.into(), height.into())),
-                    style,
-                    NSBackingStoreType::NSBackingStoreBuffered,
-                    NO
-	            ).autorelease();
-            
-                let title = NSString::alloc(nil).init_str(title);
-                window.setTitle_(title);
-                view.translates_resizing_mask_into_constraints(true);
-                msg_send![window, setContentView:view.get_root_backing_node()];
-                msg_send![window, setTitlebarAppearsTransparent:YES];
-                msg_send![window, setTitleVisibility:1];
-                window
-            },
+        Window {
+            window: Platform::create_window(title, &frame, view.get_root_backing_node()),
             content_view: view,
+            frame
         }
     }
-    
+
     pub fn make_key(&self) {
-        unsafe {
-            self.window.makeKeyAndOrderFront_(nil);
-        }
+        Platform::make_key_window(&self.window);
     }
 
-    /*pub fn set_content_view(&mut self, view: &T) {
-        unsafe {
-            view.translates_resizing_mask_into_constraints(true);
-            msg_send![self.window, setContentView:&*view.get_root_backing_node()];
-        }
-    }*/
-
-    pub fn set_frame(&self, rect: NSRect) {
-        unsafe {
-            msg_send![self.window, setFrame:rect display:YES];
-        }
+    pub fn set_frame(&mut self, rect: Rect) {
+        Platform::set_window_frame(&self.window, &rect);
+        self.frame = rect;
     }
 
     pub fn set_background_color(&self, color: Color) {
-        unsafe {
-            msg_send![self.window, setBackgroundColor:color.into_platform_specific_color()];
-        }
+        Platform::set_window_background_color(&self.window, &color);
     }
 }